@@ -0,0 +1,85 @@
+use std::{collections::HashMap, hint::black_box};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use make_class_rs::address_parser::{AddressParser, AddressResolver, CompiledExpr};
+
+const BASE: usize = 0x7f00_0000_0000;
+const MODULES: usize = 200;
+
+const EXPRESSIONS: [(&str, &str); 3] = [
+    ("pointer_chain", "[[game.exe + 0x100] + 0x18] + 0x30"),
+    (
+        "constant_offsets",
+        "game.exe + 0x1000 * 2 + (0x40 << 4) - 8 + 0x10 * 3",
+    ),
+    (
+        "statements",
+        "let base = [game.exe + 0x100]; base > 0 ? [base + 0x18] : game.exe",
+    ),
+];
+
+/// Module list searched by name like real backends, memory read as address + 8
+struct FakeProcess {
+    modules: Vec<(String, usize)>,
+    generation: Option<u64>,
+}
+
+impl FakeProcess {
+    fn new(generation: Option<u64>) -> Self {
+        let mut modules = (0..MODULES)
+            .map(|i| (format!("lib{i}.so"), BASE + (i + 1) * 0x100_0000))
+            .collect::<Vec<_>>();
+        modules.push(("game.exe".into(), BASE));
+        Self {
+            modules,
+            generation,
+        }
+    }
+}
+
+impl AddressResolver for FakeProcess {
+    fn module_symbol_to_address(&self, module_name: &str) -> Option<isize> {
+        self.modules
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(module_name))
+            .map(|(_, base)| *base as isize)
+    }
+
+    fn dereference(&self, address: usize) -> Option<isize> {
+        Some(address as isize + 8)
+    }
+
+    fn generation(&self) -> Option<u64> {
+        self.generation
+    }
+}
+
+fn bench_address_eval(c: &mut Criterion) {
+    let cached = FakeProcess::new(Some(0));
+    let uncached = FakeProcess::new(None);
+
+    let mut group = c.benchmark_group("address_eval");
+    for (name, src) in EXPRESSIONS {
+        let compiled = CompiledExpr::compile(src).unwrap();
+        // what every evaluation cost before, parse text then evaluate
+        group.bench_function(format!("{name}/parse_each_time"), |b| {
+            b.iter(|| {
+                AddressParser::new(black_box(src))
+                    .parse()
+                    .unwrap()
+                    .eval(&mut HashMap::new(), &uncached)
+                    .unwrap()
+            })
+        });
+        group.bench_function(format!("{name}/compiled"), |b| {
+            b.iter(|| compiled.eval(&mut HashMap::new(), &uncached).unwrap())
+        });
+        group.bench_function(format!("{name}/compiled_cached_lookup"), |b| {
+            b.iter(|| compiled.eval(&mut HashMap::new(), &cached).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_address_eval);
+criterion_main!(benches);
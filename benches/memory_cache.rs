@@ -0,0 +1,90 @@
+use std::{
+    hint::black_box,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use make_class_rs::{
+    address_parser::AddressResolver,
+    memory::{MemoryReaderWriter, MemoryState, cache::MemoryCache},
+};
+
+const BASE: usize = 0x7f00_0000_0000;
+const CLASS_SIZE: usize = 0x1000;
+const FIELD_SIZE: usize = 8;
+
+/// Backend that only count how many reads reach it, clones share the counter
+#[derive(Default, Clone)]
+struct CountingMemory {
+    reads: Arc<AtomicUsize>,
+}
+
+impl MemoryReaderWriter for CountingMemory {
+    fn read_buf(&self, addr: usize, buffer: &mut [u8]) -> usize {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+        for (i, b) in buffer.iter_mut().enumerate() {
+            *b = addr.wrapping_add(i) as u8;
+        }
+        buffer.len()
+    }
+
+    fn write_buf(&self, _addr: usize, _buffer: &[u8]) -> eyre::Result<()> {
+        Ok(())
+    }
+
+    fn can_read(&self, _addr: usize) -> bool {
+        true
+    }
+}
+
+impl AddressResolver for CountingMemory {
+    fn module_symbol_to_address(&self, _module_name: &str) -> Option<isize> {
+        None
+    }
+
+    fn dereference(&self, _address: usize) -> Option<isize> {
+        None
+    }
+}
+
+impl MemoryState for CountingMemory {}
+
+/// One inspector frame, every field read its own bytes
+fn draw_frame(memory: &dyn MemoryState) {
+    memory.invalidate_cache();
+    memory.prefetch(BASE, CLASS_SIZE);
+    let mut buf = [0u8; FIELD_SIZE];
+    for offset in (0..CLASS_SIZE).step_by(FIELD_SIZE) {
+        memory.read_buf(BASE + offset, &mut buf);
+        black_box(&buf);
+    }
+}
+
+fn reads_per_frame(memory: &dyn MemoryState, counter: &AtomicUsize) -> usize {
+    let before = counter.load(Ordering::Relaxed);
+    draw_frame(memory);
+    counter.load(Ordering::Relaxed) - before
+}
+
+fn bench_memory_cache(c: &mut Criterion) {
+    let direct = CountingMemory::default();
+    let behind_cache = CountingMemory::default();
+    let cached = MemoryCache::new(Box::new(behind_cache.clone()));
+
+    println!(
+        "backend reads per frame: direct {}, cached {}",
+        reads_per_frame(&direct, &direct.reads),
+        reads_per_frame(&cached, &behind_cache.reads),
+    );
+
+    let mut group = c.benchmark_group("inspector_frame");
+    group.bench_function("direct", |b| b.iter(|| draw_frame(&direct)));
+    group.bench_function("cached", |b| b.iter(|| draw_frame(&cached)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_memory_cache);
+criterion_main!(benches);
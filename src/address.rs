@@ -1,17 +1,28 @@
-use std::collections::HashMap;
+use std::time::Instant;
 
-use crate::{address_parser::AddressParser, global_state::global_state};
+use crate::{
+    address_parser::CompiledExpr, class::resolver::ProjectResolver, global_state::global_state,
+};
 
 pub struct AddressString {
     addr_str: String,
+    /// `addr_str` compiled on first evaluation, dropped when text change
+    compiled: Option<CompiledExpr>,
     value: Option<usize>,
+    /// from last `reevaluate`
+    error: Option<String>,
+    /// last time `reevaluate` got a different value
+    changed_at: Option<Instant>,
 }
 
 impl From<usize> for AddressString {
     fn from(value: usize) -> Self {
         Self {
             addr_str: format!("{value:#X}"),
+            compiled: None,
             value: Some(value),
+            error: None,
+            changed_at: None,
         }
     }
 }
@@ -30,21 +41,67 @@ impl std::fmt::Display for AddressString {
 
 impl AddressString {
     pub fn load_str(addr: &str) -> eyre::Result<Self> {
-        let value = parse_address_str(addr)?;
+        let compiled = CompiledExpr::compile(addr)?;
+        let value = eval_address(&compiled)?;
         Ok(Self {
             addr_str: addr.to_string(),
+            compiled: Some(compiled),
             value: Some(value),
+            error: None,
+            changed_at: None,
         })
     }
 
     pub fn address_value(&self) -> usize {
         self.value.unwrap_or_default()
     }
+
+    /// Evaluate the expression again against current memory backend
+    ///
+    /// On error the value reset to 0, old value point to memory we no longer trust
+    pub fn reevaluate(&mut self) -> eyre::Result<()> {
+        let value = self.evaluate();
+        let new = value.as_ref().ok().copied();
+        if new.is_some() && new != self.value {
+            self.changed_at = Some(Instant::now());
+        }
+        self.value = new;
+        self.error = value.as_ref().err().map(|e| format!("{e}"));
+        value.map(|_| ())
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub fn changed_at(&self) -> Option<Instant> {
+        self.changed_at
+    }
+
+    /// Replace expression text keeping the value, for rename
+    pub fn rewrite(&mut self, addr: String) {
+        self.addr_str = addr;
+        self.compiled = None;
+    }
+
+    fn evaluate(&mut self) -> eyre::Result<usize> {
+        let compiled = match self.compiled.take() {
+            Some(compiled) => compiled,
+            None => CompiledExpr::compile(&self.addr_str)?,
+        };
+        let value = eval_address(&compiled);
+        self.compiled = Some(compiled);
+        value
+    }
 }
 
-fn parse_address_str(addr: &str) -> eyre::Result<usize> {
-    let eval = AddressParser::new(addr).parse()?;
-    let mut env = HashMap::new();
-    let v = eval.eval(&mut env, &*global_state().memory)?;
+fn eval_address(compiled: &CompiledExpr) -> eyre::Result<usize> {
+    // assignments stay local to this expression
+    let mut env = global_state().variables.env();
+    let state = global_state();
+    let v = compiled.eval(
+        &mut env,
+        &ProjectResolver::new(&state.class_list, &*state.memory),
+    )?;
     Ok(v as _)
 }
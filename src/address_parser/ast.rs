@@ -1,74 +1,122 @@
 use std::collections::HashMap;
 
-use eyre::ContextCompat;
+use super::{AddressParserResult, AddressResolver, CompiledExpr, FieldPath, Signature};
 
-use super::{AddressParserResult, AddressResolver};
+/// Value type of `u32[expr]` like dereference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DerefType {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    Ptr32,
+    Ptr64,
+}
+
+impl DerefType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "u8" => Self::U8,
+            "u16" => Self::U16,
+            "u32" => Self::U32,
+            "u64" => Self::U64,
+            "i8" => Self::I8,
+            "i16" => Self::I16,
+            "i32" => Self::I32,
+            "i64" => Self::I64,
+            "ptr32" => Self::Ptr32,
+            "ptr64" => Self::Ptr64,
+            _ => return None,
+        })
+    }
+
+    pub fn size(self) -> usize {
+        match self {
+            Self::U8 | Self::I8 => 1,
+            Self::U16 | Self::I16 => 2,
+            Self::U32 | Self::I32 | Self::Ptr32 => 4,
+            Self::U64 | Self::I64 | Self::Ptr64 => 8,
+        }
+    }
+
+    /// Read through resolver, signed types are sign extended
+    pub fn read(self, resolver: &dyn AddressResolver, address: usize) -> Option<isize> {
+        let mut buf = [0u8; 8];
+        let buf = &mut buf[..self.size()];
+        if !resolver.read_exact(address, buf) {
+            return None;
+        }
+        Some(match self {
+            Self::U8 => buf[0] as isize,
+            Self::U16 => u16::from_ne_bytes(buf.try_into().ok()?) as isize,
+            Self::U32 | Self::Ptr32 => u32::from_ne_bytes(buf.try_into().ok()?) as isize,
+            Self::U64 | Self::Ptr64 => u64::from_ne_bytes(buf.try_into().ok()?) as isize,
+            Self::I8 => buf[0] as i8 as isize,
+            Self::I16 => i16::from_ne_bytes(buf.try_into().ok()?) as isize,
+            Self::I32 => i32::from_ne_bytes(buf.try_into().ok()?) as isize,
+            Self::I64 => i64::from_ne_bytes(buf.try_into().ok()?) as isize,
+        })
+    }
+}
 
 #[derive(Debug)]
 pub enum Node {
     Number(isize),
     ModuleSymbol(String),
+    /// `module!symbol`, exported or symbol table address
+    Export(String, String),
     Dereference(Box<Node>),
+    TypedDereference(DerefType, Box<Node>),
     Add(Box<Node>, Box<Node>),
     Sub(Box<Node>, Box<Node>),
     Mul(Box<Node>, Box<Node>),
     Div(Box<Node>, Box<Node>),
     Pow(Box<Node>, Box<Node>),
+    Mod(Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Neg(Box<Node>),
+    Shl(Box<Node>, Box<Node>),
+    /// logical shift, tagged pointer have high bit set
+    Shr(Box<Node>, Box<Node>),
+    Eq(Box<Node>, Box<Node>),
+    Ne(Box<Node>, Box<Node>),
+    Lt(Box<Node>, Box<Node>),
+    Le(Box<Node>, Box<Node>),
+    Gt(Box<Node>, Box<Node>),
+    Ge(Box<Node>, Box<Node>),
+    /// cond ? then : other, only the taken branch is evaluated
+    Cond(Box<Node>, Box<Node>, Box<Node>),
     Sin(Box<Node>),
     Cos(Box<Node>),
     Sqrt(Box<Node>),
+    /// instruction address, displacement offset, instruction length
+    Rip(Box<Node>, Box<Node>, Box<Node>),
+    Signature(String, Signature),
+    /// `Class.field->field`, value of the last field
+    Path(FieldPath),
+    /// `&Class.field->field`
+    AddressOf(FieldPath),
+    /// variable, or class address when no variable with the name
     Var(String),
     Assignment(String, Box<Node>),
+    /// `a = 1; [a] + 2`, value of last statement
+    Block(Vec<Node>),
 }
 
 impl Node {
+    /// Compile and evaluate once, keep a [`CompiledExpr`] to evaluate again
     pub fn eval(
         &self,
         env: &mut HashMap<String, isize>,
         resolver: &dyn AddressResolver,
     ) -> AddressParserResult<isize> {
-        match self {
-            Node::Number(a) => Ok(*a),
-            Node::ModuleSymbol(a) => resolver
-                .module_symbol_to_address(a.as_str())
-                .context(obfstring!("Failed load module symbol: ") + a),
-            Node::Dereference(node) => {
-                let addr = node.eval(env, resolver)?;
-                if addr <= 0 {
-                    eyre::bail!("{}{addr:#X}", obfstr!("Cant dereference negative ptr: "))
-                }
-                resolver.dereference(addr as usize).context(
-                    obfstring!("Failed dereference address: ") + format!("{addr:#X}").as_str(),
-                )
-            }
-            Node::Add(node, node1) => Ok(node
-                .eval(env, resolver)?
-                .saturating_add(node1.eval(env, resolver)?)),
-            Node::Sub(node, node1) => Ok(node
-                .eval(env, resolver)?
-                .saturating_sub(node1.eval(env, resolver)?)),
-            Node::Mul(node, node1) => Ok(node
-                .eval(env, resolver)?
-                .saturating_mul(node1.eval(env, resolver)?)),
-            Node::Div(node, node1) => Ok(node
-                .eval(env, resolver)?
-                .saturating_div(node1.eval(env, resolver)?)),
-            Node::Pow(node, node1) => Ok(node
-                .eval(env, resolver)?
-                .saturating_pow(node1.eval(env, resolver)?.try_into()?)),
-            Node::Sin(node) => Ok(f64::sin(node.eval(env, resolver)? as f64).round() as isize),
-            Node::Cos(node) => Ok(f64::cos(node.eval(env, resolver)? as f64).round() as isize),
-            Node::Sqrt(node) => Ok(f64::sqrt(node.eval(env, resolver)? as f64).round() as isize),
-            Node::Var(a) => Ok(env
-                .get(a)
-                .copied()
-                .context(obfstring!("Failed get variable: ") + a)?),
-            Node::Assignment(a, node) => {
-                let value = node.eval(env, resolver)?;
-                env.insert(a.to_owned(), value);
-                Ok(value)
-            }
-        }
+        CompiledExpr::from_node(self).eval(env, resolver)
     }
 
     pub fn boxed(self) -> Box<Self> {
@@ -0,0 +1,571 @@
+// Address expression compiled to a flat list of stack operations
+//
+// Class address can be evaluated every frame, parsing its text and walking
+// boxed nodes each time is wasted work. Constant parts are folded while
+// compiling, module lookups are kept until resolver say modules may have moved.
+
+use std::collections::HashMap;
+
+use eyre::ContextCompat;
+use parking_lot::Mutex;
+
+use super::{
+    AddressParser, AddressParserResult, AddressResolver, FieldPath, FieldPlace, Signature, Span,
+    ast::{DerefType, Node},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnaryOp {
+    Not,
+    Neg,
+    Sin,
+    Cos,
+    Sqrt,
+}
+
+impl UnaryOp {
+    fn apply(self, value: isize) -> isize {
+        match self {
+            Self::Not => !value,
+            Self::Neg => value.saturating_neg(),
+            Self::Sin => f64::sin(value as f64).round() as isize,
+            Self::Cos => f64::cos(value as f64).round() as isize,
+            Self::Sqrt => f64::sqrt(value as f64).round() as isize,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Mod,
+    And,
+    Or,
+    Shl,
+    Shr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl BinaryOp {
+    fn apply(self, lhs: isize, rhs: isize) -> AddressParserResult<isize> {
+        Ok(match self {
+            Self::Add => lhs.saturating_add(rhs),
+            Self::Sub => lhs.saturating_sub(rhs),
+            Self::Mul => lhs.saturating_mul(rhs),
+            Self::Div if rhs == 0 => eyre::bail!(obfstr!("Division by zero")),
+            Self::Div => lhs.saturating_div(rhs),
+            Self::Pow => lhs.saturating_pow(rhs.try_into()?),
+            Self::Mod => lhs.checked_rem(rhs).context(obfstring!("Modulo by zero"))?,
+            Self::And => lhs & rhs,
+            Self::Or => lhs | rhs,
+            Self::Shl => u32::try_from(rhs)
+                .ok()
+                .and_then(|rhs| lhs.checked_shl(rhs))
+                .context(obfstring!("Invalid shift amount: ") + &rhs.to_string())?,
+            // logical shift, tagged pointer have high bit set
+            Self::Shr => u32::try_from(rhs)
+                .ok()
+                .and_then(|rhs| (lhs as usize).checked_shr(rhs))
+                .map(|v| v as isize)
+                .context(obfstring!("Invalid shift amount: ") + &rhs.to_string())?,
+            // comparison result is 1 or 0
+            Self::Eq => (lhs == rhs) as isize,
+            Self::Ne => (lhs != rhs) as isize,
+            Self::Lt => (lhs < rhs) as isize,
+            Self::Le => (lhs <= rhs) as isize,
+            Self::Gt => (lhs > rhs) as isize,
+            Self::Ge => (lhs >= rhs) as isize,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Push(isize),
+    /// module base, index in `lookups`
+    Module(usize),
+    /// `module!symbol`, index in `lookups`
+    Export(usize),
+    Deref,
+    TypedDeref(DerefType),
+    Unary(UnaryOp),
+    Binary(BinaryOp),
+    /// pop instruction length, displacement offset, then instruction address
+    Rip,
+    /// `sig(module, ..)`, index in `lookups`
+    Signature(usize),
+    /// index in `paths`, with module lookup when the text can be a module name
+    Path(usize, Option<usize>),
+    AddressOf(usize),
+    /// index in `names`
+    Var(usize),
+    /// set variable to top of stack, value stay
+    Store(usize),
+    Pop,
+    /// pop, continue at op index when 0
+    JumpIfZero(usize),
+    Jump(usize),
+}
+
+/// What a module lookup ask the resolver
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Lookup {
+    Module(String),
+    Export(String, String),
+    /// scan whole module, worst one to repeat every frame
+    Signature(String, Signature),
+}
+
+impl Lookup {
+    fn resolve(&self, resolver: &dyn AddressResolver) -> Option<isize> {
+        match self {
+            Self::Module(module) => resolver.module_symbol_to_address(module),
+            Self::Export(module, symbol) => resolver.export_to_address(module, symbol),
+            Self::Signature(module, signature) => resolver.find_signature(module, signature),
+        }
+    }
+}
+
+/// Lookup results of one resolver generation
+#[derive(Debug, Default)]
+struct LookupCache {
+    generation: Option<u64>,
+    values: Vec<Option<Option<isize>>>,
+}
+
+/// Address expression ready to be evaluated many times
+#[derive(Debug, Default)]
+pub struct CompiledExpr {
+    ops: Vec<Op>,
+    names: Vec<String>,
+    paths: Vec<FieldPath>,
+    lookups: Vec<Lookup>,
+    cache: Mutex<LookupCache>,
+}
+
+impl CompiledExpr {
+    pub fn compile(src: &str) -> AddressParserResult<Self> {
+        let node = AddressParser::new(src).parse()?;
+        Ok(Self::from_node(&node))
+    }
+
+    pub fn from_node(node: &Node) -> Self {
+        let mut compiled = Self::default();
+        compiled.emit(node);
+        compiled
+    }
+
+    fn emit(&mut self, node: &Node) {
+        let op = match node {
+            Node::Number(n) => Op::Push(*n),
+            Node::ModuleSymbol(module) => Op::Module(self.lookup(Lookup::Module(module.clone()))),
+            Node::Export(module, symbol) => {
+                Op::Export(self.lookup(Lookup::Export(module.clone(), symbol.clone())))
+            }
+            Node::Dereference(node) => {
+                self.emit(node);
+                Op::Deref
+            }
+            Node::TypedDereference(ty, node) => {
+                self.emit(node);
+                Op::TypedDeref(*ty)
+            }
+            Node::Add(lhs, rhs) => return self.binary(BinaryOp::Add, lhs, rhs),
+            Node::Sub(lhs, rhs) => return self.binary(BinaryOp::Sub, lhs, rhs),
+            Node::Mul(lhs, rhs) => return self.binary(BinaryOp::Mul, lhs, rhs),
+            Node::Div(lhs, rhs) => return self.binary(BinaryOp::Div, lhs, rhs),
+            Node::Pow(lhs, rhs) => return self.binary(BinaryOp::Pow, lhs, rhs),
+            Node::Mod(lhs, rhs) => return self.binary(BinaryOp::Mod, lhs, rhs),
+            Node::And(lhs, rhs) => return self.binary(BinaryOp::And, lhs, rhs),
+            Node::Or(lhs, rhs) => return self.binary(BinaryOp::Or, lhs, rhs),
+            Node::Shl(lhs, rhs) => return self.binary(BinaryOp::Shl, lhs, rhs),
+            Node::Shr(lhs, rhs) => return self.binary(BinaryOp::Shr, lhs, rhs),
+            Node::Eq(lhs, rhs) => return self.binary(BinaryOp::Eq, lhs, rhs),
+            Node::Ne(lhs, rhs) => return self.binary(BinaryOp::Ne, lhs, rhs),
+            Node::Lt(lhs, rhs) => return self.binary(BinaryOp::Lt, lhs, rhs),
+            Node::Le(lhs, rhs) => return self.binary(BinaryOp::Le, lhs, rhs),
+            Node::Gt(lhs, rhs) => return self.binary(BinaryOp::Gt, lhs, rhs),
+            Node::Ge(lhs, rhs) => return self.binary(BinaryOp::Ge, lhs, rhs),
+            Node::Not(node) => return self.unary(UnaryOp::Not, node),
+            Node::Neg(node) => return self.unary(UnaryOp::Neg, node),
+            Node::Sin(node) => return self.unary(UnaryOp::Sin, node),
+            Node::Cos(node) => return self.unary(UnaryOp::Cos, node),
+            Node::Sqrt(node) => return self.unary(UnaryOp::Sqrt, node),
+            Node::Cond(cond, then, other) => return self.cond(cond, then, other),
+            Node::Rip(node, offset, len) => {
+                self.emit(node);
+                self.emit(offset);
+                self.emit(len);
+                Op::Rip
+            }
+            Node::Signature(module, signature) => {
+                Op::Signature(self.lookup(Lookup::Signature(module.clone(), signature.clone())))
+            }
+            Node::Path(path) => {
+                // `game.exe` is a path too
+                let module = path
+                    .is_dotted()
+                    .then(|| self.lookup(Lookup::Module(path.to_string())));
+                Op::Path(self.path(path), module)
+            }
+            Node::AddressOf(path) => Op::AddressOf(self.path(path)),
+            Node::Var(name) => Op::Var(self.name(name)),
+            Node::Assignment(name, node) => {
+                self.emit(node);
+                Op::Store(self.name(name))
+            }
+            Node::Block(statements) => {
+                if statements.is_empty() {
+                    self.ops.push(Op::Push(0));
+                }
+                // value of last statement stay on stack
+                for (i, statement) in statements.iter().enumerate() {
+                    if i > 0 {
+                        self.ops.push(Op::Pop);
+                    }
+                    self.emit(statement);
+                }
+                return;
+            }
+        };
+        self.ops.push(op);
+    }
+
+    fn unary(&mut self, op: UnaryOp, node: &Node) {
+        let start = self.ops.len();
+        self.emit(node);
+        // whole operand must be constant, last op alone can be a branch end
+        if let [Op::Push(v)] = &mut self.ops[start..] {
+            *v = op.apply(*v);
+            return;
+        }
+        self.ops.push(Op::Unary(op));
+    }
+
+    /// Both sides constant fold, unless it fail like `1 / 0`, eval report that
+    fn binary(&mut self, op: BinaryOp, lhs: &Node, rhs: &Node) {
+        let start = self.ops.len();
+        self.emit(lhs);
+        self.emit(rhs);
+        if let &[Op::Push(lhs), Op::Push(rhs)] = &self.ops[start..]
+            && let Ok(v) = op.apply(lhs, rhs)
+        {
+            self.ops.truncate(start);
+            self.ops.push(Op::Push(v));
+            return;
+        }
+        self.ops.push(Op::Binary(op));
+    }
+
+    /// Constant condition only keep the taken branch
+    fn cond(&mut self, cond: &Node, then: &Node, other: &Node) {
+        let start = self.ops.len();
+        self.emit(cond);
+        if let &[Op::Push(c)] = &self.ops[start..] {
+            self.ops.truncate(start);
+            return self.emit(if c != 0 { then } else { other });
+        }
+
+        let jump_other = self.ops.len();
+        self.ops.push(Op::JumpIfZero(0));
+        self.emit(then);
+        let jump_end = self.ops.len();
+        self.ops.push(Op::Jump(0));
+        self.ops[jump_other] = Op::JumpIfZero(self.ops.len());
+        self.emit(other);
+        self.ops[jump_end] = Op::Jump(self.ops.len());
+    }
+
+    fn lookup(&mut self, lookup: Lookup) -> usize {
+        match self.lookups.iter().position(|l| *l == lookup) {
+            Some(i) => i,
+            None => {
+                self.lookups.push(lookup);
+                self.lookups.len() - 1
+            }
+        }
+    }
+
+    fn name(&mut self, name: &str) -> usize {
+        match self.names.iter().position(|n| n == name) {
+            Some(i) => i,
+            None => {
+                self.names.push(name.to_owned());
+                self.names.len() - 1
+            }
+        }
+    }
+
+    fn path(&mut self, path: &FieldPath) -> usize {
+        self.paths.push(path.clone());
+        self.paths.len() - 1
+    }
+
+    pub fn eval(
+        &self,
+        env: &mut HashMap<String, isize>,
+        resolver: &dyn AddressResolver,
+    ) -> AddressParserResult<isize> {
+        let mut stack: Vec<isize> = Vec::with_capacity(8);
+        let mut pc = 0;
+        while let Some(op) = self.ops.get(pc) {
+            pc += 1;
+            let value = match *op {
+                Op::Push(v) => v,
+                Op::Module(slot) => self.lookup_value(slot, resolver).with_context(|| {
+                    let Lookup::Module(module) = &self.lookups[slot] else {
+                        unreachable!()
+                    };
+                    obfstring!("Failed load module symbol: ") + module
+                })?,
+                Op::Export(slot) => self.lookup_value(slot, resolver).with_context(|| {
+                    let Lookup::Export(module, symbol) = &self.lookups[slot] else {
+                        unreachable!()
+                    };
+                    format!("{}{module}!{symbol}", obfstr!("Unknown symbol: "))
+                })?,
+                Op::Deref => {
+                    let addr = dereferencable(pop(&mut stack))?;
+                    // message built only on failure, this run every frame
+                    resolver.dereference(addr).with_context(|| {
+                        obfstring!("Failed dereference address: ") + format!("{addr:#X}").as_str()
+                    })?
+                }
+                Op::TypedDeref(ty) => {
+                    let addr = dereferencable(pop(&mut stack))?;
+                    ty.read(resolver, addr).with_context(|| {
+                        obfstring!("Failed dereference address: ") + format!("{addr:#X}").as_str()
+                    })?
+                }
+                Op::Unary(op) => op.apply(pop(&mut stack)),
+                Op::Binary(op) => {
+                    let rhs = pop(&mut stack);
+                    op.apply(pop(&mut stack), rhs)?
+                }
+                Op::Rip => {
+                    let len = pop(&mut stack);
+                    let offset = pop(&mut stack);
+                    let addr = pop(&mut stack);
+                    let at = addr.saturating_add(offset);
+                    let disp = DerefType::I32
+                        .read(resolver, at as usize)
+                        .with_context(|| {
+                            obfstring!("Failed read displacement at: ")
+                                + format!("{at:#X}").as_str()
+                        })?;
+                    addr.saturating_add(len).saturating_add(disp)
+                }
+                Op::Signature(slot) => self.lookup_value(slot, resolver).with_context(|| {
+                    let Lookup::Signature(module, signature) = &self.lookups[slot] else {
+                        unreachable!()
+                    };
+                    obfstring!("Signature not found in ") + module + ": " + &signature.to_string()
+                })?,
+                Op::Path(i, module) => match module.and_then(|m| self.lookup_value(m, resolver)) {
+                    Some(base) => base,
+                    None => path_value(&self.paths[i], resolver)?,
+                },
+                Op::AddressOf(i) => resolve_path(&self.paths[i], resolver)?.address as isize,
+                Op::Var(i) => {
+                    let name = &self.names[i];
+                    match env.get(name) {
+                        Some(v) => *v,
+                        None => match FieldPath::from_word(name, Span::default())
+                            .and_then(|path| resolver.resolve_path(&path))
+                        {
+                            Some(place) => place?.address as isize,
+                            None => eyre::bail!("{}{name}", obfstr!("Failed get variable: ")),
+                        },
+                    }
+                }
+                Op::Store(i) => {
+                    let value = stack.last().copied().unwrap_or_default();
+                    env.insert(self.names[i].clone(), value);
+                    continue;
+                }
+                Op::Pop => {
+                    stack.pop();
+                    continue;
+                }
+                Op::JumpIfZero(to) => {
+                    if pop(&mut stack) == 0 {
+                        pc = to;
+                    }
+                    continue;
+                }
+                Op::Jump(to) => {
+                    pc = to;
+                    continue;
+                }
+            };
+            stack.push(value);
+        }
+        Ok(stack.pop().unwrap_or_default())
+    }
+
+    /// Cached while resolver generation stay the same
+    fn lookup_value(&self, slot: usize, resolver: &dyn AddressResolver) -> Option<isize> {
+        let lookup = &self.lookups[slot];
+        let Some(generation) = resolver.generation() else {
+            return lookup.resolve(resolver);
+        };
+        let mut cache = self.cache.lock();
+        if cache.generation != Some(generation) {
+            cache.generation = Some(generation);
+            cache.values = vec![None; self.lookups.len()];
+        }
+        *cache.values[slot].get_or_insert_with(|| lookup.resolve(resolver))
+    }
+}
+
+/// Compiler always push operands first
+fn pop(stack: &mut Vec<isize>) -> isize {
+    stack.pop().unwrap_or_default()
+}
+
+fn dereferencable(addr: isize) -> AddressParserResult<usize> {
+    if addr <= 0 {
+        eyre::bail!("{}{addr:#X}", obfstr!("Cant dereference negative ptr: "))
+    }
+    Ok(addr as usize)
+}
+
+/// Value of the last field, read as unsigned
+fn path_value(path: &FieldPath, resolver: &dyn AddressResolver) -> AddressParserResult<isize> {
+    let place = resolve_path(path, resolver)?;
+    match place.size {
+        None => Ok(place.address as isize),
+        Some(size @ (1 | 2 | 4 | 8)) => {
+            let mut buf = [0u8; 8];
+            if !resolver.read_exact(place.address, &mut buf[..size]) {
+                eyre::bail!(
+                    "{}{path}{}{:#X}",
+                    obfstr!("Failed read "),
+                    obfstr!(" at "),
+                    place.address
+                );
+            }
+            Ok(u64::from_le_bytes(buf) as isize)
+        }
+        Some(size) => eyre::bail!(
+            "{path}{}{size}{}{path}{}",
+            obfstr!(" is "),
+            obfstr!(" bytes, use &"),
+            obfstr!(" for its address")
+        ),
+    }
+}
+
+fn resolve_path(
+    path: &FieldPath,
+    resolver: &dyn AddressResolver,
+) -> AddressParserResult<FieldPlace> {
+    match resolver.resolve_path(path) {
+        Some(place) => place,
+        None if path.is_dotted() => {
+            eyre::bail!("{}{path}", obfstr!("Unknown module or class: "))
+        }
+        None => eyre::bail!("{}{}", obfstr!("Unknown class: "), path.root),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    /// `game.exe` at 0x400000, count how often module or signature is asked
+    #[derive(Default)]
+    struct Modules {
+        lookups: Cell<usize>,
+        generation: Cell<Option<u64>>,
+    }
+
+    impl AddressResolver for Modules {
+        fn module_symbol_to_address(&self, module_name: &str) -> Option<isize> {
+            self.lookups.set(self.lookups.get() + 1);
+            (module_name == "game.exe").then_some(0x400000)
+        }
+
+        fn find_signature(&self, module_name: &str, _signature: &Signature) -> Option<isize> {
+            self.lookups.set(self.lookups.get() + 1);
+            (module_name == "game.exe").then_some(0x401000)
+        }
+
+        fn dereference(&self, address: usize) -> Option<isize> {
+            Some(address as isize * 2)
+        }
+
+        fn generation(&self) -> Option<u64> {
+            self.generation.get()
+        }
+    }
+
+    #[test]
+    fn test_compile() {
+        use Op::*;
+        let ops = |src: &str| CompiledExpr::compile(src).unwrap().ops;
+
+        assert_eq!(ops("1 + 2 * 3"), [Push(7)]);
+        assert_eq!(ops("-(1 << 4) + ~0"), [Push(-17)]);
+        // module name is also a path, class lookup when not a module
+        assert_eq!(
+            ops("game.exe + 0x10 * 4"),
+            [Path(0, Some(0)), Push(0x40), Binary(BinaryOp::Add)]
+        );
+        assert_eq!(ops("1 > 0 ? 1337.dll : [0]"), [Module(0)]);
+        assert_eq!(ops(""), [Push(0)]);
+        // only else branch is a push, no fold
+        assert_eq!(ops("-([0] ? 1 : 2)").last(), Some(&Unary(UnaryOp::Neg)));
+        // error stay for eval
+        assert_eq!(ops("5 % 0"), [Push(5), Push(0), Binary(BinaryOp::Mod)]);
+
+        let compiled = CompiledExpr::compile("a = game.exe; [a] ? game.exe : 1").unwrap();
+        assert_eq!(compiled.lookups.len(), 1);
+        let memory = Modules::default();
+        let mut env = HashMap::new();
+        assert_eq!(compiled.eval(&mut env, &memory).unwrap(), 0x400000);
+        assert_eq!(env["a"], 0x400000);
+        assert_eq!(memory.lookups.get(), 2);
+
+        // same generation ask once
+        memory.generation.set(Some(1));
+        for _ in 0..3 {
+            compiled.eval(&mut HashMap::new(), &memory).unwrap();
+        }
+        assert_eq!(memory.lookups.get(), 3);
+        memory.generation.set(Some(2));
+        compiled.eval(&mut HashMap::new(), &memory).unwrap();
+        assert_eq!(memory.lookups.get(), 4);
+
+        let compiled = CompiledExpr::compile(r#"sig("game.exe", "48 8B ?? 05")"#).unwrap();
+        for _ in 0..3 {
+            assert_eq!(
+                compiled.eval(&mut HashMap::new(), &memory).unwrap(),
+                0x401000
+            );
+        }
+        assert_eq!(memory.lookups.get(), 5);
+
+        let eval = |src: &str| CompiledExpr::compile(src)?.eval(&mut HashMap::new(), &memory);
+        assert_eq!(eval("[0x10] ? 1 : 2").unwrap(), 1);
+        assert_eq!(eval("[0x10] == 0 ? 1 : 2").unwrap(), 2);
+        assert_eq!(eval("1; 2; 3").unwrap(), 3);
+        assert!(eval("5 % 0").is_err());
+        assert!(eval("1 / 0").is_err());
+        assert_eq!(eval("x = [0x10]; -(x ? 1 : 2)").unwrap(), -1);
+        assert_eq!(eval("x = [0x10]; ~(x ? 0 : 2)").unwrap(), -1);
+        assert_eq!(eval("x = 0; -(x ? 1 : 2)").unwrap(), -2);
+        assert!(eval("missing.dll").is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use super::{Span, lexer::Lexer, token::Token};
+
+/// Word under the cursor, what a completion replace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordAt {
+    /// whole word, cursor can be in the middle of it
+    pub span: Span,
+    /// typed part before cursor
+    pub prefix: String,
+    /// inside "quotes" like sig("game.exe", ..), only module names fit
+    pub in_string: bool,
+}
+
+/// Word at byte `cursor`, lexing stop there so text after can be anything
+pub fn word_at(src: &str, cursor: usize) -> Option<WordAt> {
+    let mut lexer = Lexer::partial(src);
+    // `module!symbol` is one word
+    let mut word_start = None;
+    let mut export_start = None;
+    loop {
+        let token = lexer.next_token().ok()?;
+        let span = lexer.span;
+        if token.is_eof() || span.start >= cursor {
+            return None;
+        }
+        if span.end < cursor {
+            export_start = match token {
+                Token::Bang => word_start,
+                _ => None,
+            };
+            word_start =
+                matches!(token, Token::Symbol(_) | Token::ModuleSymbol(_)).then_some(span.start);
+            continue;
+        }
+
+        let word = |start: usize| WordAt {
+            span: Span::new(start, span.end),
+            prefix: src[start..cursor].to_owned(),
+            in_string: false,
+        };
+        return match token {
+            Token::Symbol(_) | Token::ModuleSymbol(_) => {
+                Some(word(export_start.unwrap_or(span.start)))
+            }
+            // `module!` nothing typed after yet
+            Token::Bang => word_start.map(word),
+            Token::Str(_) => {
+                let start = span.start + 1;
+                // unterminated one run to the end
+                let closed = span.end > start && src[..span.end].ends_with('"');
+                let end = if closed { span.end - 1 } else { span.end };
+                (cursor <= end).then(|| WordAt {
+                    span: Span::new(start, end),
+                    prefix: src[start..cursor].to_owned(),
+                    in_string: true,
+                })
+            }
+            _ => None,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_at() {
+        let word = |src: &str, cursor| word_at(src, cursor).map(|w| (w.span.range(), w.prefix));
+
+        assert_eq!(word("game.ex", 7), Some((0..7, "game.ex".into())));
+        // middle of word replace whole word
+        assert_eq!(word("[game.exe] + 8", 3), Some((1..9, "ga".into())));
+        assert_eq!(word("base + of", 9), Some((7..9, "of".into())));
+        // after operator or space nothing to complete
+        assert_eq!(word("base +", 6), None);
+        assert_eq!(word("base ", 5), None);
+        assert_eq!(word("0x10", 4), None);
+        // text after cursor is not checked
+        assert_eq!(word("ba @ \"", 2), Some((0..2, "ba".into())));
+        assert_eq!(
+            word("[libc.so.6!mal", 14),
+            Some((1..14, "libc.so.6!mal".into()))
+        );
+        assert_eq!(word("game.exe!", 9), Some((0..9, "game.exe!".into())));
+        assert_eq!(word("1 != x", 6), Some((5..6, "x".into())));
+
+        let w = word_at("sig(\"game", 9).unwrap();
+        assert!(w.in_string);
+        assert_eq!((w.span.range(), w.prefix.as_str()), (5..9, "game"));
+        let w = word_at("sig(\"game.exe\", \"48\")", 7).unwrap();
+        assert_eq!((w.span.range(), w.prefix.as_str()), (5..13, "ga"));
+        assert_eq!(word("sig(\"game.exe\"", 14), None);
+    }
+}
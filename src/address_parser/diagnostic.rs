@@ -0,0 +1,106 @@
+use std::ops::Range;
+
+/// Byte range in the expression source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// From start of `self` to end of `other`
+    pub fn to(self, other: Span) -> Self {
+        Self::new(self.start, other.end.max(self.start))
+    }
+
+    pub fn range(self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// Parse error pointing into the source, travel inside `eyre::Report`
+///
+/// UI get it back with `report.downcast_ref::<ParseDiagnostic>()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDiagnostic {
+    pub message: String,
+    pub span: Span,
+    pub expected: Option<String>,
+    pub found: Option<String>,
+    pub help: Option<String>,
+}
+
+impl ParseDiagnostic {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+            expected: None,
+            found: None,
+            help: None,
+        }
+    }
+
+    /// "Expected `expected` but found `found`"
+    pub fn expected(expected: impl Into<String>, found: impl Into<String>, span: Span) -> Self {
+        let expected = expected.into();
+        let found = found.into();
+        Self {
+            message: format!(
+                "{}{expected}{}{found}",
+                obfstr!("Expected "),
+                obfstr!(" but found ")
+            ),
+            span,
+            expected: Some(expected),
+            found: Some(found),
+            help: None,
+        }
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Source with `^^^` under the span, for monospace output
+    pub fn render(&self, src: &str) -> String {
+        // spans come from lexer so they sit on char boundaries
+        let start = self.span.start.min(src.len());
+        let end = self.span.end.clamp(start, src.len());
+        let pad = src.get(..start).map_or(start, |s| s.chars().count());
+        let carets = src.get(start..end).map_or(1, |s| s.chars().count()).max(1);
+        let mut out = format!(
+            "{src}\n{}{} {}",
+            " ".repeat(pad),
+            "^".repeat(carets),
+            self.message
+        );
+        if let Some(help) = &self.help {
+            out += &format!("\n{}{help}", obfstr!("help: "));
+        }
+        out
+    }
+}
+
+impl std::fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.message, obfstr!(" at "), self.span)?;
+        if let Some(help) = &self.help {
+            write!(f, "\n{}{help}", obfstr!("help: "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseDiagnostic {}
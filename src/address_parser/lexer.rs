@@ -1,57 +1,144 @@
-use super::{AddressParserResult, token::Token};
+use super::{
+    AddressParserResult,
+    diagnostic::{ParseDiagnostic, Span},
+    token::Token,
+};
 
 pub struct Lexer<'a> {
     pub curr: char,
     pub prev: Option<char>,
+    /// byte offset of `curr`
     pub pos: usize,
+    /// of last token from `next_token`
+    pub span: Span,
     pub src: &'a str,
     pub eof: bool,
     pub error: bool,
+    /// text still being typed, unterminated string run to the end
+    pub partial: bool,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(src: &'a str) -> Self {
         let eof = src.is_empty();
-        let curr = if !eof {
-            src.chars().nth(0).unwrap()
-        } else {
-            '\0'
-        };
+        let curr = src.chars().next().unwrap_or('\0');
 
         Self {
             curr,
             prev: None,
             pos: 0,
+            span: Span::default(),
             src,
             eof,
             error: false,
+            partial: false,
+        }
+    }
+
+    /// For completion, source is cut at the cursor or not finished
+    pub fn partial(src: &'a str) -> Self {
+        Self {
+            partial: true,
+            ..Self::new(src)
         }
     }
 
     fn bump(&mut self) {
-        self.pos += 1;
-        if self.pos >= self.src.len() {
+        self.pos += self.curr.len_utf8();
+        let Some(next) = self.src[self.pos..].chars().next() else {
+            self.pos = self.src.len();
             self.eof = true;
             return;
-        }
+        };
 
         self.prev = Some(self.curr);
-        self.curr = self.src.chars().nth(self.pos).unwrap();
+        self.curr = next;
+    }
+
+    /// Bump when next char is `c`, for two char operators
+    fn bump_if(&mut self, c: char) -> bool {
+        if !self.eof && self.curr == c {
+            self.bump();
+            return true;
+        }
+        false
     }
 
     fn consume_whitespace(&mut self) {
-        while self.curr.is_whitespace() {
+        while !self.eof && self.curr.is_whitespace() {
             self.bump();
         }
     }
 
+    fn fail(&mut self, diagnostic: ParseDiagnostic) -> eyre::Report {
+        self.error = true;
+        self.span = diagnostic.span;
+        diagnostic.into()
+    }
+
+    /// Letters, digits, `_` and `.` from current char, return its range
+    fn word(&mut self) -> (usize, usize) {
+        let start = self.pos;
+        while !self.eof && is_word_char(self.curr) {
+            self.bump();
+        }
+        (start, self.pos)
+    }
+
+    /// `10`, `0x1_0000`, `0b1010`, `0o17`, or module name start with digit
+    ///
+    /// Literals are 64 bit patterns, `0xFFFF_FFFF_FFFF_FFFF` is -1
+    fn number(&mut self) -> AddressParserResult<Token> {
+        let (start, end) = self.word();
+        let word = &self.src[start..end];
+        let (radix, digits) = match word.get(..2) {
+            Some("0x" | "0X") => (16, &word[2..]),
+            Some("0b" | "0B") => (2, &word[2..]),
+            Some("0o" | "0O") => (8, &word[2..]),
+            _ if word.contains('.') => return Ok(Token::ModuleSymbol(word.to_string())),
+            _ => (10, word),
+        };
+
+        let span = Span::new(start, end);
+        let digits = digits.replace('_', "");
+        if digits.is_empty() {
+            return Err(self.fail(ParseDiagnostic::new(obfstr!("Number without digits"), span)));
+        }
+        if let Some(c) = digits.chars().find(|c| !c.is_digit(radix)) {
+            let msg = format!(
+                "{}'{c}'{}{radix}",
+                obfstr!("Invalid digit "),
+                obfstr!(" for base ")
+            );
+            let help = match radix {
+                16 => obfstr!("hex digits are 0-9 and A-F").to_string(),
+                10 if c.is_alphabetic() => obfstr!("hex number need 0x prefix").to_string(),
+                _ => format!("{}{radix}", obfstr!("digits must be below ")),
+            };
+            return Err(self.fail(ParseDiagnostic::new(msg, span).with_help(help)));
+        }
+        match usize::from_str_radix(&digits, radix) {
+            Ok(v) => Ok(Token::Number(v as isize)),
+            Err(_) => Err(self.fail(
+                ParseDiagnostic::new(obfstr!("Number too large"), span)
+                    .with_help(obfstr!("literals are at most 64 bit")),
+            )),
+        }
+    }
+
     pub fn next_token(&mut self) -> AddressParserResult<Token> {
+        self.consume_whitespace();
+        let start = self.pos;
+        let token = self.token()?;
+        self.span = Span::new(start, self.pos);
+        Ok(token)
+    }
+
+    fn token(&mut self) -> AddressParserResult<Token> {
         if self.eof {
             return Ok(Token::Eof);
         }
 
-        self.consume_whitespace();
-
         match self.curr {
             '(' => {
                 self.bump();
@@ -69,77 +156,46 @@ impl<'a> Lexer<'a> {
                 self.bump();
                 Ok(Token::CloseBrackets)
             }
-            c if c.is_ascii_hexdigit() || c.is_alphabetic() => {
-                let start = self.pos;
-                let start_char = self.curr;
-                let mut end = start + 1;
-
+            ',' => {
                 self.bump();
-
-                // yes this is hex number
-                if start_char == '0' && (self.curr == 'x' || self.curr == 'X') {
-                    // move next
+                Ok(Token::Comma)
+            }
+            ';' => {
+                self.bump();
+                Ok(Token::Semicolon)
+            }
+            '"' => {
+                self.bump();
+                let start = self.pos;
+                while !self.eof && self.curr != '"' {
                     self.bump();
-                    end += 1;
-
-                    while !self.eof && !self.curr.is_whitespace() {
-                        if self.curr == '.' {
-                            // why we get . here
-                            self.error = true;
-                            return Err(eyre::eyre!(
-                                "{}{}",
-                                obfstr!("Failed parse type hex number at "),
-                                self.pos
-                            ));
-                        }
-
-                        self.bump();
-                        end += 1;
-                    }
-
-                    // +2 for remove 0x || 0X
-                    return Ok(Token::Number(isize::from_str_radix(
-                        &self.src[start + 2..end],
-                        16,
-                    )?));
                 }
-
-                let mut is_module_name = false;
-                let mut has_non_numberic = !start_char.is_ascii_digit();
-                // 3 case left
-                //
-                // number base 10
-                // symbol name: let, with, while, custume variable name
-                // module name: target.dll
-                while !self.eof
-                    && (self.curr.is_ascii_hexdigit() // this include number
-                        || self.curr.is_alphabetic() // abcd
-                        || self.curr == '.')
-                {
-                    // dot => module
-                    if !is_module_name && self.curr == '.' {
-                        is_module_name = true;
-                    }
-
-                    if !has_non_numberic && !self.curr.is_ascii_digit() {
-                        has_non_numberic = true;
-                    }
-
-                    self.bump();
-                    end += 1;
+                if self.eof && self.partial {
+                    return Ok(Token::Str(self.src[start..].to_string()));
                 }
-
-                if is_module_name {
-                    return Ok(Token::ModuleSymbol(self.src[start..end].to_string()));
+                if self.eof {
+                    return Err(self.fail(
+                        ParseDiagnostic::new(
+                            obfstr!("Unterminated string"),
+                            Span::new(start - 1, self.pos),
+                        )
+                        .with_help(obfstr!("close it with \"")),
+                    ));
                 }
-
-                if !has_non_numberic {
-                    return Ok(Token::Number(isize::from_str_radix(
-                        &self.src[start..end],
-                        10,
-                    )?));
+                let s = self.src[start..self.pos].to_string();
+                self.bump();
+                Ok(Token::Str(s))
+            }
+            c if c.is_ascii_digit() => self.number(),
+            // symbol name: let, sin, custom variable name
+            // module name: target.dll
+            c if c.is_alphabetic() || c == '_' => {
+                let (start, end) = self.word();
+                let word = self.src[start..end].to_string();
+                match word.contains('.') {
+                    true => Ok(Token::ModuleSymbol(word)),
+                    false => Ok(Token::Symbol(word)),
                 }
-                Ok(Token::Symbol(self.src[start..end].to_string()))
             }
             '+' => {
                 self.bump();
@@ -148,6 +204,9 @@ impl<'a> Lexer<'a> {
 
             '-' => {
                 self.bump();
+                if self.bump_if('>') {
+                    return Ok(Token::Arrow);
+                }
                 Ok(Token::Sub)
             }
 
@@ -166,25 +225,182 @@ impl<'a> Lexer<'a> {
                 Ok(Token::Pow)
             }
 
+            '%' => {
+                self.bump();
+                Ok(Token::Mod)
+            }
+
+            '&' => {
+                self.bump();
+                Ok(Token::And)
+            }
+
+            '|' => {
+                self.bump();
+                Ok(Token::Or)
+            }
+
+            '~' => {
+                self.bump();
+                Ok(Token::Not)
+            }
+
+            '?' => {
+                self.bump();
+                Ok(Token::Question)
+            }
+
+            ':' => {
+                self.bump();
+                Ok(Token::Colon)
+            }
+
+            '<' => {
+                self.bump();
+                if self.bump_if('<') {
+                    return Ok(Token::Shl);
+                }
+                if self.bump_if('=') {
+                    return Ok(Token::LessEq);
+                }
+                Ok(Token::Less)
+            }
+
+            '>' => {
+                self.bump();
+                if self.bump_if('>') {
+                    return Ok(Token::Shr);
+                }
+                if self.bump_if('=') {
+                    return Ok(Token::GreaterEq);
+                }
+                Ok(Token::Greater)
+            }
+
+            '!' => {
+                self.bump();
+                if self.bump_if('=') {
+                    return Ok(Token::NotEq);
+                }
+                Ok(Token::Bang)
+            }
+
             '=' => {
                 self.bump();
+                if self.bump_if('=') {
+                    return Ok(Token::EqEq);
+                }
                 Ok(Token::Equals)
             }
 
-            token => {
-                self.error = true;
-                Err(eyre::eyre!(
-                    "{} {token} at {}",
-                    obfstr!("Unknown token while parse"),
-                    self.pos
-                ))
-            }
+            token => Err(self.fail(ParseDiagnostic::new(
+                format!("{}'{token}'", obfstr!("Unknown character ")),
+                Span::new(self.pos, self.pos + token.len_utf8()),
+            ))),
         }
     }
 }
 
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+/// `s` lex back as one module or symbol name
+///
+/// Names from the backend can be `libstdc++.so.6` or `?Create@@YAXXZ`,
+/// inserting those into an expression would not parse
+pub fn is_lexable_word(s: &str) -> bool {
+    matches!(
+        Lexer::new(s).next_token(),
+        Ok(Token::Symbol(w) | Token::ModuleSymbol(w)) if w == s
+    )
+}
+
 impl<'a> std::fmt::Display for Lexer<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.curr)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(src: &str) -> AddressParserResult<Vec<Token>> {
+        let mut lexer = Lexer::new(src);
+        let mut tokens = vec![];
+        loop {
+            match lexer.next_token()? {
+                Token::Eof => return Ok(tokens),
+                t => tokens.push(t),
+            }
+        }
+    }
+
+    #[test]
+    fn test_lexable_word() {
+        for s in ["game.exe", "1337.dll", "libc.so.6", "_start", "malloc"] {
+            assert!(is_lexable_word(s), "{s}");
+        }
+        for s in [
+            "",
+            "libstdc++.so.6",
+            "ld-linux-x86-64.so.2",
+            "?Create@@YAXXZ",
+            " a",
+        ] {
+            assert!(!is_lexable_word(s), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_lexer_numbers() {
+        use Token::*;
+        let n = Number;
+        for (src, expected) in [
+            ("0x10+4", vec![n(0x10), Add, n(4)]),
+            ("[0x10]", vec![OpenBrackets, n(0x10), CloseBrackets]),
+            ("-0x10", vec![Sub, n(0x10)]),
+            ("0X1_0000", vec![n(0x10000)]),
+            ("1_000_000", vec![n(1_000_000)]),
+            ("0b1010", vec![n(10)]),
+            ("0o17", vec![n(15)]),
+            ("0xFFFF_FFFF_FFFF_FFFF", vec![n(-1)]),
+            ("0x10,0x20)", vec![n(0x10), Comma, n(0x20), RParent]),
+            ("  7  ", vec![n(7)]),
+            ("0x10 ", vec![n(0x10)]),
+            ("", vec![]),
+            ("   ", vec![]),
+            ("abc", vec![Symbol("abc".into())]),
+            ("_tmp1", vec![Symbol("_tmp1".into())]),
+            ("7z.dll+1", vec![ModuleSymbol("7z.dll".into()), Add, n(1)]),
+            ("my_game.exe", vec![ModuleSymbol("my_game.exe".into())]),
+            (
+                "libc.so.6!malloc",
+                vec![
+                    ModuleSymbol("libc.so.6".into()),
+                    Bang,
+                    Symbol("malloc".into()),
+                ],
+            ),
+            ("1!=2", vec![n(1), NotEq, n(2)]),
+        ] {
+            assert_eq!(tokens(src).unwrap(), expected, "{src}");
+        }
+
+        for (src, err) in [
+            ("0x", "Number without digits at 0..2"),
+            ("1 + 0x_", "Number without digits at 4..7"),
+            ("0xFG", "Invalid digit 'G' for base 16 at 0..4"),
+            ("0b102", "Invalid digit '2' for base 2 at 0..5"),
+            ("10abc", "Invalid digit 'a' for base 10 at 0..5"),
+            ("0x1.dll", "Invalid digit '.' for base 16 at 0..7"),
+            ("2 * 0x1_0000_0000_0000_0000", "Number too large at 4..27"),
+            ("99999999999999999999", "Number too large at 0..20"),
+        ] {
+            let e = tokens(src).unwrap_err();
+            let d = e.downcast_ref::<ParseDiagnostic>().unwrap();
+            assert_eq!(format!("{} at {}", d.message, d.span), err, "{src}");
+        }
+    }
+}
@@ -1,11 +1,54 @@
 mod ast;
+mod compile;
+mod complete;
+mod diagnostic;
 mod lexer;
 mod parser;
+mod path;
+mod signature;
 mod token;
 
 pub type AddressParserResult<T> = eyre::Result<T>;
+pub use compile::CompiledExpr;
+pub use complete::{WordAt, word_at};
+pub use diagnostic::{ParseDiagnostic, Span};
+pub use lexer::is_lexable_word;
 pub use parser::AddressParser;
+pub use path::{Access, FieldPath, FieldPlace, PathSegment};
+pub use signature::Signature;
 pub trait AddressResolver {
     fn module_symbol_to_address(&self, module_name: &str) -> Option<isize>;
+    /// `module!symbol`, from module ELF symbols or PE exports
+    fn export_to_address(&self, _module_name: &str, _symbol_name: &str) -> Option<isize> {
+        None
+    }
     fn dereference(&self, address: usize) -> Option<isize>;
+    /// Fill whole buffer or fail, memory backends read it directly
+    ///
+    /// Default go through `dereference`, so only up to pointer size
+    fn read_exact(&self, address: usize, buffer: &mut [u8]) -> bool {
+        if buffer.len() > size_of::<usize>() {
+            return false;
+        }
+        let Some(value) = self.dereference(address) else {
+            return false;
+        };
+        buffer.copy_from_slice(&value.to_ne_bytes()[..buffer.len()]);
+        true
+    }
+    /// Address of first `signature` match inside module
+    fn find_signature(&self, _module_name: &str, _signature: &Signature) -> Option<isize> {
+        None
+    }
+    /// Same value while module bases stay valid, [`CompiledExpr`] keep module, symbol and
+    /// signature lookups until it change
+    ///
+    /// None when resolver cant tell, lookups are done every evaluation
+    fn generation(&self) -> Option<u64> {
+        None
+    }
+    /// Project class layout for `Class.field->field`, None when root is not a class
+    fn resolve_path(&self, _path: &FieldPath) -> Option<eyre::Result<FieldPlace>> {
+        None
+    }
 }
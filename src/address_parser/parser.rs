@@ -1,9 +1,33 @@
-use super::{AddressParserResult, ast::Node, lexer::Lexer, token::Token};
+use super::{
+    Access, AddressParserResult, FieldPath, Signature,
+    ast::{DerefType, Node},
+    diagnostic::{ParseDiagnostic, Span},
+    lexer::Lexer,
+    token::{Assoc, Token, UNARY_PREC},
+};
+
+/// Function call argument, strings only valid as argument
+#[derive(Debug)]
+enum Arg {
+    Str(String, Span),
+    Expr(Box<Node>, Span),
+}
+
+impl Arg {
+    fn span(&self) -> Span {
+        match self {
+            Arg::Str(_, span) | Arg::Expr(_, span) => *span,
+        }
+    }
+}
 
 pub struct AddressParser<'a> {
     pub curr: Token,
+    pub curr_span: Span,
     pub lexer: Lexer<'a>,
-    pub peeked: Option<Token>,
+    pub peeked: Option<(Token, Span)>,
+    /// every variable and class path seen, for rename
+    pub paths: Vec<FieldPath>,
 }
 
 impl<'a> AddressParser<'a> {
@@ -11,14 +35,90 @@ impl<'a> AddressParser<'a> {
         let lexer = Lexer::new(src);
         Self {
             curr: Token::Eof,
+            curr_span: Span::default(),
             lexer,
             peeked: None,
+            paths: vec![],
         }
     }
 
+    /// `;` separated statements, errors are `ParseDiagnostic` inside the report
     #[tracing::instrument(name = "Parse", skip(self))]
     pub fn parse(&mut self) -> AddressParserResult<Box<Node>> {
-        self.expr(1)
+        let mut statements = vec![];
+        loop {
+            match self.peek_token()? {
+                Token::Eof => break,
+                Token::Semicolon => {
+                    self.next_token()?;
+                    continue;
+                }
+                _ => statements.push(*self.statement()?),
+            }
+
+            let rest = self.peek_token()?;
+            if !rest.is_eof() && rest != Token::Semicolon {
+                let span = self.peek_span()?;
+                return Err(ParseDiagnostic::new(
+                    format!("{}{}", obfstr!("Unexpected "), self.describe(&rest, span)),
+                    span,
+                )
+                .with_help(obfstr!("missing operator or ';' before it?"))
+                .into());
+            }
+        }
+
+        // empty address is 0
+        Ok(match statements.len() {
+            0 => Node::Number(0).boxed(),
+            1 => statements.pop().unwrap().boxed(),
+            _ => Node::Block(statements).boxed(),
+        })
+    }
+
+    /// `let a = expr`, `a = expr` or expression
+    fn statement(&mut self) -> AddressParserResult<Box<Node>> {
+        if self.peek_token()? == Token::Symbol("let".into()) {
+            self.next_token()?;
+            self.next_token()?;
+            let Token::Symbol(name) = self.curr.clone() else {
+                return Err(ParseDiagnostic::expected(
+                    obfstr!("variable name"),
+                    self.describe(&self.curr, self.curr_span),
+                    self.curr_span,
+                )
+                .into());
+            };
+            // not `expect('=')`, `==` also start with '='
+            self.next_token()?;
+            if self.curr != Token::Equals {
+                return Err(ParseDiagnostic::expected(
+                    "'='",
+                    self.describe(&self.curr, self.curr_span),
+                    self.curr_span,
+                )
+                .into());
+            }
+            return Ok(Node::Assignment(name, self.expr(1)?).boxed());
+        }
+
+        let start = self.peek_span()?;
+        let e = self.expr(1)?;
+        if self.peek_token()? != Token::Equals {
+            return Ok(e);
+        }
+        let Node::Var(name) = *e else {
+            return Err(ParseDiagnostic::new(
+                obfstr!("Cannot assign to expression"),
+                start.to(self.curr_span),
+            )
+            .with_help(obfstr!(
+                "only a variable name go before '=', compare with '=='"
+            ))
+            .into());
+        };
+        self.next_token()?;
+        Ok(Node::Assignment(name, self.expr(1)?).boxed())
     }
 
     #[tracing::instrument(name = "Expr", skip(self))]
@@ -39,12 +139,22 @@ impl<'a> AddressParser<'a> {
             }
 
             self.next_token()?;
+            let op_span = self.curr_span;
+            // cond ? then : other
+            if curr == Token::Question {
+                let then = self.expr(1)?;
+                self.expect_closing(':', '?', op_span)?;
+                let other = self.expr(op_prec)?;
+                lhs = Node::Cond(lhs, then, other).boxed();
+                continue;
+            }
+
             let rhs = match op_assoc {
-                0 => self.expr(op_prec + 1),
-                _ => self.expr(op_prec),
+                Assoc::Left => self.expr(op_prec + 1),
+                Assoc::Right => self.expr(op_prec),
             }?;
 
-            lhs = self.operation(curr, lhs, rhs)?;
+            lhs = self.operation(curr, op_span, lhs, rhs)?;
         }
         Ok(lhs)
     }
@@ -53,50 +163,123 @@ impl<'a> AddressParser<'a> {
     fn atom(&mut self) -> AddressParserResult<Box<Node>> {
         match self.peek_token()? {
             Token::LParent => {
-                self.expect('(')?;
+                let open = self.expect('(')?;
                 let e = self.expr(1)?;
-                self.expect(')')?;
+                self.expect_closing(')', '(', open)?;
                 Ok(e)
             }
             Token::OpenBrackets => {
-                self.expect('[')?;
+                let open = self.expect('[')?;
                 let e = self.expr(1)?;
-                self.expect(']')?;
+                self.expect_closing(']', '[', open)?;
                 Ok(Node::Dereference(e).boxed())
             }
             Token::Number(n) => {
                 self.next_token()?;
                 Ok(Node::Number(n).boxed())
             }
+            Token::Not => {
+                self.next_token()?;
+                Ok(Node::Not(self.expr(UNARY_PREC)?).boxed())
+            }
+            Token::Sub => {
+                self.next_token()?;
+                Ok(Node::Neg(self.expr(UNARY_PREC)?).boxed())
+            }
+            Token::Add => {
+                self.next_token()?;
+                self.expr(UNARY_PREC)
+            }
             Token::Symbol(symbol) => {
                 self.next_token()?;
+                let symbol_span = self.curr_span;
+                // u32[expr]
+                if let Some(ty) = DerefType::from_name(&symbol)
+                    && self.peek_token()? == Token::OpenBrackets
+                {
+                    let open = self.expect('[')?;
+                    let e = self.expr(1)?;
+                    self.expect_closing(']', '[', open)?;
+                    return Ok(Node::TypedDereference(ty, e).boxed());
+                }
                 match self.peek_token()? {
-                    // sin(expr)
+                    // sin(expr), sig("game.exe", "48 8B ??")
                     Token::LParent => {
-                        self.expect('(')?;
-                        let e = self.expr(1)?;
-                        self.expect(')')?;
-                        self.function(&symbol, e)
+                        let open = self.expect('(')?;
+                        let args = self.arguments(open)?;
+                        self.function(&symbol, symbol_span.to(self.curr_span), args)
+                    }
+                    Token::Symbol(other) => Err(ParseDiagnostic::new(
+                        format!("{}'{other}'", obfstr!("Unexpected symbol ")),
+                        self.peek_span()?,
+                    )
+                    .with_help(format!(
+                        "{}'{symbol}'{}'{other}'?",
+                        obfstr!("missing operator between "),
+                        obfstr!(" and ")
+                    ))
+                    .into()),
+                    // game!CreateInterface
+                    Token::Bang => self.export(symbol),
+                    Token::Arrow => {
+                        // lexer symbol is always a name
+                        let path = FieldPath::from_word(&symbol, symbol_span).unwrap();
+                        Ok(Node::Path(self.field_path(path)?).boxed())
+                    }
+                    _ => {
+                        self.paths
+                            .extend(FieldPath::from_word(&symbol, symbol_span));
+                        Ok(Node::Var(symbol).boxed())
                     }
-                    Token::Symbol(symbol_2) => match symbol_2.as_str() {
-                        "let" => {
-                            // let a = 10
-                            self.next_token()?;
-                            self.expect('=')?;
-                            let e = self.expr(1)?;
-                            Ok(Node::Assignment(symbol_2, e).boxed())
-                        }
-                        other => eyre::bail!("Two consecutive symbols: {other}"),
-                    },
-                    _ => Ok(Node::Var(symbol).boxed()),
                 }
             }
             Token::ModuleSymbol(v) => {
                 self.next_token()?;
-                Ok(Node::ModuleSymbol(v).boxed())
+                if self.peek_token()? == Token::Bang {
+                    return self.export(v);
+                }
+                match FieldPath::from_word(&v, self.curr_span) {
+                    Some(path) => Ok(Node::Path(self.field_path(path)?).boxed()),
+                    // 1337.dll
+                    None => Ok(Node::ModuleSymbol(v).boxed()),
+                }
+            }
+            // &Class.field
+            Token::And => {
+                self.next_token()?;
+                self.next_token()?;
+                let path = match &self.curr {
+                    Token::Symbol(w) | Token::ModuleSymbol(w) => {
+                        FieldPath::from_word(w, self.curr_span)
+                    }
+                    _ => None,
+                };
+                let Some(path) = path else {
+                    return Err(ParseDiagnostic::expected(
+                        obfstr!("class or field"),
+                        self.describe(&self.curr, self.curr_span),
+                        self.curr_span,
+                    )
+                    .with_help(obfstr!(
+                        "'&' take address of a class field, like &Player->health"
+                    ))
+                    .into());
+                };
+                Ok(Node::AddressOf(self.field_path(path)?).boxed())
+            }
+            t => {
+                let span = self.peek_span()?;
+                let diagnostic =
+                    ParseDiagnostic::expected(obfstr!("expression"), self.describe(&t, span), span);
+                Err(match t {
+                    Token::Eof => diagnostic.with_help(obfstr!("expression is incomplete")),
+                    Token::Str(_) => {
+                        diagnostic.with_help(obfstr!("strings only go in function arguments"))
+                    }
+                    _ => diagnostic,
+                }
+                .into())
             }
-            Token::Eof => Ok(Node::Number(0).boxed()),
-            t => eyre::bail!("Unrecognized atom {t}"),
         }
     }
 
@@ -104,6 +287,7 @@ impl<'a> AddressParser<'a> {
     fn operation(
         &mut self,
         op: Token,
+        span: Span,
         lhs: Box<Node>,
         rhs: Box<Node>,
     ) -> AddressParserResult<Box<Node>> {
@@ -113,46 +297,254 @@ impl<'a> AddressParser<'a> {
             Token::Mul => Node::Mul(lhs, rhs).boxed(),
             Token::Div => Node::Div(lhs, rhs).boxed(),
             Token::Pow => Node::Pow(lhs, rhs).boxed(),
+            Token::Mod => Node::Mod(lhs, rhs).boxed(),
+            Token::And => Node::And(lhs, rhs).boxed(),
+            Token::Or => Node::Or(lhs, rhs).boxed(),
+            Token::Shl => Node::Shl(lhs, rhs).boxed(),
+            Token::Shr => Node::Shr(lhs, rhs).boxed(),
+            Token::EqEq => Node::Eq(lhs, rhs).boxed(),
+            Token::NotEq => Node::Ne(lhs, rhs).boxed(),
+            Token::Less => Node::Lt(lhs, rhs).boxed(),
+            Token::LessEq => Node::Le(lhs, rhs).boxed(),
+            Token::Greater => Node::Gt(lhs, rhs).boxed(),
+            Token::GreaterEq => Node::Ge(lhs, rhs).boxed(),
 
-            _ => eyre::bail!("Token not valid operation {op}"),
+            _ => {
+                return Err(ParseDiagnostic::new(
+                    format!(
+                        "{}{}",
+                        self.describe(&op, span),
+                        obfstr!(" is not an operator")
+                    ),
+                    span,
+                )
+                .into());
+            }
         })
     }
 
+    /// Rest of `module!symbol` after module name
+    fn export(&mut self, module: String) -> AddressParserResult<Box<Node>> {
+        self.next_token()?;
+        let bang = self.curr_span;
+        self.next_token()?;
+        match &self.curr {
+            Token::Symbol(symbol) | Token::ModuleSymbol(symbol) => {
+                Ok(Node::Export(module, symbol.clone()).boxed())
+            }
+            _ => Err(ParseDiagnostic::expected(
+                obfstr!("symbol name"),
+                self.describe(&self.curr, self.curr_span),
+                self.curr_span,
+            )
+            .with_help(format!(
+                "{}{bang}{}",
+                obfstr!("after '!' at "),
+                obfstr!(", like game.exe!CreateInterface")
+            ))
+            .into()),
+        }
+    }
+
+    /// Rest of `Class.field->field` after its first word
+    fn field_path(&mut self, mut path: FieldPath) -> AddressParserResult<FieldPath> {
+        while self.peek_token()? == Token::Arrow {
+            self.next_token()?;
+            let arrow = self.curr_span;
+            self.next_token()?;
+            let pushed = match &self.curr {
+                Token::Symbol(w) | Token::ModuleSymbol(w) => {
+                    path.push_word(Access::Arrow, w, self.curr_span.start)
+                }
+                _ => None,
+            };
+            if pushed.is_none() {
+                return Err(ParseDiagnostic::expected(
+                    obfstr!("field name"),
+                    self.describe(&self.curr, self.curr_span),
+                    self.curr_span,
+                )
+                .with_help(format!("{}{arrow}", obfstr!("after '->' at ")))
+                .into());
+            }
+        }
+        self.paths.push(path.clone());
+        Ok(path)
+    }
+
+    /// Comma separated, after `(` until `)`
+    fn arguments(&mut self, open: Span) -> AddressParserResult<Vec<Arg>> {
+        let mut args = vec![];
+        if self.peek_token()? == Token::RParent {
+            self.next_token()?;
+            return Ok(args);
+        }
+        loop {
+            let start = self.peek_span()?;
+            match self.peek_token()? {
+                Token::Str(s) => {
+                    self.next_token()?;
+                    args.push(Arg::Str(s, start));
+                }
+                _ => {
+                    let e = self.expr(1)?;
+                    args.push(Arg::Expr(e, start.to(self.curr_span)));
+                }
+            }
+            self.next_token()?;
+            match self.curr {
+                Token::Comma => continue,
+                Token::RParent => return Ok(args),
+                _ => {
+                    return Err(ParseDiagnostic::expected(
+                        obfstr!("',' or ')'"),
+                        self.describe(&self.curr, self.curr_span),
+                        self.curr_span,
+                    )
+                    .with_help(format!("{}{open}", obfstr!("to close '(' at ")))
+                    .into());
+                }
+            }
+        }
+    }
+
     #[tracing::instrument(name = "Function", skip(self))]
-    fn function(&mut self, op: &str, arg: Box<Node>) -> AddressParserResult<Box<Node>> {
-        match op.to_lowercase().as_str() {
-            "sin" | "sine" => Ok(Node::Sin(arg).boxed()),
-            "cos" | "cosine" => Ok(Node::Cos(arg).boxed()),
-            "sqrt" => Ok(Node::Sqrt(arg).boxed()),
-            _ => eyre::bail!("Not support function: {op}"),
+    fn function(&mut self, op: &str, span: Span, args: Vec<Arg>) -> AddressParserResult<Box<Node>> {
+        let name = op.to_lowercase();
+        let usage = match name.as_str() {
+            "sin" | "sine" | "cos" | "cosine" | "sqrt" => format!("{op}(value)"),
+            "rip" => format!("{op}(instruction, displacement offset, instruction length)"),
+            "sig" => format!("{op}(\"module\", \"48 8B 05 ?? ??\")"),
+            _ => {
+                return Err(ParseDiagnostic::new(
+                    format!("{}'{op}'", obfstr!("Unknown function ")),
+                    span,
+                )
+                .with_help(obfstr!("functions are sin, cos, sqrt, rip, sig"))
+                .into());
+            }
+        };
+        let missing = || {
+            ParseDiagnostic::new(format!("{op}{}", obfstr!(": missing argument")), span)
+                .with_help(format!("{}{usage}", obfstr!("usage: ")))
+        };
+
+        let mut args = args.into_iter();
+        let mut expr = || match args.next() {
+            Some(Arg::Expr(e, _)) => Ok(e),
+            Some(Arg::Str(s, span)) => {
+                Err(
+                    ParseDiagnostic::expected(obfstr!("expression"), format!("\"{s}\""), span)
+                        .with_help(format!("{}{usage}", obfstr!("usage: "))),
+                )
+            }
+            None => Err(missing()),
+        };
+        let node = match name.as_str() {
+            "sin" | "sine" => Node::Sin(expr()?),
+            "cos" | "cosine" => Node::Cos(expr()?),
+            "sqrt" => Node::Sqrt(expr()?),
+            // rip(instruction, displacement offset, instruction length)
+            "rip" => Node::Rip(expr()?, expr()?, expr()?),
+            // sig("module", "48 8B 05 ?? ?? ?? ??")
+            _ => {
+                let mut string = || match args.next() {
+                    Some(Arg::Str(s, span)) => Ok((s, span)),
+                    Some(Arg::Expr(_, span)) => Err(ParseDiagnostic::expected(
+                        obfstr!("\"string\""),
+                        obfstr!("expression"),
+                        span,
+                    )
+                    .with_help(format!("{}{usage}", obfstr!("usage: ")))),
+                    None => Err(missing()),
+                };
+                let (module, _) = string()?;
+                let (pattern, pattern_span) = string()?;
+                let signature = Signature::parse(&pattern)
+                    .map_err(|e| ParseDiagnostic::new(e.to_string(), pattern_span))?;
+                Node::Signature(module, signature)
+            }
+        };
+        if let Some(extra) = args.next() {
+            return Err(ParseDiagnostic::new(
+                format!("{op}{}", obfstr!(": too many arguments")),
+                extra.span(),
+            )
+            .with_help(format!("{}{usage}", obfstr!("usage: ")))
+            .into());
         }
+        Ok(node.boxed())
     }
 
-    fn expect(&mut self, expect_token: char) -> AddressParserResult<()> {
+    /// Next token must be `expect_token`, return its span
+    fn expect(&mut self, expect_token: char) -> AddressParserResult<Span> {
         self.next_token()?;
         let curr: char = self.curr.clone().into();
         if expect_token != curr {
-            eyre::bail!("Expected '{expect_token}' but found '{}'", curr)
+            return Err(ParseDiagnostic::expected(
+                format!("'{expect_token}'"),
+                self.describe(&self.curr, self.curr_span),
+                self.curr_span,
+            )
+            .into());
         }
-        Ok(())
+        Ok(self.curr_span)
     }
 
-    fn peek_token(&mut self) -> AddressParserResult<Token> {
+    /// `expect` for the closing half of `open`
+    fn expect_closing(
+        &mut self,
+        close: char,
+        open: char,
+        open_span: Span,
+    ) -> AddressParserResult<Span> {
+        self.expect(close)
+            .map_err(|e| match e.downcast::<ParseDiagnostic>() {
+                Ok(d) => d
+                    .with_help(format!(
+                        "'{open}'{}{open_span}{}'{close}'",
+                        obfstr!(" at "),
+                        obfstr!(" need a matching ")
+                    ))
+                    .into(),
+                Err(e) => e,
+            })
+    }
+
+    /// Token as user wrote it, for error message
+    fn describe(&self, token: &Token, span: Span) -> String {
+        match self.lexer.src.get(span.range()) {
+            Some(text) if !token.is_eof() => format!("'{text}'"),
+            _ => token.describe(),
+        }
+    }
+
+    fn peek(&mut self) -> AddressParserResult<&(Token, Span)> {
         if self.peeked.is_none() {
-            self.peeked = Some(self.lexer.next_token()?);
+            let token = self.lexer.next_token()?;
+            self.peeked = Some((token, self.lexer.span));
         }
-        Ok(self.peeked.clone().unwrap())
+        Ok(self.peeked.as_ref().unwrap())
+    }
+
+    fn peek_token(&mut self) -> AddressParserResult<Token> {
+        Ok(self.peek()?.0.clone())
+    }
+
+    fn peek_span(&mut self) -> AddressParserResult<Span> {
+        Ok(self.peek()?.1)
     }
 
     fn next_token(&mut self) -> AddressParserResult<()> {
-        match self.peeked.take() {
-            Some(token) => {
-                self.curr = token;
-            }
+        let (token, span) = match self.peeked.take() {
+            Some(peeked) => peeked,
             None => {
-                self.curr = self.lexer.next_token()?;
+                let token = self.lexer.next_token()?;
+                (token, self.lexer.span)
             }
-        }
+        };
+        self.curr = token;
+        self.curr_span = span;
         Ok(())
     }
 }
@@ -193,4 +585,261 @@ mod tests {
         let ret = eval.eval(&mut env, &resolver).unwrap();
         println!("Ret={ret}");
     }
+
+    #[test]
+    fn test_precedence() {
+        struct NoMemory;
+        impl AddressResolver for NoMemory {
+            fn module_symbol_to_address(&self, _module_name: &str) -> Option<isize> {
+                None
+            }
+
+            fn dereference(&self, _address: usize) -> Option<isize> {
+                None
+            }
+        }
+        let eval = |src: &str| {
+            AddressParser::new(src)
+                .parse()?
+                .eval(&mut HashMap::new(), &NoMemory)
+        };
+
+        for (src, expected) in [
+            ("1 + 2 * 3", 7),
+            ("10 - 4 - 3", 3),
+            ("2 ^ 3 ^ 2", 512),
+            ("17 % 5 * 2", 4),
+            ("0x1234 & ~0xF", 0x1230),
+            ("~0 >> 60", 0xF),
+            ("1 << 4 + 1", 32),
+            ("0x10 | 0x1 & 0x3", 0x11),
+            ("0xFF & 0xF == 0xF", 1),
+            ("1 < 2", 1),
+            ("2 <= 1", 0),
+            ("2 > 1 == 1", 1),
+            ("1 >= 2", 0),
+            ("2 != 2", 0),
+            ("3 > 2 ? 0x10 : 0x20", 0x10),
+            ("0 ? 1 : 0 ? 2 : 3", 3),
+            ("1 + 1 ? 5 : 6", 5),
+            ("-0x10", -0x10),
+            ("-2 ^ 2", -4),
+            ("-2 * 3", -6),
+            ("4 - -1", 5),
+            ("+3 - +1", 2),
+            ("--5", 5),
+            ("~-1", 0),
+            ("0x10+4", 0x14),
+            ("(0x10)*2", 0x20),
+            ("0b1 << 0o3", 8),
+            ("1 ?2:3", 2),
+        ] {
+            assert_eq!(eval(src).unwrap(), expected, "{src}");
+        }
+
+        for src in ["5 % 0", "1 / 0", "1 << 64", "1 ? 2", "1 ! 2"] {
+            assert!(eval(src).is_err(), "{src}");
+        }
+    }
+
+    #[test]
+    fn test_typed_dereference() {
+        use crate::memory::{
+            ModuleInfo, Protection,
+            snapshot::{MemorySnapshot, SnapshotRegion},
+        };
+
+        let mut bytes = vec![0u8; 0x100];
+        // count, then table of 8 byte entries
+        bytes[0x10..0x14].copy_from_slice(&3u32.to_ne_bytes());
+        bytes[0x38..0x40].copy_from_slice(&0x1234usize.to_ne_bytes());
+        bytes[0x40..0x42].copy_from_slice(&(-2i16).to_ne_bytes());
+        bytes[0x48..0x4C].copy_from_slice(&0xDEADBEEFu32.to_ne_bytes());
+        bytes[0xFF] = 0x7F;
+        let memory = MemorySnapshot::new(
+            vec![SnapshotRegion {
+                base: 0x400000,
+                protection: Protection::READ,
+                bytes,
+            }],
+            vec![ModuleInfo {
+                name: "game.exe".into(),
+                base: 0x400000,
+                size: 0x100,
+            }],
+        );
+        let eval = |src: &str| {
+            AddressParser::new(src)
+                .parse()?
+                .eval(&mut HashMap::new(), &memory)
+        };
+
+        assert_eq!(
+            eval("[game.exe + 0x20 + u32[game.exe + 0x10 ] * 8 ]").unwrap(),
+            0x1234
+        );
+        assert_eq!(eval("i16[game.exe + 0x40 ]").unwrap(), -2);
+        assert_eq!(eval("u16[game.exe + 0x40 ]").unwrap(), 0xFFFE);
+        assert_eq!(eval("ptr32[game.exe + 0x48 ]").unwrap(), 0xDEADBEEF);
+        assert_eq!(
+            eval("i32[game.exe + 0x48 ]").unwrap(),
+            0xDEADBEEFu32 as i32 as isize
+        );
+        // last byte, pointer sized read would run past the region
+        assert_eq!(eval("u8[game.exe + 0xFF ]").unwrap(), 0x7F);
+        assert!(eval("[game.exe + 0xFF ]").is_err());
+        assert!(eval("u16[game.exe + 0xFF ]").is_err());
+        // not a type, still a variable
+        let mut env = HashMap::from([("u33".to_string(), 5)]);
+        assert_eq!(
+            AddressParser::new("u33 + 1")
+                .parse()
+                .unwrap()
+                .eval(&mut env, &memory)
+                .unwrap(),
+            6
+        );
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let diagnostic = |src: &str| {
+            let e = AddressParser::new(src).parse().unwrap_err();
+            e.downcast::<ParseDiagnostic>().unwrap()
+        };
+
+        let d = diagnostic("[0x10 + 4");
+        assert_eq!(d.span, Span::new(9, 9));
+        assert_eq!(d.expected.as_deref(), Some("']'"));
+        assert_eq!(d.found.as_deref(), Some("end of input"));
+        assert_eq!(d.help.as_deref(), Some("'[' at 0..1 need a matching ']'"));
+        assert_eq!(
+            d.render("[0x10 + 4"),
+            "[0x10 + 4\n         ^ Expected ']' but found end of input\n\
+             help: '[' at 0..1 need a matching ']'"
+        );
+
+        let d = diagnostic("(1 + 2]");
+        assert_eq!((d.span, d.found.as_deref()), (Span::new(6, 7), Some("']'")));
+
+        let d = diagnostic("1 +");
+        assert_eq!(d.message, "Expected expression but found end of input");
+
+        let d = diagnostic("0x10 20");
+        assert_eq!(d.message, "Unexpected '20'");
+        assert_eq!(d.span, Span::new(5, 7));
+
+        let d = diagnostic("a b");
+        assert_eq!(d.span, Span::new(2, 3));
+        assert_eq!(
+            d.help.as_deref(),
+            Some("missing operator between 'a' and 'b'?")
+        );
+
+        let d = diagnostic("foo(1)");
+        assert_eq!(
+            (d.message.as_str(), d.span),
+            ("Unknown function 'foo'", Span::new(0, 6))
+        );
+
+        let d = diagnostic("sin(1, 0x20)");
+        assert_eq!(d.span, Span::new(7, 11));
+
+        let d = diagnostic("rip(1, 2)");
+        assert_eq!(d.message, "rip: missing argument");
+
+        let d = diagnostic(r#"sig("a.dll", "48 4G")"#);
+        assert_eq!(d.span, Span::new(13, 20));
+
+        let d = diagnostic(r#"sig(1, "48")"#);
+        assert_eq!(d.span, Span::new(4, 5));
+
+        // lexer errors are diagnostic too, span in bytes
+        let d = diagnostic("ä + 0xZZ");
+        assert_eq!(d.span, Span::new(5, 9));
+        let d = diagnostic(r#"sig("a.dll)"#);
+        assert_eq!(d.message, "Unterminated string");
+
+        // empty address is still 0
+        assert!(AddressParser::new("  ").parse().is_ok());
+    }
+
+    #[test]
+    fn test_statements() {
+        struct NoMemory;
+        impl AddressResolver for NoMemory {
+            fn module_symbol_to_address(&self, module_name: &str) -> Option<isize> {
+                (module_name == "game.exe").then_some(0x400000)
+            }
+
+            fn dereference(&self, address: usize) -> Option<isize> {
+                (address == 0x400100).then_some(0x1000)
+            }
+        }
+        let eval = |src: &str| {
+            let mut env = HashMap::new();
+            let v = AddressParser::new(src).parse()?.eval(&mut env, &NoMemory)?;
+            eyre::Ok((v, env))
+        };
+
+        let (v, env) = eval("base = game.exe + 0x100; [base] + 0x20").unwrap();
+        assert_eq!(v, 0x1020);
+        assert_eq!(env["base"], 0x400100);
+
+        // let assign the name after it
+        let (v, env) = eval("let a = 5; let b = a * 2; a + b").unwrap();
+        assert_eq!((v, env["a"], env["b"]), (15, 5, 10));
+
+        assert_eq!(eval(";; 1;; 2;").unwrap().0, 2);
+        assert_eq!(eval("a = 1 == 1").unwrap().0, 1);
+
+        let d = AddressParser::new("1 + 2 = 3").parse().unwrap_err();
+        let d = d.downcast::<ParseDiagnostic>().unwrap();
+        assert_eq!(
+            (d.message.as_str(), d.span),
+            ("Cannot assign to expression", Span::new(0, 5))
+        );
+        assert!(AddressParser::new("let 1 = 2").parse().is_err());
+        assert!(AddressParser::new("let a == 5").parse().is_err());
+        assert!(AddressParser::new("a = 1 b = 2").parse().is_err());
+    }
+
+    #[test]
+    fn test_exports() {
+        struct Exports;
+        impl AddressResolver for Exports {
+            fn module_symbol_to_address(&self, _module_name: &str) -> Option<isize> {
+                None
+            }
+
+            fn dereference(&self, _address: usize) -> Option<isize> {
+                None
+            }
+
+            fn export_to_address(&self, module_name: &str, symbol_name: &str) -> Option<isize> {
+                match (module_name, symbol_name) {
+                    ("game.exe", "CreateInterface") => Some(0x401000),
+                    ("libgame.so", "g_world") => Some(0x7F0000),
+                    _ => None,
+                }
+            }
+        }
+        let eval = |src: &str| {
+            AddressParser::new(src)
+                .parse()?
+                .eval(&mut HashMap::new(), &Exports)
+        };
+
+        assert_eq!(eval("game.exe!CreateInterface + 8").unwrap(), 0x401008);
+        assert_eq!(eval("libgame.so ! g_world").unwrap(), 0x7F0000);
+        assert_eq!(eval("1 != 2").unwrap(), 1);
+        let e = eval("game.exe!Missing").unwrap_err();
+        assert_eq!(e.to_string(), "Unknown symbol: game.exe!Missing");
+
+        let d = AddressParser::new("game.exe! + 1").parse().unwrap_err();
+        let d = d.downcast::<ParseDiagnostic>().unwrap();
+        assert_eq!(d.expected.as_deref(), Some("symbol name"));
+        assert_eq!(d.span, Span::new(10, 11));
+        assert!(AddressParser::new("!g_world").parse().is_err());
+    }
 }
@@ -0,0 +1,101 @@
+use super::Span;
+
+/// How a step reach its field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// `a.b`, field inside
+    Dot,
+    /// `a->b`, field behind pointer
+    Arrow,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathSegment {
+    pub access: Access,
+    pub name: String,
+    pub span: Span,
+}
+
+/// `Class.field->field`, root is a class name
+///
+/// Parser only know the shape, `AddressResolver::resolve_path` give it meaning
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldPath {
+    pub root: String,
+    pub root_span: Span,
+    pub fields: Vec<PathSegment>,
+}
+
+impl FieldPath {
+    /// `a.b.c` word from lexer, None when a part is not a name like `1.dll`
+    pub fn from_word(word: &str, span: Span) -> Option<Self> {
+        let (root, rest) = match word.split_once('.') {
+            Some((root, rest)) => (root, Some(rest)),
+            None => (word, None),
+        };
+        if !is_name(root) {
+            return None;
+        }
+        let mut path = Self {
+            root: root.to_owned(),
+            root_span: Span::new(span.start, span.start + root.len()),
+            fields: vec![],
+        };
+        if let Some(rest) = rest {
+            path.push_word(Access::Dot, rest, span.start + root.len() + 1)?;
+        }
+        Some(path)
+    }
+
+    /// Append `b.c` word starting at byte `start`, `b` reached by `access`
+    pub fn push_word(&mut self, access: Access, word: &str, start: usize) -> Option<()> {
+        let mut access = access;
+        let mut pos = start;
+        for part in word.split('.') {
+            if !is_name(part) {
+                return None;
+            }
+            self.fields.push(PathSegment {
+                access,
+                name: part.to_owned(),
+                span: Span::new(pos, pos + part.len()),
+            });
+            access = Access::Dot;
+            pos += part.len() + 1;
+        }
+        Some(())
+    }
+
+    /// Only `.` steps, same text could be a module name
+    pub fn is_dotted(&self) -> bool {
+        self.fields.iter().all(|f| f.access == Access::Dot)
+    }
+}
+
+impl std::fmt::Display for FieldPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.root)?;
+        for field in &self.fields {
+            match field.access {
+                Access::Dot => write!(f, ".{}", field.name)?,
+                Access::Arrow => write!(f, "->{}", field.name)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where a path lead
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldPlace {
+    pub address: usize,
+    /// of last field, None when path is the class itself
+    pub size: Option<usize>,
+}
+
+fn is_name(s: &str) -> bool {
+    s.chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
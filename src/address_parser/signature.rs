@@ -0,0 +1,119 @@
+/// Array of bytes pattern, `None` match any byte
+///
+/// Written as hex bytes split by whitespace, `??` or `?` for wildcard:
+/// `48 8B 05 ?? ?? ?? ?? 48 85 C0`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature(Vec<Option<u8>>);
+
+impl Signature {
+    pub fn parse(src: &str) -> eyre::Result<Self> {
+        let bytes = src
+            .split_whitespace()
+            .map(|b| match b {
+                "?" | "??" => Ok(None),
+                b if b.len() == 2 => u8::from_str_radix(b, 16)
+                    .map(Some)
+                    .map_err(|_| eyre::eyre!("{}{b}", obfstr!("Invalid signature byte: "))),
+                b => eyre::bail!("{}{b}", obfstr!("Invalid signature byte: ")),
+            })
+            .collect::<eyre::Result<Vec<_>>>()?;
+        if bytes.is_empty() {
+            eyre::bail!(obfstr!("Empty signature"));
+        }
+        Ok(Self(bytes))
+    }
+
+    pub fn size(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn matches(&self, bytes: &[u8]) -> bool {
+        bytes.len() >= self.size()
+            && self
+                .0
+                .iter()
+                .zip(bytes)
+                .all(|(p, b)| p.is_none_or(|p| p == *b))
+    }
+
+    /// Offset of first match in `haystack`
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        haystack.windows(self.size()).position(|w| self.matches(w))
+    }
+}
+
+impl std::fmt::Display for Signature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, b) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            match b {
+                Some(b) => write!(f, "{b:02X}")?,
+                None => write!(f, "??")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        address_parser::AddressParser,
+        memory::{
+            ModuleInfo, Protection,
+            snapshot::{MemorySnapshot, SnapshotRegion},
+        },
+    };
+
+    #[test]
+    fn test_signature() {
+        let sig = Signature::parse("48 8B 05 ?? ?? ?? ?? 48 85 C0").unwrap();
+        assert_eq!(sig.size(), 10);
+        assert_eq!(sig.to_string(), "48 8B 05 ?? ?? ?? ?? 48 85 C0");
+        assert!(Signature::parse("").is_err());
+        assert!(Signature::parse("48 8G").is_err());
+        assert!(Signature::parse("488B").is_err());
+
+        // mov rax, [rip + 0x1000] at 0x400020
+        let mut code = vec![0xCCu8; 0x1000];
+        code[0x20..0x2A]
+            .copy_from_slice(&[0x48, 0x8B, 0x05, 0x00, 0x10, 0x00, 0x00, 0x48, 0x85, 0xC0]);
+        assert_eq!(sig.find(&code), Some(0x20));
+
+        let memory = MemorySnapshot::new(
+            vec![SnapshotRegion {
+                base: 0x400000,
+                protection: Protection::READ,
+                bytes: code,
+            }],
+            vec![ModuleInfo {
+                name: "game.exe".into(),
+                base: 0x400000,
+                size: 0x1000,
+            }],
+        );
+        let eval = |src: &str| {
+            AddressParser::new(src)
+                .parse()?
+                .eval(&mut HashMap::new(), &memory)
+        };
+
+        assert_eq!(
+            eval(r#"sig("game.exe", "48 8B 05 ?? ?? ?? ?? 48 85 C0")"#).unwrap(),
+            0x400020
+        );
+        // next instruction 0x400027 + displacement
+        assert_eq!(
+            eval(r#"rip(sig("game.exe", "48 8B 05 ?? ?? ?? ??"), 3, 7)"#).unwrap(),
+            0x401027
+        );
+        assert!(eval(r#"sig("game.exe", "48 8B 06")"#).is_err());
+        assert!(eval(r#"sig("other.dll", "48 8B 05")"#).is_err());
+        assert!(eval(r#"sig("game.exe")"#).is_err());
+    }
+}
@@ -9,11 +9,36 @@ pub enum Token {
     Mul,
     Div,
     Pow,
+    Mod,
+    // bitwise
+    And,
+    Or,
+    Not,
+    Shl,
+    Shr,
+    // comparison
+    EqEq,
+    NotEq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    // a ? b : c
+    Question,
+    Colon,
+    // Class->field
+    Arrow,
+    // module!symbol
+    Bang,
     Equals,
+    Comma,
+    Semicolon,
     Number(isize),
     // sin cos
     Symbol(String),
     ModuleSymbol(String),
+    // "quoted"
+    Str(String),
     Eof,
 }
 
@@ -22,17 +47,60 @@ impl Token {
         matches!(self, &Token::Eof)
     }
 
-    // not understand
-    pub fn info(&self) -> Option<(usize, usize)> {
+    /// For error message, quoted
+    pub fn describe(&self) -> String {
         match self {
-            Token::Add | Token::Sub => Some((10, 0)),
-            Token::Mul | Token::Div => Some((20, 0)),
-            Token::Pow => Some((30, 1)),
+            Token::Eof => obfstr!("end of input").to_string(),
+            Token::Str(s) => format!("\"{s}\""),
+            t => format!("'{t}'"),
+        }
+    }
+
+    /// Binary operator precedence and associativity, higher bind tighter
+    ///
+    /// | prec | operators             | assoc |
+    /// |------|-----------------------|-------|
+    /// | 1    | `a ? b : c`           | right |
+    /// | 5    | `== != < <= > >=`     | left  |
+    /// | 6    | `\|`                  | left  |
+    /// | 7    | `&`                   | left  |
+    /// | 8    | `<< >>`               | left  |
+    /// | 10   | `+ -`                 | left  |
+    /// | 20   | `* / %`               | left  |
+    /// | 25   | unary `~ - +`         |       |
+    /// | 30   | `^` (power)           | right |
+    ///
+    /// Same order as Rust, comparison bind looser than bitwise so
+    /// `[x] & 0xF == 0` mean `([x] & 0xF) == 0`
+    pub fn info(&self) -> Option<(usize, Assoc)> {
+        match self {
+            Token::Question => Some((1, Assoc::Right)),
+            Token::EqEq
+            | Token::NotEq
+            | Token::Less
+            | Token::LessEq
+            | Token::Greater
+            | Token::GreaterEq => Some((5, Assoc::Left)),
+            Token::Or => Some((6, Assoc::Left)),
+            Token::And => Some((7, Assoc::Left)),
+            Token::Shl | Token::Shr => Some((8, Assoc::Left)),
+            Token::Add | Token::Sub => Some((10, Assoc::Left)),
+            Token::Mul | Token::Div | Token::Mod => Some((20, Assoc::Left)),
+            Token::Pow => Some((30, Assoc::Right)),
             _ => None,
         }
     }
 }
 
+/// Operand of unary `~ - +`, between `* / %` and `^`, so `-2 ^ 2` is -4
+pub const UNARY_PREC: usize = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Assoc {
+    Left,
+    Right,
+}
+
 impl Into<char> for Token {
     fn into(self) -> char {
         match self {
@@ -45,10 +113,26 @@ impl Into<char> for Token {
             Token::Mul => '*',
             Token::Div => '/',
             Token::Pow => '^',
+            Token::Mod => '%',
+            Token::And => '&',
+            Token::Or => '|',
+            Token::Not => '~',
+            // first char, Display write the whole operator
+            Token::Shl | Token::Less | Token::LessEq => '<',
+            Token::Shr | Token::Greater | Token::GreaterEq => '>',
+            Token::EqEq => '=',
+            Token::NotEq => '!',
+            Token::Question => '?',
+            Token::Colon => ':',
+            Token::Arrow => '-',
+            Token::Bang => '!',
             Token::Equals => '=',
+            Token::Comma => ',',
+            Token::Semicolon => ';',
             Token::Number(_) => 'N',
             Token::Symbol(_) => 'S',
             Token::ModuleSymbol(_) => 'M',
+            Token::Str(_) => '"',
             Token::Eof => 'E',
         }
     }
@@ -66,10 +150,26 @@ impl Into<char> for &Token {
             Token::Mul => '*',
             Token::Div => '/',
             Token::Pow => '^',
+            Token::Mod => '%',
+            Token::And => '&',
+            Token::Or => '|',
+            Token::Not => '~',
+            // first char, Display write the whole operator
+            Token::Shl | Token::Less | Token::LessEq => '<',
+            Token::Shr | Token::Greater | Token::GreaterEq => '>',
+            Token::EqEq => '=',
+            Token::NotEq => '!',
+            Token::Question => '?',
+            Token::Colon => ':',
+            Token::Arrow => '-',
+            Token::Bang => '!',
             Token::Equals => '=',
+            Token::Comma => ',',
+            Token::Semicolon => ';',
             Token::Number(_) => 'N',
             Token::Symbol(_) => 'S',
             Token::ModuleSymbol(_) => 'M',
+            Token::Str(_) => '"',
             Token::Eof => 'E',
         }
     }
@@ -77,7 +177,22 @@ impl Into<char> for &Token {
 
 impl std::fmt::Display for Token {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let c: char = self.into();
-        write!(f, "{c}")
+        match self {
+            Token::Number(n) => write!(f, "{n:#X}"),
+            Token::Symbol(s) | Token::ModuleSymbol(s) => write!(f, "{s}"),
+            Token::Str(s) => write!(f, "\"{s}\""),
+            Token::Eof => write!(f, "{}", obfstr!("end of input")),
+            Token::Shl => write!(f, "<<"),
+            Token::Shr => write!(f, ">>"),
+            Token::LessEq => write!(f, "<="),
+            Token::GreaterEq => write!(f, ">="),
+            Token::EqEq => write!(f, "=="),
+            Token::NotEq => write!(f, "!="),
+            Token::Arrow => write!(f, "->"),
+            _ => {
+                let c: char = self.into();
+                write!(f, "{c}")
+            }
+        }
     }
 }
@@ -1,12 +1,19 @@
+use std::time::Instant;
+
 use eframe::egui::{Color32, Theme};
 
 use crate::{
     field::{FieldResponse, allocate_padding},
     global_state::global_state,
+    memory::{gdb::GdbRemoteMemory, open_dump, snapshot::MemorySnapshot},
     ui::{
         class_list_panel::ClassListPanel,
+        globals_panel::GlobalsPanel,
         inspector_panel::InspectorPanel,
         modals::{Modals, ModelResponse},
+        pointer_scan_panel::PointerScanPanel,
+        process_panel::ProcessPanel,
+        scanner_panel::ScannerPanel,
         toolbar_panel::{ToolBarPanel, ToolBarResponse},
     },
     utils::offset_align_to,
@@ -17,6 +24,10 @@ pub struct MakeClassApp {
     inspector: InspectorPanel,
     toolbar: ToolBarPanel,
     modals: Modals,
+    process_panel: ProcessPanel,
+    scanner_panel: ScannerPanel,
+    pointer_scan_panel: PointerScanPanel,
+    globals_panel: GlobalsPanel,
 }
 
 impl MakeClassApp {
@@ -26,6 +37,10 @@ impl MakeClassApp {
             inspector: InspectorPanel::default(),
             toolbar: ToolBarPanel::default(),
             modals: Modals::default(),
+            process_panel: ProcessPanel::default(),
+            scanner_panel: ScannerPanel::default(),
+            pointer_scan_panel: PointerScanPanel::default(),
+            globals_panel: GlobalsPanel::default(),
         }
     }
 
@@ -246,12 +261,106 @@ impl MakeClassApp {
                     global_state().toasts.error(format!("{e}"));
                 }
             }
+            ToolBarResponse::OpenProcessList => {
+                self.process_panel.open = true;
+            }
+            ToolBarResponse::OpenScanner => {
+                self.scanner_panel.open = true;
+            }
+            ToolBarResponse::OpenPointerScan => {
+                self.pointer_scan_panel.open = true;
+            }
+            ToolBarResponse::OpenGlobals => {
+                self.globals_panel.open = true;
+            }
+            ToolBarResponse::AttachLastProcess => {
+                self.process_panel.attach_last();
+            }
+            ToolBarResponse::DetachProcess => {
+                self.process_panel.detach();
+            }
+            ToolBarResponse::OpenDump => {
+                let Some(path) = rfd::FileDialog::new()
+                    .add_filter(
+                        obfstr!("Memory dump"),
+                        &["mcsnap", "core", "dmp", "exe", "dll", "so"],
+                    )
+                    .add_filter(obfstr!("All files"), &["*"])
+                    .pick_file()
+                else {
+                    return;
+                };
+
+                match open_dump(&path) {
+                    Ok(memory) => {
+                        self.process_panel.detach();
+                        global_state().use_memory(memory, path.display().to_string());
+                    }
+                    Err(e) => {
+                        global_state().toasts.error(format!(
+                            "{}{}\n{e}",
+                            obfstr!("Failed open dump "),
+                            path.display()
+                        ));
+                    }
+                }
+            }
+            ToolBarResponse::ConnectGdb(target) => match GdbRemoteMemory::connect(&target) {
+                Ok(remote) => {
+                    self.process_panel.detach();
+                    global_state()
+                        .use_memory(Box::new(remote), format!("{}{target}", obfstr!("gdb ")));
+                }
+                Err(e) => {
+                    global_state().toasts.error(format!("{e}"));
+                }
+            },
+            ToolBarResponse::SaveSnapshot(depth) => {
+                let Some(path) = rfd::FileDialog::new()
+                    .add_filter(obfstr!("Memory snapshot"), &["mcsnap"])
+                    .set_file_name(obfstr!("memory.mcsnap"))
+                    .save_file()
+                else {
+                    return;
+                };
+
+                let state = global_state();
+                let snapshot = MemorySnapshot::capture(&*state.memory, &state.class_list, depth);
+                match snapshot.save(&path) {
+                    Ok(()) => {
+                        state.toasts.info(format!(
+                            "{}{}{}",
+                            snapshot.regions().len(),
+                            obfstr!(" regions saved to "),
+                            path.display()
+                        ));
+                    }
+                    Err(e) => {
+                        state
+                            .toasts
+                            .error(format!("{}{e}", obfstr!("Failed save snapshot\n")));
+                    }
+                }
+            }
         };
     }
 }
 impl eframe::App for MakeClassApp {
     fn update(&mut self, ctx: &eframe::egui::Context, _frame: &mut eframe::Frame) {
         ctx.set_theme(Theme::Dark);
+        let poller = &global_state().poller;
+        if poller.is_active() {
+            ctx.request_repaint_after(poller.interval());
+        } else if !poller.is_paused() {
+            // everything drawn this frame see the same memory
+            global_state().memory.invalidate_cache();
+        }
+        // paused keep last pages, view stay frozen
+
+        // live class addresses, before anything draw with them
+        if let Some(next) = global_state().reevaluate_live(Instant::now()) {
+            ctx.request_repaint_after(next);
+        }
         let mut toolbar_response = self.toolbar.show(ctx);
 
         self.class_list_panel.show(ctx);
@@ -290,6 +399,11 @@ impl eframe::App for MakeClassApp {
             self.progress_toolbar_response(toolbar_response);
         }
 
+        self.process_panel.show(ctx);
+        self.scanner_panel.show(ctx);
+        self.pointer_scan_panel.show(ctx);
+        self.globals_panel.show(ctx);
+
         let mut style = (*ctx.style()).clone();
         let saved = style.clone();
         style.visuals.widgets.noninteractive.bg_fill = Color32::from_rgb(0x10, 0x10, 0x10);
@@ -1,8 +1,16 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
 use crate::field::{
     Field, class_instance::ClassInstanceField, class_pointer::ClassPointerField, hex::HexField,
 };
 
-use super::{Class, ClassId};
+use super::{
+    Class, ClassId, ReevalPolicy,
+    resolver::{Rename, class_dependencies, rename_in_expr},
+};
 
 pub struct ClassList {
     classes: Vec<Class>,
@@ -102,4 +110,87 @@ impl ClassList {
     pub fn get_class_by_name(&self, name: impl AsRef<str>) -> Option<&Class> {
         self.classes.iter().find(|c| c.name == name.as_ref())
     }
+
+    /// Re-evaluate every class address expression, classes used by name go first
+    ///
+    /// return class name with its error for each failed class
+    pub fn reevaluate_addresses(&self) -> Vec<(String, eyre::Report)> {
+        let now = Instant::now();
+        self.evaluation_order()
+            .into_iter()
+            .filter_map(|c| c.reevaluate_address(now).err().map(|e| (c.name.clone(), e)))
+            .collect()
+    }
+
+    /// Some class policy want its address again now
+    pub fn is_live_due(&self, now: Instant) -> bool {
+        self.classes
+            .iter()
+            .any(|c| c.reevaluate_in(now).is_some_and(|left| left.is_zero()))
+    }
+
+    /// Re-evaluate classes their policy say are due, every frame
+    ///
+    /// return time until next interval class, errors stay in the address string
+    pub fn reevaluate_live(&self, now: Instant) -> Option<Duration> {
+        if self
+            .classes
+            .iter()
+            .all(|c| c.reeval.get() == ReevalPolicy::Manual)
+        {
+            return None;
+        }
+
+        let mut next: Option<Duration> = None;
+        for c in self.evaluation_order() {
+            let Some(mut left) = c.reevaluate_in(now) else {
+                continue;
+            };
+            if left.is_zero() {
+                _ = c.reevaluate_address(now);
+                left = c.reevaluate_in(now).unwrap_or_default();
+            }
+            if let ReevalPolicy::Interval(_) = c.reeval.get() {
+                next = Some(next.map_or(left, |n| n.min(left)));
+            }
+        }
+        next
+    }
+
+    /// Dependencies before dependents, a cycle just keep list order
+    fn evaluation_order(&self) -> Vec<&Class> {
+        fn visit<'a>(
+            list: &'a ClassList,
+            deps: &HashMap<ClassId, HashSet<ClassId>>,
+            id: ClassId,
+            visited: &mut HashSet<ClassId>,
+            order: &mut Vec<&'a Class>,
+        ) {
+            if !visited.insert(id) {
+                return;
+            }
+            for dep in deps.get(&id).into_iter().flatten() {
+                visit(list, deps, *dep, visited, order);
+            }
+            order.extend(list.get_class(id));
+        }
+
+        let deps = class_dependencies(self);
+        let mut visited = HashSet::new();
+        let mut order = vec![];
+        for c in &self.classes {
+            visit(self, &deps, c.id(), &mut visited, &mut order);
+        }
+        order
+    }
+
+    /// Write new name into every class address using the old one
+    pub fn rename_in_addresses(&self, rename: &Rename) {
+        for c in &self.classes {
+            let new = rename_in_expr(self, &c.address.borrow().to_string(), rename);
+            if let Some(new) = new {
+                c.address.borrow_mut().rewrite(new);
+            }
+        }
+    }
 }
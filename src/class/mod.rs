@@ -1,6 +1,10 @@
 pub mod class_list;
+pub mod resolver;
 
-use std::cell::RefCell;
+use std::{
+    cell::{Cell, RefCell},
+    time::{Duration, Instant},
+};
 
 use crate::{
     address::AddressString,
@@ -31,10 +35,47 @@ impl From<usize> for ClassId {
     }
 }
 
+/// When class address expression run again by itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum ReevalPolicy {
+    /// only on enter, backend change or refresh
+    #[default]
+    Manual,
+    EveryFrame,
+    Interval(Duration),
+}
+
+impl ReevalPolicy {
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
+    pub const ALL: [Self; 3] = [
+        Self::Manual,
+        Self::EveryFrame,
+        Self::Interval(Self::DEFAULT_INTERVAL),
+    ];
+
+    /// Same kind, interval length ignored
+    pub fn same_kind(self, other: Self) -> bool {
+        std::mem::discriminant(&self) == std::mem::discriminant(&other)
+    }
+}
+
+impl std::fmt::Display for ReevalPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Manual => "Manual",
+            Self::EveryFrame => "Every frame",
+            Self::Interval(_) => "Interval",
+        };
+        write!(f, "{s}")
+    }
+}
+
 pub struct Class {
     id: ClassId,
     pub name: String,
     pub address: RefCell<AddressString>,
+    pub reeval: Cell<ReevalPolicy>,
+    reevaluated_at: Cell<Option<Instant>>,
     pub fields: Vec<Box<dyn Field>>,
 }
 
@@ -70,6 +111,8 @@ impl Class {
             id: id.into(),
             name: name.into(),
             address: RefCell::new(0.into()),
+            reeval: Default::default(),
+            reevaluated_at: Default::default(),
             fields: create_dummy_fields(),
         }
     }
@@ -79,6 +122,8 @@ impl Class {
             id: id.into(),
             name: name.into(),
             address: RefCell::new(0.into()),
+            reeval: Default::default(),
+            reevaluated_at: Default::default(),
             fields: vec![],
         }
     }
@@ -87,6 +132,25 @@ impl Class {
         self.id
     }
 
+    /// Evaluate address expression again, error stay in `AddressString` too
+    pub fn reevaluate_address(&self, now: Instant) -> eyre::Result<()> {
+        self.reevaluated_at.set(Some(now));
+        self.address.borrow_mut().reevaluate()
+    }
+
+    /// Time left until policy want the address again, None for manual
+    pub fn reevaluate_in(&self, now: Instant) -> Option<Duration> {
+        match self.reeval.get() {
+            ReevalPolicy::Manual => None,
+            ReevalPolicy::EveryFrame => Some(Duration::ZERO),
+            ReevalPolicy::Interval(every) => Some(
+                self.reevaluated_at
+                    .get()
+                    .map_or(Duration::ZERO, |at| every.saturating_sub(now - at)),
+            ),
+        }
+    }
+
     pub fn class_size(&self) -> usize {
         self.fields.iter().map(|f| f.field_size()).sum()
     }
@@ -265,3 +329,34 @@ impl Class {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reeval_policy() {
+        let class = Class::empty(0, "Player");
+        let now = Instant::now();
+        assert_eq!(class.reevaluate_in(now), None);
+
+        class.reeval.set(ReevalPolicy::EveryFrame);
+        assert_eq!(class.reevaluate_in(now), Some(Duration::ZERO));
+
+        // never evaluated yet, due right away
+        class
+            .reeval
+            .set(ReevalPolicy::Interval(Duration::from_millis(500)));
+        assert_eq!(class.reevaluate_in(now), Some(Duration::ZERO));
+        class.reevaluated_at.set(Some(now));
+        assert_eq!(
+            class.reevaluate_in(now + Duration::from_millis(200)),
+            Some(Duration::from_millis(300))
+        );
+        assert_eq!(
+            class.reevaluate_in(now + Duration::from_secs(1)),
+            Some(Duration::ZERO)
+        );
+        assert!(ReevalPolicy::Interval(Duration::ZERO).same_kind(ReevalPolicy::ALL[2]));
+    }
+}
@@ -0,0 +1,348 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    address_parser::{
+        Access, AddressParser, AddressResolver, FieldPath, FieldPlace, Signature, Span,
+    },
+    field::{FieldId, field_tag::FieldTag},
+};
+
+use super::{Class, ClassId, class_list::ClassList};
+
+/// Memory resolver that also know project classes, for `Class.field->field`
+///
+/// Class name resolve to its last evaluated address
+pub struct ProjectResolver<'a> {
+    classes: &'a ClassList,
+    memory: &'a dyn AddressResolver,
+}
+
+impl<'a> ProjectResolver<'a> {
+    pub fn new(classes: &'a ClassList, memory: &'a dyn AddressResolver) -> Self {
+        Self { classes, memory }
+    }
+
+    fn place(&self, path: &FieldPath) -> eyre::Result<FieldPlace> {
+        let (root, steps) = walk(self.classes, path, None)?;
+        // class being evaluated is borrowed mut
+        let Ok(address) = root.address.try_borrow().map(|a| a.address_value()) else {
+            eyre::bail!(
+                "{}{}{}",
+                obfstr!("Address of class "),
+                root.name,
+                obfstr!(" cannot use itself")
+            );
+        };
+
+        let mut address = address;
+        let mut size = None;
+        for (i, (class, pos)) in steps.iter().enumerate() {
+            // class itself stand for its instance, only pointer field deref
+            if i > 0 && path.fields[i].access == Access::Arrow {
+                address = self.memory.dereference(address).ok_or_else(|| {
+                    eyre::eyre!(
+                        "{}{}{}{address:#X}",
+                        obfstr!("Failed dereference "),
+                        path.fields[i - 1].name,
+                        obfstr!(" at ")
+                    )
+                })? as usize;
+            }
+            // pointer came from target memory, can be garbage
+            let offset = class.fields[..*pos]
+                .iter()
+                .map(|f| f.field_size())
+                .sum::<usize>();
+            address = address.checked_add(offset).ok_or_else(|| {
+                eyre::eyre!(
+                    "{}{}{}{address:#X}",
+                    obfstr!("Field "),
+                    path.fields[i].name,
+                    obfstr!(" out of address space, base ")
+                )
+            })?;
+            size = Some(class.fields[*pos].field_size());
+        }
+        Ok(FieldPlace { address, size })
+    }
+}
+
+impl AddressResolver for ProjectResolver<'_> {
+    fn module_symbol_to_address(&self, module_name: &str) -> Option<isize> {
+        self.memory.module_symbol_to_address(module_name)
+    }
+
+    fn export_to_address(&self, module_name: &str, symbol_name: &str) -> Option<isize> {
+        self.memory.export_to_address(module_name, symbol_name)
+    }
+
+    fn generation(&self) -> Option<u64> {
+        self.memory.generation()
+    }
+
+    fn dereference(&self, address: usize) -> Option<isize> {
+        self.memory.dereference(address)
+    }
+
+    fn read_exact(&self, address: usize, buffer: &mut [u8]) -> bool {
+        self.memory.read_exact(address, buffer)
+    }
+
+    fn find_signature(&self, module_name: &str, signature: &Signature) -> Option<isize> {
+        self.memory.find_signature(module_name, signature)
+    }
+
+    fn resolve_path(&self, path: &FieldPath) -> Option<eyre::Result<FieldPlace>> {
+        find_class(self.classes, &path.root, None)?;
+        Some(self.place(path))
+    }
+}
+
+/// Class or field got a new name, `old` is what expressions still use
+pub enum Rename {
+    Class {
+        class: ClassId,
+        old: String,
+    },
+    Field {
+        class: ClassId,
+        field: FieldId,
+        old: String,
+    },
+}
+
+/// Root class and (owner class, field position) of each path field, no memory touched
+///
+/// With `rename` the old name still match
+fn walk<'a>(
+    classes: &'a ClassList,
+    path: &FieldPath,
+    rename: Option<&Rename>,
+) -> eyre::Result<(&'a Class, Vec<(&'a Class, usize)>)> {
+    let root = find_class(classes, &path.root, rename)
+        .ok_or_else(|| eyre::eyre!("{}{}", obfstr!("Unknown class: "), path.root))?;
+
+    let mut steps: Vec<(&Class, usize)> = vec![];
+    let mut class = root;
+    for (i, segment) in path.fields.iter().enumerate() {
+        // root class stand for its instance
+        if i == 0 && segment.access == Access::Arrow {
+            eyre::bail!("{}{}", path.root, obfstr!(" is not a pointer, use ."))
+        }
+        if i > 0 {
+            let (owner, pos) = steps[i - 1];
+            let field = &owner.fields[pos];
+            let prev = &path.fields[i - 1].name;
+            match (segment.access, field.field_tag()) {
+                (Access::Dot, FieldTag::ClassInstance)
+                | (Access::Arrow, FieldTag::ClassPointer) => {}
+                (Access::Dot, FieldTag::ClassPointer) => {
+                    eyre::bail!("{prev}{}", obfstr!(" is a pointer, use ->"))
+                }
+                (Access::Arrow, FieldTag::ClassInstance) => {
+                    eyre::bail!("{prev}{}", obfstr!(" is not a pointer, use ."))
+                }
+                _ => eyre::bail!("{prev}{}", obfstr!(" is not a class")),
+            }
+            class = field
+                .class_id()
+                .and_then(|id| classes.get_class(id))
+                .ok_or_else(|| eyre::eyre!("{prev}{}", obfstr!(" refer to missing class")))?;
+        }
+        let pos = find_field(class, &segment.name, rename).ok_or_else(|| {
+            eyre::eyre!(
+                "{}{}{}{}",
+                obfstr!("Class "),
+                class.name,
+                obfstr!(" has no field "),
+                segment.name
+            )
+        })?;
+        steps.push((class, pos));
+    }
+    Ok((root, steps))
+}
+
+fn find_class<'a>(
+    classes: &'a ClassList,
+    name: &str,
+    rename: Option<&Rename>,
+) -> Option<&'a Class> {
+    match rename {
+        Some(Rename::Class { class, old }) if old == name => classes.get_class(*class),
+        _ => classes.get_class_by_name(name),
+    }
+}
+
+fn find_field(class: &Class, name: &str, rename: Option<&Rename>) -> Option<usize> {
+    if let Some(Rename::Field {
+        class: class_id,
+        field,
+        old,
+    }) = rename
+        && *class_id == class.id()
+        && old == name
+    {
+        return class.field_pos(*field);
+    }
+    class
+        .fields
+        .iter()
+        .position(|f| f.name().is_some_and(|n| n == name))
+}
+
+/// Expression with renamed class or field written with its new name, None when nothing to change
+pub fn rename_in_expr(classes: &ClassList, src: &str, rename: &Rename) -> Option<String> {
+    let mut parser = AddressParser::new(src);
+    parser.parse().ok()?;
+
+    let mut edits: Vec<(Span, String)> = vec![];
+    for path in &parser.paths {
+        let Ok((root, steps)) = walk(classes, path, Some(rename)) else {
+            continue;
+        };
+        if path.root != root.name {
+            edits.push((path.root_span, root.name.clone()));
+        }
+        for ((class, pos), segment) in steps.iter().zip(&path.fields) {
+            if let Some(name) = class.fields[*pos].name()
+                && segment.name != name
+            {
+                edits.push((segment.span, name));
+            }
+        }
+    }
+    if edits.is_empty() {
+        return None;
+    }
+
+    // back to front so spans stay valid
+    edits.sort_by_key(|(span, _)| std::cmp::Reverse(span.start));
+    edits.dedup_by_key(|(span, _)| span.start);
+    let mut out = src.to_owned();
+    for (span, name) in edits {
+        out.replace_range(span.range(), &name);
+    }
+    Some(out)
+}
+
+/// Class names each class address use, for evaluation order
+pub fn class_dependencies(classes: &ClassList) -> HashMap<ClassId, HashSet<ClassId>> {
+    classes
+        .classes()
+        .iter()
+        .map(|c| {
+            let src = c.address.borrow().to_string();
+            let mut parser = AddressParser::new(&src);
+            _ = parser.parse();
+            let deps = parser
+                .paths
+                .iter()
+                .filter_map(|p| classes.get_class_by_name(&p.root))
+                .map(|d| d.id())
+                .filter(|id| *id != c.id())
+                .collect();
+            (c.id(), deps)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        address::AddressString,
+        field::{Field, class_pointer::ClassPointerField, hex::HexField, int::IntField},
+        memory::{
+            ModuleInfo, Protection,
+            snapshot::{MemorySnapshot, SnapshotRegion},
+        },
+    };
+
+    #[test]
+    fn test_project_paths() {
+        let mut list = ClassList::default();
+        let player = list.add_empty_class("Player");
+        let world = list.add_empty_class("World");
+
+        let class = list.get_class_mut(player).unwrap();
+        class.add_field(HexField::<64>::new().boxed());
+        let health = IntField::<32>::signed_default();
+        health.set_name("health".into());
+        let health_id = health.id();
+        class.add_field(health.boxed());
+
+        let class = list.get_class_mut(world).unwrap();
+        class.address.replace(AddressString::from(0x400000));
+        class.add_field(HexField::<64>::new().boxed());
+        let local = ClassPointerField::new_with_class_id(player);
+        local.set_name("local".into());
+        class.add_field(local.boxed());
+
+        // World at 0x400000, World.local at +8 point to Player at 0x400100
+        // Bad at 0x400180, Bad.player hold garbage
+        let bad = list.add_empty_class("Bad");
+        let class = list.get_class_mut(bad).unwrap();
+        class.address.replace(AddressString::from(0x400180));
+        let pointer = ClassPointerField::new_with_class_id(player);
+        pointer.set_name("player".into());
+        class.add_field(pointer.boxed());
+
+        let mut bytes = vec![0u8; 0x200];
+        bytes[8..16].copy_from_slice(&0x400100u64.to_le_bytes());
+        bytes[0x180..0x188].copy_from_slice(&(u64::MAX - 3).to_le_bytes());
+        bytes[0x108..0x10C].copy_from_slice(&(-5i32).to_le_bytes());
+        let memory = MemorySnapshot::new(
+            vec![SnapshotRegion {
+                base: 0x400000,
+                protection: Protection::READ,
+                bytes,
+            }],
+            vec![ModuleInfo {
+                name: "game.exe".into(),
+                base: 0x400000,
+                size: 0x200,
+            }],
+        );
+
+        let resolver = ProjectResolver::new(&list, &memory);
+        let eval = |src: &str| {
+            AddressParser::new(src)
+                .parse()?
+                .eval(&mut HashMap::new(), &resolver)
+        };
+        assert_eq!(eval("World").unwrap(), 0x400000);
+        assert_eq!(eval("World.local").unwrap(), 0x400100);
+        assert_eq!(eval("&World.local").unwrap(), 0x400008);
+        assert_eq!(eval("&World.local->health").unwrap(), 0x400108);
+        assert_eq!(eval("World.local->health").unwrap(), 0xFFFF_FFFB);
+        assert_eq!(eval("i32[&World.local->health]").unwrap(), -5);
+        assert_eq!(eval("game.exe + 1").unwrap(), 0x400001);
+        let e = eval("World->local->health").unwrap_err();
+        assert!(e.to_string().contains("World is not a pointer"));
+        assert!(eval("Bad.player->health").is_err());
+        assert!(eval("World.local.health").is_err());
+        assert!(eval("World.missing").is_err());
+        assert!(eval("&(World)").is_err());
+
+        // rename field then class, expressions follow
+        let src = "&World.local->health + World.local - Player";
+        list.get_class(player).unwrap().fields[1].set_name("hp".into());
+        let rename = Rename::Field {
+            class: player,
+            field: health_id,
+            old: "health".into(),
+        };
+        let src = rename_in_expr(&list, src, &rename).unwrap();
+        assert_eq!(src, "&World.local->hp + World.local - Player");
+
+        list.get_class_mut(world).unwrap().name = "Level".into();
+        let rename = Rename::Class {
+            class: world,
+            old: "World".into(),
+        };
+        let src = rename_in_expr(&list, &src, &rename).unwrap();
+        assert_eq!(src, "&Level.local->hp + Level.local - Player");
+        assert_eq!(rename_in_expr(&list, "game.exe + 8", &rename), None);
+    }
+}
@@ -48,9 +48,10 @@ impl Field for BoolField {
         let mut val = 0u8;
         let address = ctx.address + ctx.offset;
 
-        global_state()
+        let readable = global_state()
             .memory
-            .read_buf(address, std::slice::from_mut(&mut val));
+            .read_buf(address, std::slice::from_mut(&mut val))
+            == 1;
 
         let valid_bool = val == 0 || val == 1;
         let mut field_response = None;
@@ -74,6 +75,7 @@ impl Field for BoolField {
                 ui,
                 ctx,
                 &self.state,
+                readable,
                 || {
                     if !valid_bool {
                         (Value::U8(val), Color32::RED)
@@ -81,16 +83,9 @@ impl Field for BoolField {
                         (Value::Bool(val == 1), Color32::GOLD)
                     }
                 },
-                |buf| match buf.to_lowercase().as_str() {
-                    "1" | "true" | "yes" | "on" => {
-                        // TODO write 1 into it
-                        Ok(())
-                    }
-                    "0" | "false" | "no" | "off" => {
-                        // TODO write 0 into it
-                        Ok(())
-                    }
-                    _ => eyre::bail!("Unsupport boolean: {buf}"),
+                |buf| {
+                    let v = Value::parse_for(FieldTag::Bool, buf)?;
+                    global_state().memory.write_buf(address, &v.to_ne_bytes())
                 },
             );
         });
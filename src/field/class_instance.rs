@@ -172,6 +172,10 @@ impl Field for ClassInstanceField {
         Some(&self.state)
     }
 
+    fn class_id(&self) -> Option<ClassId> {
+        Some(self.class_id.get())
+    }
+
     fn field_size(&self) -> usize {
         if let Some(c) = global_state().class_list.get_class(self.class_id.get()) {
             return c.class_size();
@@ -40,10 +40,12 @@ impl ClassPointerField {
         ui: &mut egui::Ui,
         ctx: &mut InspectorContext,
         address: usize,
+        readable: bool,
     ) -> Option<FieldResponse> {
         let class = ctx.class_list.get_class(self.class_id.get());
 
         let mut field_response = None;
+        let field_address = ctx.address + ctx.offset;
         let (text, exists) = if let Some(cl) = class {
             (format!("[{}]", cl.name), true)
         } else {
@@ -72,8 +74,14 @@ impl ClassPointerField {
             ui,
             ctx,
             &self.state,
+            readable,
             || (Value::HexAddress(address), Color32::YELLOW),
-            |_b| eyre::bail!("unimplemented"),
+            |buf| {
+                let v = Value::parse_for(FieldTag::ClassPointer, buf)?;
+                global_state()
+                    .memory
+                    .write_buf(field_address, &v.to_ne_bytes())
+            },
         );
 
         let mut job = LayoutJob::default();
@@ -180,6 +188,10 @@ impl Field for ClassPointerField {
         Some(&self.state)
     }
 
+    fn class_id(&self) -> Option<ClassId> {
+        Some(self.class_id.get())
+    }
+
     fn field_size(&self) -> usize {
         8
     }
@@ -190,7 +202,7 @@ impl Field for ClassPointerField {
         // deref this ptr
         let address = ctx.address + ctx.offset;
         let mut ptr_buf = [0; 8];
-        global_state().memory.read_buf(address, &mut ptr_buf);
+        let readable = global_state().memory.read_buf(address, &mut ptr_buf) == ptr_buf.len();
         let deref_address = usize::from_ne_bytes(ptr_buf);
 
         let collapsing_id = Id::new(format!("{:?}{}{address}", self.id, ctx.inspector_level));
@@ -198,7 +210,7 @@ impl Field for ClassPointerField {
         let state = CollapsingState::load_with_default_open(ui.ctx(), collapsing_id, false);
 
         let body = state
-            .show_header(ui, |ui| self.show_header(ui, ctx, deref_address))
+            .show_header(ui, |ui| self.show_header(ui, ctx, deref_address, readable))
             .body(|ui| self.show_body(ui, ctx, deref_address));
 
         if let Some(new) = body.2.and_then(|inner| inner.inner) {
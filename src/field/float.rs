@@ -33,7 +33,7 @@ impl<const N: usize> Field for FloatField<N> {
     }
 
     fn field_tag(&self) -> FieldTag {
-        if N == 32 {
+        if N == 4 {
             FieldTag::Float32
         } else {
             FieldTag::Float64
@@ -64,7 +64,7 @@ impl<const N: usize> Field for FloatField<N> {
     ) -> Option<super::FieldResponse> {
         let mut buf = [0; N];
         let address = ctx.address + ctx.offset;
-        global_state().memory.read_buf(address, &mut buf);
+        let readable = global_state().memory.read_buf(address, &mut buf) == N;
 
         let mut field_response = None;
 
@@ -87,17 +87,27 @@ impl<const N: usize> Field for FloatField<N> {
                 ui,
                 ctx,
                 &self.state,
-                || (Value::F32(3.3), Color32::WHITE),
-                |buf| match N {
-                    4 => {
-                        let v = buf.parse::<f32>()?;
-                        eyre::bail!("{}{v}", obfstr!("unimplemented write float "))
-                    }
-                    8 => {
-                        let v = buf.parse::<f64>()?;
-                        eyre::bail!("{}{v}", obfstr!("unimplemented write float "))
+                readable,
+                || match N {
+                    4 => (
+                        Value::F32(f32::from_ne_bytes(buf[..].try_into().unwrap())),
+                        Color32::WHITE,
+                    ),
+                    8 => (
+                        Value::F64(f64::from_ne_bytes(buf[..].try_into().unwrap())),
+                        Color32::WHITE,
+                    ),
+                    _ => (
+                        Value::String(format!("Invalid FloatField size {N}")),
+                        Color32::RED,
+                    ),
+                },
+                |buf| {
+                    if N != 4 && N != 8 {
+                        eyre::bail!("Unsupport Float size: {N}")
                     }
-                    _ => eyre::bail!("Unsupport Float size: {N}"),
+                    let v = Value::parse_for(self.field_tag(), buf)?;
+                    global_state().memory.write_buf(address, &v.to_ne_bytes())
                 },
             );
         });
@@ -1,7 +1,9 @@
 use eframe::egui::{Color32, Label, Sense, Ui, text::LayoutJob};
 
 use crate::{
-    global_state::global_state, inspection::InspectorContext, styling::create_text_format,
+    global_state::global_state,
+    inspection::InspectorContext,
+    styling::{UNREADABLE_COLOR, create_text_format, region_kind_color},
 };
 
 use super::{Field, FieldId, FieldResponse, field_tag::FieldTag};
@@ -22,9 +24,27 @@ impl<const N: usize> HexField<N> {
             id: FieldId::next_id(),
         }
     }
-    fn byte_view(&self, ctx: &mut InspectorContext, job: &mut LayoutJob, buf: &[u8]) {
+    /// Bytes from `readable` on could not be read, shown as `??`
+    fn byte_view(
+        &self,
+        ctx: &mut InspectorContext,
+        job: &mut LayoutJob,
+        buf: &[u8],
+        readable: usize,
+    ) {
         for (i, b) in buf.iter().enumerate() {
             let b = *b;
+            let leading_space = 4. + if i == 0 { 4. } else { 0. };
+
+            if i >= readable {
+                job.append(
+                    "??",
+                    leading_space,
+                    create_text_format(ctx.is_selected(self.id), UNREADABLE_COLOR),
+                );
+                continue;
+            }
+
             // generate unique color for each byte
             // by it's seed
             let mut rng = fastrand::Rng::with_seed(b as _);
@@ -35,8 +55,6 @@ impl<const N: usize> HexField<N> {
                 Color32::from_rgb(rng.u8(MIN), rng.u8(MIN), rng.u8(MIN))
             };
 
-            let leading_space = 4. + if i == 0 { 4. } else { 0. };
-
             job.append(
                 &format!("{b:02X}"),
                 leading_space,
@@ -125,12 +143,25 @@ impl<const N: usize> HexField<N> {
         }
         let address = usize::from_ne_bytes(buf[..].try_into().unwrap());
 
-        if global_state().memory.can_read(address) {
+        let memory = &global_state().memory;
+        let region = memory.region_at(address);
+        let readable = match &region {
+            Some(r) => r.protection.read,
+            // backend without layout
+            None => memory.can_read(address),
+        };
+
+        if readable {
+            let color = region
+                .as_ref()
+                .map(|r| region_kind_color(r.kind()))
+                .unwrap_or(Color32::YELLOW);
+
             let mut job = LayoutJob::default();
             job.append(
                 &format!("-> {address:X}"),
                 4.,
-                create_text_format(ctx.is_selected(self.id), Color32::YELLOW),
+                create_text_format(ctx.is_selected(self.id), color),
             );
 
             let r = ui.add(Label::new(job).sense(Sense::click()));
@@ -139,8 +170,15 @@ impl<const N: usize> HexField<N> {
                 ctx.toggle_select(self.id);
             }
 
-            if r.hovered() {
-                // todo: preview this
+            if let Some(region) = region {
+                r.on_hover_text(format!(
+                    "{} {}\n{:X} - {:X} {}",
+                    region.kind(),
+                    region.name.as_deref().unwrap_or_default(),
+                    region.start,
+                    region.end(),
+                    region.protection,
+                ));
             }
         }
     }
@@ -174,14 +212,14 @@ impl<const N: usize> Field for HexField<N> {
 
     fn draw(&self, ui: &mut eframe::egui::Ui, ctx: &mut InspectorContext) -> Option<FieldResponse> {
         let mut buf = vec![0; N / 8];
-        global_state()
+        let readable = global_state()
             .memory
             .read_buf(ctx.address + ctx.offset, &mut buf);
         let mut field_response = None;
         ui.horizontal(|ui| {
             let mut job = LayoutJob::default();
             self.display_field_prelude(ui, ctx, &mut job);
-            self.byte_view(ctx, &mut job, &buf);
+            self.byte_view(ctx, &mut job, &buf, readable);
 
             let r = ui.add(Label::new(job).sense(Sense::click()));
             if r.clicked() {
@@ -192,9 +230,12 @@ impl<const N: usize> Field for HexField<N> {
                 field_response.replace(r);
             }
 
-            self.int_view(ui, ctx, &buf);
-            self.float_view(ui, ctx, &buf);
-            self.pointer_view(ui, ctx, &buf, &mut field_response);
+            // zeroed bytes would show as real values
+            if readable == buf.len() {
+                self.int_view(ui, ctx, &buf);
+                self.float_view(ui, ctx, &buf);
+                self.pointer_view(ui, ctx, &buf, &mut field_response);
+            }
         });
         ctx.offset += self.field_size();
         field_response
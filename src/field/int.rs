@@ -38,7 +38,7 @@ impl<const N: usize> IntField<N> {
     pub fn unsigned_default() -> Self {
         Self {
             id: FieldId::next_id(),
-            signed: true,
+            signed: false,
             state: FieldState::new(format!("u{N}",)),
         }
     }
@@ -87,7 +87,7 @@ impl<const N: usize> Field for IntField<N> {
     ) -> Option<super::FieldResponse> {
         let mut buf = vec![0; N / 8];
         let address = ctx.address + ctx.offset;
-        global_state().memory.read_buf(address, &mut buf);
+        let readable = global_state().memory.read_buf(address, &mut buf) == buf.len();
 
         let mut field_response = None;
         ui.horizontal(|ui| {
@@ -119,6 +119,7 @@ impl<const N: usize> Field for IntField<N> {
                 ui,
                 ctx,
                 &self.state,
+                readable,
                 || match N {
                     8 => {
                         if self.signed {
@@ -171,7 +172,10 @@ impl<const N: usize> Field for IntField<N> {
                         Color32::RED,
                     ),
                 },
-                |_buf| eyre::bail!("Not implement write"),
+                |buf| {
+                    let v = Value::parse_for(self.field_tag(), buf)?;
+                    global_state().memory.write_buf(address, &v.to_ne_bytes())
+                },
             );
         });
 
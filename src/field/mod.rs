@@ -16,10 +16,14 @@ use eframe::egui::{
 };
 
 use crate::{
+    class::{ClassId, resolver::Rename},
     generator::Generator,
     global_state::global_state,
     inspection::InspectorContext,
-    styling::{create_text_format, create_text_offset_format, get_current_font_size_hex_view},
+    styling::{
+        UNREADABLE_COLOR, create_text_format, create_text_offset_format,
+        get_current_font_size_hex_view,
+    },
     value::Value,
 };
 
@@ -115,6 +119,11 @@ pub trait Field {
 
     fn field_size(&self) -> usize;
 
+    /// Class this field refer to, pointer or instance
+    fn class_id(&self) -> Option<ClassId> {
+        None
+    }
+
     fn boxed(self) -> Box<dyn Field>
     where
         Self: Sized + 'static,
@@ -178,6 +187,7 @@ pub trait Field {
         state: &FieldState,
         color: Color32,
     ) {
+        let mut renamed = None;
         if state.name_state.borrow().editing {
             let name_state = &mut *state.name_state.borrow_mut();
 
@@ -201,6 +211,7 @@ pub trait Field {
                 name_state.editing = false;
             } else if r.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
                 // enter only
+                let old = name_state.name_before_edit.clone();
                 if let Err(e) = name_state.validate_consume_name() {
                     ctx.toasts
                         .error(format!("{}\n{e}", obfstr!("Not a valid field name:")));
@@ -208,6 +219,9 @@ pub trait Field {
                 } else {
                     // finished editing
                     name_state.editing = false;
+                    if old != name_state.name {
+                        renamed = Some(old);
+                    }
                 }
             }
 
@@ -238,6 +252,15 @@ pub trait Field {
                 ctx.toggle_select(self.id());
             }
         }
+
+        // name state borrow is gone, expressions using old name can be walked now
+        if let Some(old) = renamed {
+            global_state().apply_rename(Rename::Field {
+                class: ctx.class_container,
+                field: self.id(),
+                old,
+            });
+        }
     }
 
     fn display_ptr_arrow(&self, ui: &mut egui::Ui, ctx: &mut InspectorContext, color: Color32) {
@@ -332,6 +355,8 @@ pub fn display_field_value(
     ui: &mut egui::Ui,
     ctx: &mut InspectorContext,
     state: &FieldState,
+    // from the read field already did, dont read again
+    readable: bool,
     display_value_fn: impl FnOnce() -> (Value, Color32),
     writer_new_value_fn: impl FnOnce(&str) -> eyre::Result<()>,
 ) {
@@ -388,6 +413,7 @@ pub fn display_field_value(
     let mut job = LayoutJob::default();
 
     let (v, color) = display_value_fn();
+    let color = if readable { color } else { UNREADABLE_COLOR };
 
     job.append(
         &v.to_string(),
@@ -395,7 +421,10 @@ pub fn display_field_value(
         create_text_format(ctx.is_selected(field.id()), color),
     );
 
-    let r = ui.add(Label::new(job).sense(Sense::click()));
+    let mut r = ui.add(Label::new(job).sense(Sense::click()));
+    if !readable {
+        r = r.on_hover_text(obfstr!("Memory not readable"));
+    }
 
     if r.double_clicked() {
         // enter edit mode
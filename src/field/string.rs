@@ -88,12 +88,13 @@ impl<const TEXT_KIND: usize> Field for TextField<TEXT_KIND> {
         }
 
         if self.buffer.borrow().len() != alloc_size {
-            self.buffer.borrow_mut().resize(self.char_count.get(), 0);
+            self.buffer.borrow_mut().resize(alloc_size, 0);
         }
 
-        global_state()
+        let readable = global_state()
             .memory
-            .read_buf(address, &mut *self.buffer.borrow_mut());
+            .read_buf(address, &mut *self.buffer.borrow_mut())
+            == alloc_size;
 
         let mut field_response = None;
         ui.horizontal(|ui| {
@@ -114,6 +115,7 @@ impl<const TEXT_KIND: usize> Field for TextField<TEXT_KIND> {
                 ui,
                 ctx,
                 &self.state,
+                readable,
                 || match TEXT_KIND {
                     8 => {
                         let b = self.buffer.borrow();
@@ -128,7 +130,13 @@ impl<const TEXT_KIND: usize> Field for TextField<TEXT_KIND> {
                     }
                     _ => (Value::String(format!("Invalid TextKind")), Color32::RED),
                 },
-                |_b| eyre::bail!("unimplemented"),
+                |buf| {
+                    let Value::String(text) = Value::parse_for(self.field_tag(), buf)? else {
+                        unreachable!()
+                    };
+                    let bytes = encode_text::<TEXT_KIND>(&text, self.char_count.get())?;
+                    global_state().memory.write_buf(address, &bytes)
+                },
             );
         });
         ctx.offset += self.field_size();
@@ -208,11 +216,12 @@ impl<const TEXT_KIND: usize> Field for PointerTextField<TEXT_KIND> {
         }
 
         if self.buffer.borrow().len() != alloc_size {
-            self.buffer.borrow_mut().resize(self.char_count.get(), 0);
+            self.buffer.borrow_mut().resize(alloc_size, 0);
         }
 
         let mut ptr_buf = [0u8; 8];
-        global_state().memory.read_buf(address, &mut ptr_buf);
+        // field itself is the pointer
+        let readable = global_state().memory.read_buf(address, &mut ptr_buf) == ptr_buf.len();
 
         let buf_addr = usize::from_ne_bytes(ptr_buf);
 
@@ -243,6 +252,7 @@ impl<const TEXT_KIND: usize> Field for PointerTextField<TEXT_KIND> {
                 ui,
                 ctx,
                 &self.state,
+                readable,
                 || match TEXT_KIND {
                     8 => {
                         let b = self.buffer.borrow();
@@ -257,7 +267,16 @@ impl<const TEXT_KIND: usize> Field for PointerTextField<TEXT_KIND> {
                     }
                     _ => (Value::String(format!("Invalid TextKind")), Color32::RED),
                 },
-                |_b| eyre::bail!("unimplemented"),
+                |buf| {
+                    if buf_addr == 0 {
+                        eyre::bail!("{}", obfstr!("Cant write text into null pointer"))
+                    }
+                    let Value::String(text) = Value::parse_for(self.field_tag(), buf)? else {
+                        unreachable!()
+                    };
+                    let bytes = encode_text::<TEXT_KIND>(&text, self.char_count.get())?;
+                    global_state().memory.write_buf(buf_addr, &bytes)
+                },
             );
         });
 
@@ -265,3 +284,63 @@ impl<const TEXT_KIND: usize> Field for PointerTextField<TEXT_KIND> {
         field_response
     }
 }
+
+/// Encode text for a buffer of `char_count` code units
+///
+/// Always null terminated, so at most `char_count - 1` units of text are kept.
+/// Longer text is truncated on a character boundary, bytes after the terminator are untouched.
+pub fn encode_text<const TEXT_KIND: usize>(text: &str, char_count: usize) -> eyre::Result<Vec<u8>> {
+    if char_count == 0 {
+        eyre::bail!(
+            "{}",
+            obfstr!("Text field has no room, change its char count first")
+        )
+    }
+    let max_units = char_count - 1;
+
+    match TEXT_KIND {
+        8 => {
+            let mut end = text.len().min(max_units);
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            let mut bytes = text.as_bytes()[..end].to_vec();
+            bytes.push(0);
+            Ok(bytes)
+        }
+        16 => {
+            let mut units = vec![];
+            for c in text.chars() {
+                let mut tmp = [0u16; 2];
+                let encoded = c.encode_utf16(&mut tmp);
+                if units.len() + encoded.len() > max_units {
+                    break;
+                }
+                units.extend_from_slice(encoded);
+            }
+            units.push(0);
+            Ok(units.iter().flat_map(|u| u.to_ne_bytes()).collect())
+        }
+        _ => eyre::bail!("{}{TEXT_KIND}", obfstr!("Invalid TextKind ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_text() {
+        assert_eq!(encode_text::<8>("abc", 8).unwrap(), b"abc\0");
+        // truncate with room for terminator
+        assert_eq!(encode_text::<8>("abcdef", 4).unwrap(), b"abc\0");
+        // never split a multi byte char
+        assert_eq!(encode_text::<8>("aé", 3).unwrap(), b"a\0");
+        assert!(encode_text::<8>("abc", 0).is_err());
+
+        let utf16 = encode_text::<16>("a\u{1F600}b", 3).unwrap();
+        assert_eq!(utf16, [b'a', 0, 0, 0]);
+        let utf16 = encode_text::<16>("hi", 8).unwrap();
+        assert_eq!(utf16, [b'h', 0, b'i', 0, 0, 0]);
+    }
+}
@@ -64,9 +64,9 @@ impl<const N: usize> Field for VectorField<N> {
     ) -> Option<super::FieldResponse> {
         let mut buf = [0_f32; N];
         let address = ctx.address + ctx.offset;
-        global_state().memory.read_buf(address, unsafe {
+        let readable = global_state().memory.read_buf(address, unsafe {
             std::slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut f32 as *mut u8, buf.len() * 4)
-        });
+        }) == N * 4;
 
         let mut field_response = None;
         ui.horizontal(|ui| {
@@ -88,6 +88,7 @@ impl<const N: usize> Field for VectorField<N> {
                 ui,
                 ctx,
                 &self.state,
+                readable,
                 || match N {
                     2 => (Value::Vec2(buf[0], buf[1]), Color32::WHITE),
                     3 => (Value::Vec3(buf[0], buf[1], buf[2]), Color32::WHITE),
@@ -97,7 +98,10 @@ impl<const N: usize> Field for VectorField<N> {
                         Color32::RED,
                     ),
                 },
-                |_buf| eyre::bail!("unimplemented"),
+                |buf| {
+                    let v = Value::parse_for(self.field_tag(), buf)?;
+                    global_state().memory.write_buf(address, &v.to_ne_bytes())
+                },
             );
         });
 
@@ -1,10 +1,24 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
 use egui_notify::Toasts;
 
 use crate::{
-    class::class_list::ClassList,
+    class::{
+        class_list::ClassList,
+        resolver::{ProjectResolver, Rename},
+    },
     hotkeys::HotkeyManager,
     inspection::InspectorSelection,
-    memory::{MemoryState, NullMemoryReader},
+    memory::{
+        MemoryState, NullMemoryReader,
+        cache::MemoryCache,
+        poller::{DEFAULT_RATE_HZ, MemoryPoller},
+    },
+    process::{ProcessEntry, open_process_memory},
+    variables::VariableTable,
 };
 
 static mut GLOBAL: Option<GlobalState> = None;
@@ -30,9 +44,23 @@ pub struct GlobalState {
     pub selection_field: Option<InspectorSelection>,
     pub class_list: ClassList,
     pub hotkeys: HotkeyManager,
+    /// globals every class address can use, saved with project
+    pub variables: VariableTable,
 
     pub memory: Box<dyn MemoryState>,
+    /// what `memory` read from, process or snapshot file, None when nothing
+    pub memory_source: Option<String>,
+    /// same backend as `memory` without the page cache, for bulk reads off the UI thread
+    pub raw_memory: Option<Arc<dyn MemoryState>>,
+    /// refresh `memory` in background while attached
+    pub poller: MemoryPoller,
     pub toasts: Toasts,
+
+    /// process behind `memory`, None when detached
+    pub process: Option<ProcessEntry>,
+    /// executable name for "attach to last", saved with project
+    pub last_process_name: Option<String>,
+    pub auto_reattach: bool,
 }
 
 impl Default for GlobalState {
@@ -40,9 +68,82 @@ impl Default for GlobalState {
         Self {
             class_list: Default::default(),
             hotkeys: Default::default(),
+            variables: Default::default(),
             memory: Box::new(NullMemoryReader) as Box<dyn MemoryState>,
+            memory_source: None,
+            raw_memory: None,
+            poller: MemoryPoller::new(DEFAULT_RATE_HZ),
             toasts: Toasts::new(),
             selection_field: None,
+            process: None,
+            last_process_name: None,
+            auto_reattach: false,
         }
     }
 }
+
+impl GlobalState {
+    pub fn attach_process(&mut self, process: ProcessEntry) -> eyre::Result<()> {
+        let memory = open_process_memory(process.pid)?;
+        info!("Attached to {process}");
+        self.use_memory(memory, process.to_string());
+        self.last_process_name = Some(process.name.clone());
+        self.process = Some(process);
+        Ok(())
+    }
+
+    pub fn detach_process(&mut self) {
+        if let Some(process) = self.process.take() {
+            info!("Detached from {process}");
+        }
+        self.poller.unwatch();
+        self.raw_memory = None;
+        self.memory = Box::new(NullMemoryReader);
+        self.memory_source = None;
+    }
+
+    /// Swap memory backend, drop attached process if any
+    pub fn use_memory(&mut self, memory: Box<dyn MemoryState>, source: String) {
+        self.detach_process();
+        let cache = MemoryCache::new(memory);
+        self.poller.watch(cache.clone());
+        self.raw_memory = Some(cache.backend());
+        self.memory = Box::new(cache);
+        self.memory_source = Some(source);
+        self.reevaluate_class_addresses();
+    }
+
+    /// Globals first, class addresses use them
+    pub fn reevaluate_class_addresses(&mut self) {
+        self.variables
+            .evaluate(&ProjectResolver::new(&self.class_list, &*self.memory));
+        for (class_name, e) in self.class_list.reevaluate_addresses() {
+            self.toasts.warning(format!(
+                "{}{class_name}\n{e}",
+                obfstr!("Failed evaluate address of ")
+            ));
+        }
+    }
+
+    /// After globals edit, errors show in globals panel and inspector so no toast
+    pub fn reevaluate_variables(&mut self) {
+        self.variables
+            .evaluate(&ProjectResolver::new(&self.class_list, &*self.memory));
+        _ = self.class_list.reevaluate_addresses();
+    }
+
+    /// Classes due by their policy, globals first so they dont read stale values
+    pub fn reevaluate_live(&mut self, now: Instant) -> Option<Duration> {
+        if self.class_list.is_live_due(now) {
+            self.variables
+                .evaluate(&ProjectResolver::new(&self.class_list, &*self.memory));
+        }
+        self.class_list.reevaluate_live(now)
+    }
+
+    /// Class or field renamed, keep expressions pointing at it
+    pub fn apply_rename(&mut self, rename: Rename) {
+        self.class_list.rename_in_addresses(&rename);
+        self.variables.rename(&self.class_list, &rename);
+    }
+}
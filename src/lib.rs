@@ -6,6 +6,7 @@ use self::{
     global_state::{GlobalState, global_state, set_global_state, unset_global_state},
     hotkeys::HotkeyManager,
     project::ProjectData,
+    variables::VariableTable,
 };
 
 #[macro_use]
@@ -23,11 +24,15 @@ mod global_state;
 mod hotkeys;
 mod inspection;
 pub mod memory;
+mod pointer_scan;
+mod process;
 mod project;
+mod scanner;
 mod styling;
 mod ui;
 mod utils;
 pub mod value;
+mod variables;
 
 pub fn run_app() {
     let sub = tracing_subscriber::fmt()
@@ -57,11 +62,19 @@ pub fn run_app() {
             );
 
             // load global
+            let project = ProjectData::load();
+            let poll_rate_hz = project.poll_rate_hz();
             set_global_state(GlobalState {
                 hotkeys,
-                class_list: ProjectData::load().to_class_list(),
+                last_process_name: project.last_process_name(),
+                variables: VariableTable::new(project.variables()),
+                class_list: project.to_class_list(),
                 ..Default::default()
             });
+            if let Some(rate_hz) = poll_rate_hz {
+                global_state().poller.set_rate_hz(rate_hz);
+            }
+            global_state().reevaluate_variables();
 
             cc.egui_ctx.style_mut(|s| {
                 s.spacing.item_spacing = Vec2::new(4., 4.);
@@ -85,7 +98,7 @@ pub fn run_app() {
         }),
     );
 
-    ProjectData::store(global_state().class_list.classes()).save();
+    ProjectData::store(global_state()).save();
     // cleanup global;
     unset_global_state();
     r.unwrap();
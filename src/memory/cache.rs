@@ -0,0 +1,608 @@
+// Page cache in front of a backend
+//
+// Fields read a few bytes each, on a live process every read is a syscall.
+// Without poller pages are kept until `invalidate_cache`, which the app call
+// once per frame, so one frame always draw a consistent view of memory.
+// With poller the UI never drop pages, `refresh` reload them in background.
+// Then UI never wait on the backend either, missing page is zeros until the
+// poller load it. Address expressions still read inline, they need real values.
+
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+use crate::address_parser::{AddressResolver, Signature};
+
+use super::{
+    MemoryReaderWriter, MemoryRegion, MemoryState, ModuleInfo, PAGE_SIZE, SymbolInfo,
+    symbols::SymbolCache,
+};
+
+struct CachedPage {
+    bytes: Box<[u8]>,
+    /// readable offsets in the page, sorted, never adjacent
+    readable: Vec<Range<usize>>,
+    /// last time UI read it, refresh drop pages nobody look at
+    touched: Instant,
+    /// queued for the poller, nothing read yet
+    pending: bool,
+}
+
+impl CachedPage {
+    fn full(bytes: Box<[u8]>, touched: Instant) -> Self {
+        let mut page = Self {
+            bytes,
+            readable: vec![],
+            touched,
+            pending: false,
+        };
+        page.push_readable(0..PAGE_SIZE);
+        page
+    }
+
+    /// Zeros and unreadable, next refresh load it
+    fn pending(touched: Instant) -> Self {
+        Self {
+            bytes: vec![0u8; PAGE_SIZE].into_boxed_slice(),
+            readable: vec![],
+            touched,
+            pending: true,
+        }
+    }
+
+    fn push_readable(&mut self, range: Range<usize>) {
+        match self.readable.last_mut() {
+            Some(last) if last.end == range.start => last.end = range.end,
+            _ if range.is_empty() => {}
+            _ => self.readable.push(range),
+        }
+    }
+
+    /// Readable bytes starting at `offset`
+    fn readable_from(&self, offset: usize) -> usize {
+        self.readable
+            .iter()
+            .find(|r| r.contains(&offset))
+            .map_or(0, |r| r.end - offset)
+    }
+}
+
+#[derive(Default)]
+struct PageStore {
+    pages: Mutex<HashMap<usize, CachedPage>>,
+    /// bumped by writes and invalidation, refresh started before is discarded
+    generation: AtomicU64,
+    /// poller is refreshing, misses are queued instead of read
+    deferred: AtomicBool,
+}
+
+/// Id of each cached backend, generations of two backends never match
+static NEXT_BACKEND: AtomicU64 = AtomicU64::new(0);
+
+/// Cheap to clone, clones share the pages
+#[derive(Clone)]
+pub struct MemoryCache {
+    inner: Arc<dyn MemoryState>,
+    backend_id: u64,
+    store: Arc<PageStore>,
+    /// module symbol tables, kept for the backend lifetime
+    symbols: Arc<SymbolCache>,
+}
+
+impl MemoryCache {
+    pub fn new(inner: Box<dyn MemoryState>) -> Self {
+        Self {
+            inner: inner.into(),
+            backend_id: NEXT_BACKEND.fetch_add(1, Ordering::Relaxed),
+            store: Default::default(),
+            symbols: Default::default(),
+        }
+    }
+
+    pub fn inner(&self) -> &dyn MemoryState {
+        &*self.inner
+    }
+
+    pub fn backend(&self) -> Arc<dyn MemoryState> {
+        self.inner.clone()
+    }
+
+    pub fn cached_pages(&self) -> usize {
+        self.store.pages.lock().len()
+    }
+
+    /// Set by the poller while it refresh this cache
+    pub fn set_deferred(&self, deferred: bool) {
+        self.store.deferred.store(deferred, Ordering::Relaxed);
+    }
+
+    /// Reload every page read within `max_age`, forget the others
+    pub fn refresh(&self, max_age: Duration) {
+        let generation = self.store.generation.load(Ordering::Acquire);
+        let mut wanted = {
+            let mut pages = self.store.pages.lock();
+            pages.retain(|_, p| p.touched.elapsed() <= max_age);
+            pages.keys().copied().collect::<Vec<_>>()
+        };
+        wanted.sort_unstable();
+
+        // backend read without lock, UI keep drawing old bytes meanwhile
+        let now = Instant::now();
+        let mut fresh = vec![];
+        for run in wanted.chunk_by(|a, b| a.checked_add(PAGE_SIZE) == Some(*b)) {
+            fresh.extend(self.load_run(run[0], run.len(), now));
+        }
+
+        let mut pages = self.store.pages.lock();
+        if self.store.generation.load(Ordering::Acquire) != generation {
+            return;
+        }
+        for (page, loaded) in fresh {
+            if let Some(cached) = pages.get_mut(&page) {
+                cached.bytes = loaded.bytes;
+                cached.readable = loaded.readable;
+                cached.pending = false;
+            }
+        }
+    }
+
+    /// Continuous pages in one read, pages after the first hole one by one
+    fn load_run(&self, start: usize, count: usize, now: Instant) -> Vec<(usize, CachedPage)> {
+        let mut bytes = vec![0u8; count * PAGE_SIZE];
+        let n = self.inner.read_buf(start, &mut bytes);
+        bytes
+            .chunks_exact(PAGE_SIZE)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let page = start + i * PAGE_SIZE;
+                if (i + 1) * PAGE_SIZE <= n {
+                    (page, CachedPage::full(chunk.into(), now))
+                } else {
+                    (page, self.load_page(page, now))
+                }
+            })
+            .collect()
+    }
+
+    fn load_page(&self, page: usize, now: Instant) -> CachedPage {
+        let mut cached = CachedPage {
+            pending: false,
+            ..CachedPage::pending(now)
+        };
+        let n = self.inner.read_buf(page, &mut cached.bytes);
+        cached.push_readable(0..n);
+        if n == PAGE_SIZE {
+            return cached;
+        }
+
+        // image sections dont start on page boundary,
+        // read stop at the hole but later part of the page can be fine
+        let hole = page + n;
+        let page_end = page.saturating_add(PAGE_SIZE);
+        let regions = self.inner.regions();
+        let from = regions.partition_point(|r| r.end() <= hole);
+        for r in regions[from..].iter().take_while(|r| r.start < page_end) {
+            if !r.protection.read || r.start <= hole {
+                continue;
+            }
+            let start = r.start - page;
+            let end = r.end().min(page_end) - page;
+            let m = self.inner.read_buf(r.start, &mut cached.bytes[start..end]);
+            cached.push_readable(start..start + m);
+        }
+        cached
+    }
+
+    fn page<'a>(
+        &self,
+        pages: &'a mut HashMap<usize, CachedPage>,
+        page: usize,
+        now: Instant,
+        inline: bool,
+    ) -> &'a mut CachedPage {
+        let cached = pages.entry(page).or_insert_with(|| match inline {
+            true => self.load_page(page, now),
+            false => CachedPage::pending(now),
+        });
+        if inline && cached.pending {
+            *cached = self.load_page(page, now);
+        }
+        cached.touched = now;
+        cached
+    }
+
+    /// Missing pages read now when `inline`, else queued for the poller
+    fn read(&self, addr: usize, buffer: &mut [u8], inline: bool) -> usize {
+        let mut pages = self.store.pages.lock();
+        let now = Instant::now();
+        let mut hole = None;
+        let mut pos = 0;
+        while pos < buffer.len() {
+            let Some(curr) = addr.checked_add(pos) else {
+                buffer[pos..].fill(0);
+                return hole.unwrap_or(pos);
+            };
+            let page = page_of(curr);
+            let cached = self.page(&mut pages, page, now, inline);
+
+            let offset = curr - page;
+            let n = (PAGE_SIZE - offset).min(buffer.len() - pos);
+            buffer[pos..pos + n].copy_from_slice(&cached.bytes[offset..offset + n]);
+            if hole.is_none() {
+                let readable = cached.readable_from(offset).min(n);
+                if readable < n {
+                    hole = Some(pos + readable);
+                }
+            }
+            pos += n;
+        }
+        hole.unwrap_or(buffer.len())
+    }
+}
+
+fn page_of(addr: usize) -> usize {
+    addr & !(PAGE_SIZE - 1)
+}
+
+impl MemoryReaderWriter for MemoryCache {
+    fn read_buf(&self, addr: usize, buffer: &mut [u8]) -> usize {
+        let inline = !self.store.deferred.load(Ordering::Relaxed);
+        self.read(addr, buffer, inline)
+    }
+
+    fn write_buf(&self, addr: usize, buffer: &[u8]) -> eyre::Result<()> {
+        let r = self.inner.write_buf(addr, buffer);
+
+        // even failed write can be partial
+        let mut pages = self.store.pages.lock();
+        self.store.generation.fetch_add(1, Ordering::Release);
+        let end = addr.saturating_add(buffer.len().max(1));
+        for page in (page_of(addr)..end).step_by(PAGE_SIZE) {
+            pages.remove(&page);
+        }
+        r
+    }
+
+    fn can_read(&self, addr: usize) -> bool {
+        self.inner.can_read(addr)
+    }
+
+    fn modules(&self) -> Vec<ModuleInfo> {
+        self.inner.modules()
+    }
+
+    fn symbols(&self) -> Vec<SymbolInfo> {
+        self.inner.symbols()
+    }
+
+    fn module_symbols(&self, module_name: &str) -> Vec<SymbolInfo> {
+        self.symbols.symbols(&*self.inner, module_name)
+    }
+
+    fn module_file(&self, module_name: &str) -> Option<PathBuf> {
+        self.inner.module_file(module_name)
+    }
+
+    fn module_generation(&self) -> u64 {
+        self.inner.module_generation()
+    }
+
+    fn regions(&self) -> Vec<MemoryRegion> {
+        self.inner.regions()
+    }
+
+    fn region_at(&self, addr: usize) -> Option<MemoryRegion> {
+        self.inner.region_at(addr)
+    }
+
+    fn invalidate_cache(&self) {
+        let mut pages = self.store.pages.lock();
+        self.store.generation.fetch_add(1, Ordering::Release);
+        pages.clear();
+    }
+
+    /// Sum of page readable ranges, inspector ask it for the whole class every frame
+    fn readable_bytes(&self, addr: usize, len: usize) -> usize {
+        let inline = !self.store.deferred.load(Ordering::Relaxed);
+        let mut pages = self.store.pages.lock();
+        let now = Instant::now();
+        let end = addr.saturating_add(len);
+        let mut readable = 0;
+        for page in (page_of(addr)..end).step_by(PAGE_SIZE) {
+            for r in &self.page(&mut pages, page, now, inline).readable {
+                let start = (page + r.start).max(addr);
+                let stop = (page + r.end).min(end);
+                readable += stop.saturating_sub(start);
+            }
+        }
+        readable
+    }
+
+    /// Load every missing readable page of the range, continuous pages in one read
+    fn prefetch(&self, addr: usize, len: usize) {
+        let mut pages = self.store.pages.lock();
+        let now = Instant::now();
+        let end = addr.saturating_add(len);
+        if self.store.deferred.load(Ordering::Relaxed) {
+            // poller load them in one go on next refresh
+            for page in (page_of(addr)..end).step_by(PAGE_SIZE) {
+                pages
+                    .entry(page)
+                    .or_insert_with(|| CachedPage::pending(now));
+            }
+            return;
+        }
+
+        let mut run_start = None;
+        let mut page = page_of(addr);
+        loop {
+            let in_range = page < end;
+            let load = in_range && !pages.contains_key(&page) && self.inner.can_read(page);
+
+            match (load, run_start) {
+                (true, None) => run_start = Some(page),
+                (false, Some(start)) => {
+                    pages.extend(self.load_run(start, (page - start) / PAGE_SIZE, now));
+                    run_start = None;
+                }
+                _ => {}
+            }
+
+            if !in_range {
+                break;
+            }
+            let Some(next) = page.checked_add(PAGE_SIZE) else {
+                break;
+            };
+            page = next;
+        }
+    }
+}
+
+impl AddressResolver for MemoryCache {
+    fn module_symbol_to_address(&self, module_name: &str) -> Option<isize> {
+        self.inner.module_symbol_to_address(module_name)
+    }
+
+    fn generation(&self) -> Option<u64> {
+        Some((self.backend_id << 32) | (self.inner.module_generation() & 0xFFFF_FFFF))
+    }
+
+    fn export_to_address(&self, module_name: &str, symbol_name: &str) -> Option<isize> {
+        // headers read once per module, not worth caching pages
+        self.symbols
+            .address(&*self.inner, module_name, symbol_name)
+            .map(|a| a as isize)
+    }
+
+    fn find_signature(&self, module_name: &str, signature: &Signature) -> Option<isize> {
+        // bulk read, dont fill the cache with it
+        self.inner.find_signature(module_name, signature)
+    }
+
+    fn read_exact(&self, address: usize, buffer: &mut [u8]) -> bool {
+        self.read(address, buffer, true) == buffer.len()
+    }
+
+    fn dereference(&self, address: usize) -> Option<isize> {
+        let last = address.checked_add(size_of::<usize>() - 1)?;
+        if !self.inner.can_read(address) || !self.inner.can_read(last) {
+            // backends with byte granular layout know better
+            return self.inner.dereference(address);
+        }
+        let mut buf = [0u8; size_of::<usize>()];
+        (self.read(address, &mut buf, true) == buf.len()).then(|| isize::from_ne_bytes(buf))
+    }
+}
+
+impl MemoryState for MemoryCache {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::memory::{Protection, readable_bytes};
+
+    const BASE: usize = 0x10000;
+
+    /// Flat memory at `base`, count reads reaching it
+    ///
+    /// Clones share bytes and counter, test keep one to look behind the cache
+    #[derive(Clone)]
+    struct CountingMemory {
+        base: usize,
+        bytes: Arc<Mutex<Vec<u8>>>,
+        reads: Arc<AtomicUsize>,
+    }
+
+    impl CountingMemory {
+        fn new(base: usize, bytes: Vec<u8>) -> Self {
+            Self {
+                base,
+                bytes: Arc::new(Mutex::new(bytes)),
+                reads: Default::default(),
+            }
+        }
+
+        fn reads(&self) -> usize {
+            self.reads.load(Ordering::Relaxed)
+        }
+    }
+
+    impl MemoryReaderWriter for CountingMemory {
+        fn read_buf(&self, addr: usize, buffer: &mut [u8]) -> usize {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            let bytes = self.bytes.lock();
+            let mut readable = 0;
+            for (i, b) in buffer.iter_mut().enumerate() {
+                let v = addr
+                    .checked_add(i)
+                    .and_then(|a| a.checked_sub(self.base))
+                    .and_then(|o| bytes.get(o).copied());
+                if v.is_some() && readable == i {
+                    readable += 1;
+                }
+                *b = v.unwrap_or_default();
+            }
+            readable
+        }
+
+        fn write_buf(&self, addr: usize, buffer: &[u8]) -> eyre::Result<()> {
+            let offset = addr - self.base;
+            self.bytes.lock()[offset..offset + buffer.len()].copy_from_slice(buffer);
+            Ok(())
+        }
+
+        fn can_read(&self, addr: usize) -> bool {
+            addr >= self.base && addr - self.base < self.bytes.lock().len()
+        }
+
+        fn regions(&self) -> Vec<MemoryRegion> {
+            vec![MemoryRegion {
+                start: self.base,
+                size: self.bytes.lock().len(),
+                protection: Protection::READ,
+                name: None,
+            }]
+        }
+    }
+
+    impl AddressResolver for CountingMemory {
+        fn module_symbol_to_address(&self, _module_name: &str) -> Option<isize> {
+            None
+        }
+
+        fn dereference(&self, _address: usize) -> Option<isize> {
+            None
+        }
+    }
+
+    impl MemoryState for CountingMemory {}
+
+    #[test]
+    fn test_memory_cache() {
+        let mut bytes = vec![0u8; 3 * PAGE_SIZE];
+        bytes[PAGE_SIZE - 4..PAGE_SIZE + 4].copy_from_slice(b"ACROSSPG");
+        let memory = CountingMemory::new(BASE, bytes);
+        let cache = MemoryCache::new(Box::new(memory.clone()));
+
+        // one read for the whole range, unreadable page after it skipped
+        cache.prefetch(BASE, 4 * PAGE_SIZE);
+        assert_eq!(memory.reads(), 1);
+        assert_eq!(cache.cached_pages(), 3);
+
+        let mut buf = [0u8; 8];
+        for offset in (0..3 * PAGE_SIZE - 8).step_by(8) {
+            cache.read_buf(BASE + offset, &mut buf);
+        }
+        cache.read_buf(BASE + PAGE_SIZE - 4, &mut buf);
+        assert_eq!(&buf, b"ACROSSPG");
+        assert_eq!(
+            cache.dereference(BASE + PAGE_SIZE - 4),
+            Some(isize::from_ne_bytes(*b"ACROSSPG"))
+        );
+        assert_eq!(memory.reads(), 1);
+
+        // write drop the page, next read see new bytes
+        cache.write_buf(BASE + 8, b"NEW").unwrap();
+        cache.read_buf(BASE + 8, &mut buf[..3]);
+        assert_eq!(&buf[..3], b"NEW");
+        assert_eq!(memory.reads(), 2);
+
+        cache.invalidate_cache();
+        assert_eq!(cache.cached_pages(), 0);
+        cache.read_buf(BASE, &mut buf);
+        assert_eq!(memory.reads(), 3);
+
+        // unreadable and end of address space
+        assert_eq!(cache.dereference(BASE + 3 * PAGE_SIZE), None);
+        let mut buf = [0xCCu8; 8];
+        assert_eq!(cache.read_buf(BASE + 3 * PAGE_SIZE - 4, &mut buf), 4);
+        assert_eq!(cache.read_buf(usize::MAX - 3, &mut buf), 0);
+        assert_eq!(buf, [0; 8]);
+        assert_eq!(cache.dereference(usize::MAX - 3), None);
+    }
+
+    #[test]
+    fn test_memory_cache_refresh() {
+        let memory = CountingMemory::new(BASE, vec![0u8; 3 * PAGE_SIZE]);
+        let cache = MemoryCache::new(Box::new(memory.clone()));
+        let mut buf = [0u8; 4];
+        cache.read_buf(BASE, &mut buf);
+        cache.read_buf(BASE + PAGE_SIZE, &mut buf);
+        cache.read_buf(BASE + 2 * PAGE_SIZE, &mut buf);
+        assert_eq!(memory.reads(), 3);
+
+        // change behind the cache, UI still see old bytes until refresh
+        memory.bytes.lock()[PAGE_SIZE..PAGE_SIZE + 4].copy_from_slice(b"POLL");
+        cache.read_buf(BASE + PAGE_SIZE, &mut buf);
+        assert_eq!(&buf, &[0; 4]);
+
+        // continuous pages reloaded in one read
+        cache.refresh(Duration::from_secs(60));
+        assert_eq!(memory.reads(), 4);
+        cache.read_buf(BASE + PAGE_SIZE, &mut buf);
+        assert_eq!(&buf, b"POLL");
+        assert_eq!(memory.reads(), 4);
+
+        // pages not read recently are forgotten
+        cache.refresh(Duration::ZERO);
+        assert_eq!(cache.cached_pages(), 0);
+    }
+
+    #[test]
+    fn test_memory_cache_deferred() {
+        let mut bytes = vec![0u8; 2 * PAGE_SIZE];
+        bytes[..4].copy_from_slice(b"LATE");
+        let memory = CountingMemory::new(BASE, bytes);
+        let cache = MemoryCache::new(Box::new(memory.clone()));
+        cache.set_deferred(true);
+
+        // miss is queued, UI get zeros without touching the backend
+        let mut buf = [0xCCu8; 4];
+        assert_eq!(cache.read_buf(BASE, &mut buf), 0);
+        assert_eq!(buf, [0; 4]);
+        cache.prefetch(BASE, 2 * PAGE_SIZE);
+        assert_eq!(memory.reads(), 0);
+        assert_eq!(cache.cached_pages(), 2);
+
+        cache.refresh(Duration::from_secs(60));
+        assert_eq!(memory.reads(), 1);
+        assert_eq!(cache.read_buf(BASE, &mut buf), 4);
+        assert_eq!(&buf, b"LATE");
+
+        // address expressions need the value now
+        cache.invalidate_cache();
+        assert_eq!(
+            cache.dereference(BASE),
+            Some(isize::from_ne_bytes(*b"LATE\0\0\0\0"))
+        );
+        assert_eq!(memory.reads(), 2);
+    }
+
+    #[test]
+    fn test_memory_cache_partial_page() {
+        // readable memory start in the middle of a page
+        let base = BASE + 0x800;
+        let cache = MemoryCache::new(Box::new(CountingMemory::new(base, vec![0xAA; 0x1000])));
+
+        let mut buf = [0u8; 0x10];
+        assert_eq!(cache.read_buf(base - 8, &mut buf), 0);
+        assert_eq!(&buf[8..], &[0xAA; 8]);
+        assert_eq!(cache.read_buf(base, &mut buf), 0x10);
+        assert_eq!(cache.read_buf(base + 0x1000 - 8, &mut buf), 8);
+        assert_eq!(readable_bytes(&cache, BASE, 3 * PAGE_SIZE), 0x1000);
+        assert_eq!(cache.readable_bytes(BASE, 3 * PAGE_SIZE), 0x1000);
+        assert_eq!(cache.readable_bytes(base + 8, 0x10), 0x10);
+    }
+}
@@ -0,0 +1,536 @@
+// Linux ELF core dump, what gcore or the kernel write on crash
+//
+// PT_LOAD segments hold the memory, the NT_FILE note tell which file
+// mapped where so module names can be resolved.
+
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use memmap2::Mmap;
+use object::{
+    Endian, Endianness, elf,
+    read::elf::{FileHeader, ProgramHeader},
+};
+
+use crate::address_parser::{AddressResolver, Signature};
+
+use super::{MemoryReaderWriter, MemoryRegion, MemoryState, ModuleInfo, Protection};
+
+#[derive(Debug, Clone)]
+pub struct CoreSegment {
+    pub start: usize,
+    /// size in process memory
+    pub size: usize,
+    /// bytes actually stored in the core, less than `size` when dumper skipped them
+    pub stored: usize,
+    pub protection: Protection,
+    file_offset: usize,
+}
+
+impl CoreSegment {
+    /// checked on parse, cant overflow
+    pub fn end(&self) -> usize {
+        self.start + self.size
+    }
+}
+
+/// Entry of NT_FILE note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoreFileMapping {
+    pub start: usize,
+    pub end: usize,
+    pub file_offset: usize,
+    pub path: String,
+}
+
+impl CoreFileMapping {
+    pub fn file_name(&self) -> Option<&str> {
+        Path::new(&self.path).file_name()?.to_str()
+    }
+}
+
+pub struct ElfCoreMemory {
+    data: Mmap,
+    /// sorted by start
+    segments: Vec<CoreSegment>,
+    files: Vec<CoreFileMapping>,
+}
+
+impl ElfCoreMemory {
+    pub fn open(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: opened read only, core files dont change under us
+        let data = unsafe { Mmap::map(&file)? };
+        Self::parse(data)
+    }
+
+    fn parse(data: Mmap) -> eyre::Result<Self> {
+        let header = elf::FileHeader64::<Endianness>::parse(&*data)?;
+        let endian = header.endian()?;
+        if header.e_type(endian) != elf::ET_CORE {
+            eyre::bail!("{}", obfstr!("ELF file is not a core dump"));
+        }
+
+        let mut segments = vec![];
+        let mut files = vec![];
+        for ph in header.program_headers(endian, &*data)? {
+            match ph.p_type(endian) {
+                elf::PT_LOAD => {
+                    let flags = ph.p_flags(endian);
+                    let start = ph.p_vaddr(endian) as usize;
+                    let size = ph.p_memsz(endian) as usize;
+                    if start.checked_add(size).is_none() {
+                        eyre::bail!("{}", obfstr!("core segment out of address space"));
+                    }
+                    let file_offset = ph.p_offset(endian) as usize;
+                    // truncated core still usable for what was written
+                    let stored = (ph.p_filesz(endian) as usize)
+                        .min(size)
+                        .min(data.len().saturating_sub(file_offset));
+                    segments.push(CoreSegment {
+                        start,
+                        size,
+                        stored,
+                        protection: Protection {
+                            read: flags & elf::PF_R != 0,
+                            write: flags & elf::PF_W != 0,
+                            execute: flags & elf::PF_X != 0,
+                        },
+                        file_offset,
+                    });
+                }
+                elf::PT_NOTE => {
+                    let Some(mut notes) = ph.notes(endian, &*data)? else {
+                        continue;
+                    };
+                    while let Some(note) = notes.next()? {
+                        if note.name() == b"CORE" && note.n_type(endian) == elf::NT_FILE {
+                            files = parse_nt_file(note.desc(), endian)?;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        segments.retain(|s| s.size > 0);
+        segments.sort_by_key(|s| s.start);
+
+        Ok(Self {
+            data,
+            segments,
+            files,
+        })
+    }
+
+    pub fn segments(&self) -> &[CoreSegment] {
+        &self.segments
+    }
+
+    pub fn file_mappings(&self) -> &[CoreFileMapping] {
+        &self.files
+    }
+
+    /// Stored part, then part not in the core
+    fn segment_regions(&self, s: &CoreSegment) -> Vec<MemoryRegion> {
+        let mut regions = vec![];
+        let name = self
+            .files
+            .iter()
+            .find(|f| f.start <= s.start && s.start < f.end)
+            .and_then(|f| f.file_name())
+            .map(str::to_owned);
+
+        if s.stored > 0 {
+            regions.push(MemoryRegion {
+                start: s.start,
+                size: s.stored,
+                protection: s.protection,
+                name: name.clone(),
+            });
+        }
+        // mapped in the process but not in the core
+        if s.stored < s.size {
+            regions.push(MemoryRegion {
+                start: s.start + s.stored,
+                size: s.size - s.stored,
+                protection: Protection {
+                    read: false,
+                    ..s.protection
+                },
+                name,
+            });
+        }
+        regions
+    }
+
+    /// Copy stored bytes, zero the rest
+    ///
+    /// return number of bytes found in the core
+    fn read_raw(&self, addr: usize, buffer: &mut [u8]) -> usize {
+        let mut pos = 0;
+        let mut covered = 0;
+        while pos < buffer.len() {
+            let curr = addr.saturating_add(pos);
+            let idx = self.segments.partition_point(|s| s.end() <= curr);
+            let Some(s) = self.segments.get(idx) else {
+                buffer[pos..].fill(0);
+                break;
+            };
+
+            if curr < s.start {
+                let n = (s.start - curr).min(buffer.len() - pos);
+                buffer[pos..pos + n].fill(0);
+                pos += n;
+                continue;
+            }
+
+            let offset = curr - s.start;
+            let n = (s.end() - curr).min(buffer.len() - pos);
+            let stored = s.stored.saturating_sub(offset).min(n);
+            if stored > 0 {
+                let from = s.file_offset + offset;
+                buffer[pos..pos + stored].copy_from_slice(&self.data[from..from + stored]);
+                // only count up to the first hole
+                if covered == pos {
+                    covered += stored;
+                }
+            }
+            buffer[pos + stored..pos + n].fill(0);
+            pos += n;
+        }
+        covered
+    }
+}
+
+/// NT_FILE description
+///
+/// count, page size, count * (start, end, page offset), count * null terminated path
+fn parse_nt_file(desc: &[u8], endian: Endianness) -> eyre::Result<Vec<CoreFileMapping>> {
+    let word = |i: usize| -> eyre::Result<usize> {
+        let bytes = desc
+            .get(i * 8..i * 8 + 8)
+            .ok_or_else(|| eyre::eyre!("{}", obfstr!("NT_FILE note truncated")))?;
+        Ok(endian.read_u64_bytes(bytes.try_into()?) as usize)
+    };
+
+    let count = word(0)?;
+    let page_size = word(1)?;
+    let names_at = count
+        .checked_mul(3)
+        .and_then(|n| n.checked_add(2)?.checked_mul(8))
+        .filter(|n| *n <= desc.len())
+        .ok_or_else(|| eyre::eyre!("{}", obfstr!("NT_FILE note truncated")))?;
+    let mut names = desc[names_at..].split(|b| *b == 0);
+
+    let mut files = Vec::with_capacity(count);
+    for i in 0..count {
+        let entry = 2 + i * 3;
+        let Some(name) = names.next() else {
+            eyre::bail!("{}", obfstr!("NT_FILE note missing file names"));
+        };
+        let (start, end) = (word(entry)?, word(entry + 1)?);
+        let file_offset = word(entry + 2)?.checked_mul(page_size);
+        let (Some(file_offset), true) = (file_offset, start <= end) else {
+            eyre::bail!("{}", obfstr!("NT_FILE note has bad mapping"));
+        };
+        files.push(CoreFileMapping {
+            start,
+            end,
+            file_offset,
+            path: String::from_utf8_lossy(name).into_owned(),
+        });
+    }
+    Ok(files)
+}
+
+impl MemoryReaderWriter for ElfCoreMemory {
+    fn read_buf(&self, addr: usize, buffer: &mut [u8]) -> usize {
+        self.read_raw(addr, buffer)
+    }
+
+    fn write_buf(&self, addr: usize, _buffer: &[u8]) -> eyre::Result<()> {
+        eyre::bail!(
+            "{}{addr:#X}",
+            obfstr!("Core dump is read only, cant write at ")
+        )
+    }
+
+    fn can_read(&self, addr: usize) -> bool {
+        let idx = self.segments.partition_point(|s| s.end() <= addr);
+        self.segments
+            .get(idx)
+            .is_some_and(|s| s.start <= addr && addr - s.start < s.stored)
+    }
+
+    fn modules(&self) -> Vec<ModuleInfo> {
+        let mut modules: Vec<ModuleInfo> = vec![];
+        for f in &self.files {
+            let Some(name) = f.file_name() else {
+                continue;
+            };
+
+            match modules.iter_mut().find(|m| m.name == name) {
+                Some(m) => {
+                    let end = f.end.max(m.base + m.size);
+                    m.base = m.base.min(f.start);
+                    m.size = end - m.base;
+                }
+                None => modules.push(ModuleInfo {
+                    name: name.to_owned(),
+                    base: f.start,
+                    size: f.end - f.start,
+                }),
+            }
+        }
+        modules
+    }
+
+    /// Dump can come from another machine, only when the file is here too
+    fn module_file(&self, module_name: &str) -> Option<PathBuf> {
+        self.files
+            .iter()
+            .find(|f| f.file_name() == Some(module_name))
+            .map(|f| PathBuf::from(&f.path))
+            .filter(|path| path.exists())
+    }
+
+    fn regions(&self) -> Vec<MemoryRegion> {
+        self.segments
+            .iter()
+            .flat_map(|s| self.segment_regions(s))
+            .collect()
+    }
+
+    fn region_at(&self, addr: usize) -> Option<MemoryRegion> {
+        let idx = self.segments.partition_point(|s| s.end() <= addr);
+        let s = self.segments.get(idx).filter(|s| s.start <= addr)?;
+        self.segment_regions(s)
+            .into_iter()
+            .find(|r| r.contains(addr))
+    }
+}
+
+impl AddressResolver for ElfCoreMemory {
+    fn module_symbol_to_address(&self, module_name: &str) -> Option<isize> {
+        self.files
+            .iter()
+            .filter(|f| f.file_name() == Some(module_name) || f.path == module_name)
+            .map(|f| f.start as isize)
+            .min()
+    }
+
+    fn dereference(&self, address: usize) -> Option<isize> {
+        let mut buf = [0u8; size_of::<usize>()];
+        (self.read_raw(address, &mut buf) == buf.len()).then(|| isize::from_ne_bytes(buf))
+    }
+
+    fn find_signature(&self, module_name: &str, signature: &Signature) -> Option<isize> {
+        super::find_signature(self, module_name, signature).map(|a| a as isize)
+    }
+
+    fn read_exact(&self, address: usize, buffer: &mut [u8]) -> bool {
+        self.read_buf(address, buffer) == buffer.len()
+    }
+}
+
+impl MemoryState for ElfCoreMemory {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        address_parser::AddressParser,
+        memory::{PAGE_SIZE, RegionKind},
+    };
+
+    use super::*;
+
+    struct Load {
+        vaddr: u64,
+        flags: u32,
+        bytes: Vec<u8>,
+        mem_size: u64,
+    }
+
+    /// Minimal little endian x86_64 core: PT_NOTE with NT_FILE then the PT_LOADs
+    fn build_core(loads: &[Load], files: &[(u64, u64, u64, &str)]) -> Vec<u8> {
+        let mut desc = vec![];
+        desc.extend((files.len() as u64).to_le_bytes());
+        desc.extend((PAGE_SIZE as u64).to_le_bytes());
+        for (start, end, page_offset, _) in files {
+            desc.extend(start.to_le_bytes());
+            desc.extend(end.to_le_bytes());
+            desc.extend(page_offset.to_le_bytes());
+        }
+        for (.., path) in files {
+            desc.extend(path.as_bytes());
+            desc.push(0);
+        }
+        desc.resize(desc.len().next_multiple_of(4), 0);
+
+        let mut note = vec![];
+        note.extend(5u32.to_le_bytes());
+        note.extend((desc.len() as u32).to_le_bytes());
+        note.extend(elf::NT_FILE.to_le_bytes());
+        note.extend(b"CORE\0\0\0\0");
+        note.extend(&desc);
+
+        let phnum = loads.len() + 1;
+        let note_offset = 64 + phnum * 56;
+        let mut data_offset = (note_offset + note.len()).next_multiple_of(PAGE_SIZE);
+
+        let mut out = vec![];
+        out.extend(b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0");
+        out.extend(elf::ET_CORE.to_le_bytes());
+        out.extend(elf::EM_X86_64.to_le_bytes());
+        out.extend(1u32.to_le_bytes());
+        out.extend(0u64.to_le_bytes()); // entry
+        out.extend(64u64.to_le_bytes()); // phoff
+        out.extend(0u64.to_le_bytes()); // shoff
+        out.extend(0u32.to_le_bytes()); // flags
+        out.extend(64u16.to_le_bytes());
+        out.extend(56u16.to_le_bytes());
+        out.extend((phnum as u16).to_le_bytes());
+        out.extend([0u8; 6]); // shentsize shnum shstrndx
+
+        let mut phdr = |p_type: u32, flags: u32, offset: u64, vaddr: u64, filesz: u64, memsz| {
+            out.extend(p_type.to_le_bytes());
+            out.extend(flags.to_le_bytes());
+            out.extend(offset.to_le_bytes());
+            out.extend(vaddr.to_le_bytes());
+            out.extend(0u64.to_le_bytes());
+            out.extend(filesz.to_le_bytes());
+            out.extend(u64::to_le_bytes(memsz));
+            out.extend(4u64.to_le_bytes());
+        };
+        phdr(elf::PT_NOTE, 0, note_offset as u64, 0, note.len() as u64, 0);
+        let mut offsets = vec![];
+        for l in loads {
+            phdr(
+                elf::PT_LOAD,
+                l.flags,
+                data_offset as u64,
+                l.vaddr,
+                l.bytes.len() as u64,
+                l.mem_size,
+            );
+            offsets.push(data_offset);
+            data_offset += l.bytes.len();
+        }
+
+        out.extend(&note);
+        for (l, offset) in loads.iter().zip(offsets) {
+            out.resize(offset, 0);
+            out.extend(&l.bytes);
+        }
+        out
+    }
+
+    fn open_core(bytes: &[u8]) -> eyre::Result<ElfCoreMemory> {
+        let path =
+            std::env::temp_dir().join(format!("make-class-test-{}.core", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let core = ElfCoreMemory::open(&path);
+        _ = std::fs::remove_file(&path);
+        core
+    }
+
+    #[test]
+    fn test_elf_core() {
+        let mut code = vec![0u8; PAGE_SIZE];
+        code[..4].copy_from_slice(b"\x7fELF");
+        code[0x20..0x28].copy_from_slice(&0x10000usize.to_ne_bytes());
+
+        let mut heap = vec![0u8; PAGE_SIZE];
+        heap[0x8..0x10].copy_from_slice(&0x1337usize.to_ne_bytes());
+
+        let core = open_core(&build_core(
+            &[
+                Load {
+                    vaddr: 0x400000,
+                    flags: elf::PF_R | elf::PF_X,
+                    bytes: code,
+                    mem_size: PAGE_SIZE as u64,
+                },
+                Load {
+                    vaddr: 0x401000,
+                    flags: elf::PF_R | elf::PF_W,
+                    bytes: vec![],
+                    mem_size: PAGE_SIZE as u64,
+                },
+                Load {
+                    vaddr: 0x10000,
+                    flags: elf::PF_R | elf::PF_W,
+                    bytes: heap,
+                    mem_size: PAGE_SIZE as u64,
+                },
+            ],
+            &[
+                (0x400000, 0x401000, 0, "/opt/game/sample.bin"),
+                (0x401000, 0x402000, 1, "/opt/game/sample.bin"),
+            ],
+        ))
+        .unwrap();
+
+        assert_eq!(core.segments().len(), 3);
+        assert_eq!(core.segments()[0].start, 0x10000);
+        assert!(core.segments()[1].protection.execute);
+        assert_eq!(core.file_mappings()[1].file_offset, PAGE_SIZE);
+
+        let code = core.region_at(0x400010).unwrap();
+        assert_eq!(code.name.as_deref(), Some("sample.bin"));
+        assert_eq!(code.kind(), RegionKind::Code);
+        assert!(!core.region_at(0x401000).unwrap().protection.read);
+        assert_eq!(core.region_at(0x10000).unwrap().kind(), RegionKind::Heap);
+        assert_eq!(core.regions().len(), 3);
+
+        assert!(core.can_read(0x400000));
+        // skipped by dumper
+        assert!(!core.can_read(0x401000));
+        assert!(!core.can_read(0x20000));
+
+        let mut buf = [0xCCu8; 8];
+        core.read_buf(0x401000 - 4, &mut buf);
+        assert_eq!(buf, [0; 8]);
+        core.read_buf(0x400000 - 4, &mut buf);
+        assert_eq!(&buf, b"\0\0\0\0\x7fELF");
+        assert!(core.write_buf(0x10000, &[0]).is_err());
+
+        assert_eq!(
+            core.modules(),
+            vec![ModuleInfo {
+                name: "sample.bin".into(),
+                base: 0x400000,
+                size: 2 * PAGE_SIZE,
+            }]
+        );
+        assert_eq!(
+            core.module_symbol_to_address("/opt/game/sample.bin"),
+            Some(0x400000)
+        );
+
+        let eval = AddressParser::new("[[sample.bin + 0x20 ] + 8 ]")
+            .parse()
+            .unwrap();
+        assert_eq!(eval.eval(&mut HashMap::new(), &core).unwrap(), 0x1337);
+        assert_eq!(core.dereference(0x400FFC), None);
+
+        // segment wrapping around the address space
+        let bad = build_core(
+            &[Load {
+                vaddr: u64::MAX - 0xFFF,
+                flags: elf::PF_R,
+                bytes: vec![],
+                mem_size: 2 * PAGE_SIZE as u64,
+            }],
+            &[],
+        );
+        assert!(open_core(&bad).is_err());
+        let bad = build_core(&[], &[(0x402000, 0x401000, 0, "/opt/game/sample.bin")]);
+        assert!(open_core(&bad).is_err());
+    }
+}
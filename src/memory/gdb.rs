@@ -0,0 +1,749 @@
+// GDB Remote Serial Protocol client over TCP
+//
+// Talks to gdbserver, QEMU user mode, emulators with a gdb stub.
+// Memory goes through `m`/`M` packets, layout from the target /proc/<pid>/maps
+// read with vFile, or qXfer:memory-map when the stub has no host io.
+// Library list from qXfer:libraries(-svr4) fill what the maps cant tell.
+
+use std::{
+    io::{self, BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+use crate::address_parser::{AddressResolver, Signature};
+
+use super::{
+    MemoryReaderWriter, MemoryRegion, MemoryState, ModuleInfo,
+    maps::{MemoryMapping, mapping_at, module_address, modules_from_mappings, parse_maps},
+};
+
+const IO_TIMEOUT: Duration = Duration::from_millis(if cfg!(test) { 500 } else { 3000 });
+/// target memory layout re-read at most this often
+const MAPS_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// used when stub dont tell PacketSize
+const DEFAULT_PACKET_SIZE: usize = 0x400;
+
+/// Called from UI thread, unreachable host must not hang it for the OS timeout
+fn connect_timeout(target: &str) -> io::Result<TcpStream> {
+    let mut last = io::Error::new(io::ErrorKind::NotFound, obfstr!("no address"));
+    for addr in target.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, IO_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last = e,
+        }
+    }
+    Err(last)
+}
+
+struct RspConnection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    no_ack: bool,
+    /// requests failed after going out, their reply may still come
+    stale: usize,
+}
+
+impl RspConnection {
+    fn new(stream: TcpStream) -> io::Result<Self> {
+        stream.set_read_timeout(Some(IO_TIMEOUT))?;
+        stream.set_write_timeout(Some(IO_TIMEOUT))?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            no_ack: false,
+            stale: 0,
+        })
+    }
+
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let mut b = [0u8];
+        self.reader.read_exact(&mut b)?;
+        Ok(b[0])
+    }
+
+    fn send(&mut self, payload: &[u8]) -> io::Result<()> {
+        let mut packet = Vec::with_capacity(payload.len() + 4);
+        packet.push(b'$');
+        packet.extend(payload);
+        packet.push(b'#');
+        packet.extend(format!("{:02x}", checksum(payload)).as_bytes());
+
+        for _ in 0..3 {
+            self.writer.write_all(&packet)?;
+            if self.no_ack {
+                return Ok(());
+            }
+            match self.read_byte()? {
+                b'+' => return Ok(()),
+                // checksum error on their side, send again
+                b'-' => continue,
+                b => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{}{b:#X}", obfstr!("Unexpected ack byte ")),
+                    ));
+                }
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            obfstr!("Packet rejected by remote"),
+        ))
+    }
+
+    fn recv(&mut self) -> io::Result<Vec<u8>> {
+        loop {
+            // skip acks and async notifications
+            while self.read_byte()? != b'$' {}
+
+            let mut raw = vec![];
+            loop {
+                match self.read_byte()? {
+                    b'#' => break,
+                    b => raw.push(b),
+                }
+            }
+            let mut cs = [0u8; 2];
+            self.reader.read_exact(&mut cs)?;
+
+            let valid = std::str::from_utf8(&cs)
+                .ok()
+                .and_then(|s| u8::from_str_radix(s, 16).ok())
+                == Some(checksum(&raw));
+
+            if !self.no_ack {
+                self.writer.write_all(if valid { b"+" } else { b"-" })?;
+            }
+            if valid || self.no_ack {
+                return Ok(decode_payload(&raw));
+            }
+        }
+    }
+
+    fn request(&mut self, payload: impl AsRef<[u8]>) -> io::Result<Vec<u8>> {
+        // late reply would be taken as answer of this request
+        // one that dont come in time is given up, so a lost packet dont break the connection forever
+        while self.stale > 0 {
+            self.stale -= 1;
+            self.recv()?;
+        }
+        let reply = self.send(payload.as_ref()).and_then(|_| self.recv());
+        if reply.is_err() {
+            self.stale += 1;
+        }
+        reply
+    }
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |a, b| a.wrapping_add(*b))
+}
+
+/// Undo binary escape `}` and run length encoding `X*n`
+pub fn decode_payload(raw: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(raw.len());
+    let mut iter = raw.iter();
+    while let Some(&b) = iter.next() {
+        match b {
+            b'}' => out.extend(iter.next().map(|n| n ^ 0x20)),
+            b'*' => {
+                let (Some(&n), Some(&last)) = (iter.next(), out.last()) else {
+                    continue;
+                };
+                out.extend(std::iter::repeat_n(last, n.saturating_sub(29) as usize));
+            }
+            b => out.push(b),
+        }
+    }
+    out
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.len().is_multiple_of(2) {
+        return None;
+    }
+    data.chunks_exact(2)
+        .map(|c| u8::from_str_radix(std::str::from_utf8(c).ok()?, 16).ok())
+        .collect()
+}
+
+fn is_error(reply: &[u8]) -> bool {
+    reply.is_empty() || (reply[0] == b'E' && reply.len() == 3)
+}
+
+/// Every `<tag .../>` or `<tag ...>` start, the text up to its `>`
+fn xml_elements<'a>(xml: &'a str, tag: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    xml.match_indices('<')
+        .map(move |(i, _)| &xml[i + 1..])
+        .filter(move |s| {
+            s.strip_prefix(tag)
+                .is_some_and(|rest| rest.starts_with([' ', '/', '>', '\t', '\n']))
+        })
+        .map(|s| &s[..s.find('>').unwrap_or(s.len())])
+}
+
+fn xml_attr(element: &str, name: &str) -> Option<String> {
+    let pattern = format!(" {name}=\"");
+    let start = element.find(&pattern)? + pattern.len();
+    let value = &element[start..start + element[start..].find('"')?];
+    Some(
+        value
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+fn parse_xml_number(s: &str) -> Option<usize> {
+    match s.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+struct MapsCache {
+    refreshed_at: Instant,
+    mappings: Vec<MemoryMapping>,
+    /// bumped when a refresh find modules loaded, unloaded or moved
+    generation: u64,
+}
+
+impl MapsCache {
+    fn replace(&mut self, mappings: Vec<MemoryMapping>) {
+        if modules_from_mappings(&mappings) != modules_from_mappings(&self.mappings) {
+            self.generation += 1;
+        }
+        self.mappings = mappings;
+    }
+}
+
+pub struct GdbRemoteMemory {
+    target: String,
+    conn: Mutex<RspConnection>,
+    packet_size: usize,
+    pid: Option<i32>,
+    memory_map: bool,
+    maps: Mutex<MapsCache>,
+    /// from qXfer libraries, base is where the loader put it
+    libraries: Vec<ModuleInfo>,
+}
+
+impl GdbRemoteMemory {
+    /// `target` as `host:port`
+    pub fn connect(target: &str) -> eyre::Result<Self> {
+        let stream = connect_timeout(target)
+            .map_err(|e| eyre::eyre!("{}{target}: {e}", obfstr!("Failed connect to ")))?;
+        let mut conn = RspConnection::new(stream)?;
+
+        let supported = conn.request(obfstr!(
+            "qSupported:multiprocess+;swbreak+;hwbreak+;qRelocInsn+"
+        ))?;
+        let supported = String::from_utf8_lossy(&supported).into_owned();
+        let feature = |name: &str| supported.split(';').any(|f| f == name);
+
+        let packet_size = supported
+            .split(';')
+            .find_map(|f| f.strip_prefix("PacketSize="))
+            .and_then(|s| usize::from_str_radix(s, 16).ok())
+            .unwrap_or(DEFAULT_PACKET_SIZE);
+
+        if feature("QStartNoAckMode+") && conn.request(b"QStartNoAckMode")? == b"OK" {
+            conn.no_ack = true;
+        }
+
+        // stubs expect the stop reason asked before anything else
+        conn.request(b"?")?;
+
+        // QCp<pid>.<tid> with multiprocess, QC<tid> without
+        let current = conn.request(b"qC")?;
+        let pid = current
+            .strip_prefix(b"QCp")
+            .and_then(|s| std::str::from_utf8(s).ok())
+            .and_then(|s| i32::from_str_radix(s.split('.').next()?, 16).ok());
+
+        let mut s = Self {
+            target: target.to_owned(),
+            conn: Mutex::new(conn),
+            packet_size,
+            pid,
+            memory_map: feature("qXfer:memory-map:read+"),
+            maps: Mutex::new(MapsCache {
+                refreshed_at: Instant::now(),
+                mappings: vec![],
+                generation: 0,
+            }),
+            libraries: vec![],
+        };
+
+        if feature("qXfer:libraries-svr4:read+") {
+            s.libraries = s.read_svr4_libraries();
+        } else if feature("qXfer:libraries:read+") {
+            s.libraries = s.read_libraries();
+        }
+        s.maps.lock().mappings = s.read_mappings();
+
+        Ok(s)
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn pid(&self) -> Option<i32> {
+        self.pid
+    }
+
+    /// Current mappings, refreshed when older than [`MAPS_REFRESH_INTERVAL`]
+    pub fn mappings(&self) -> Vec<MemoryMapping> {
+        self.with_mappings(|m| m.to_vec())
+    }
+
+    fn with_mappings<R>(&self, f: impl FnOnce(&[MemoryMapping]) -> R) -> R {
+        let mut cache = self.maps.lock();
+        if cache.refreshed_at.elapsed() >= MAPS_REFRESH_INTERVAL {
+            let mappings = self.read_mappings();
+            // keep old mappings when connection hiccup
+            if !mappings.is_empty() {
+                cache.replace(mappings);
+            }
+            cache.refreshed_at = Instant::now();
+        }
+        f(&cache.mappings)
+    }
+
+    fn read_mappings(&self) -> Vec<MemoryMapping> {
+        if let Some(pid) = self.pid
+            && let Some(content) = self.read_remote_file(&format!("/proc/{pid}/maps"))
+        {
+            return parse_maps(&String::from_utf8_lossy(&content));
+        }
+
+        if !self.memory_map {
+            return vec![];
+        }
+        let Some(xml) = self.read_xfer("memory-map", "") else {
+            return vec![];
+        };
+
+        let mut mappings = xml_elements(&xml, "memory")
+            .filter_map(|e| {
+                let start = parse_xml_number(&xml_attr(e, "start")?)?;
+                let length = parse_xml_number(&xml_attr(e, "length")?)?;
+                Some(MemoryMapping {
+                    start,
+                    end: start.checked_add(length)?,
+                    readable: true,
+                    writable: xml_attr(e, "type").as_deref() == Some("ram"),
+                    executable: false,
+                    file_offset: 0,
+                    path: None,
+                })
+            })
+            .collect::<Vec<_>>();
+        mappings.sort_by_key(|m| m.start);
+        mappings
+    }
+
+    /// Whole file from target filesystem through vFile host io
+    fn read_remote_file(&self, path: &str) -> Option<Vec<u8>> {
+        let mut conn = self.conn.lock();
+        _ = conn.request(b"vFile:setfs:0");
+
+        let reply = conn
+            .request(format!("vFile:open:{},0,0", to_hex(path.as_bytes())))
+            .ok()?;
+        let fd = reply
+            .strip_prefix(b"F")
+            .and_then(|s| std::str::from_utf8(s).ok())
+            .and_then(|s| i64::from_str_radix(s, 16).ok())
+            .filter(|fd| *fd >= 0)?;
+
+        let chunk = self.packet_size / 2;
+        let mut content = vec![];
+        let result = loop {
+            let Ok(reply) =
+                conn.request(format!("vFile:pread:{fd:x},{chunk:x},{:x}", content.len()))
+            else {
+                break None;
+            };
+            // F<len>;<binary data>
+            let Some(sep) = reply.iter().position(|b| *b == b';') else {
+                break (reply.as_slice() == b"F0").then_some(());
+            };
+            let data = &reply[sep + 1..];
+            if data.is_empty() {
+                break Some(());
+            }
+            content.extend(data);
+        };
+
+        _ = conn.request(format!("vFile:close:{fd:x}"));
+        result.map(|_| content)
+    }
+
+    /// Whole qXfer object, None when stub dont support it
+    fn read_xfer(&self, object: &str, annex: &str) -> Option<String> {
+        let mut conn = self.conn.lock();
+        let chunk = self.packet_size.saturating_sub(8).max(0x100);
+        let mut content = vec![];
+        loop {
+            let reply = conn
+                .request(format!(
+                    "qXfer:{object}:read:{annex}:{:x},{chunk:x}",
+                    content.len()
+                ))
+                .ok()?;
+            match reply.split_first() {
+                Some((b'm', data)) => content.extend(data),
+                Some((b'l', data)) => {
+                    content.extend(data);
+                    break;
+                }
+                _ => return None,
+            }
+        }
+        Some(String::from_utf8_lossy(&content).into_owned())
+    }
+
+    /// `<library name="/lib/libc.so.6" lm="0x.." l_addr="0x7f.." l_ld="0x.."/>`
+    fn read_svr4_libraries(&self) -> Vec<ModuleInfo> {
+        let Some(xml) = self.read_xfer("libraries-svr4", "") else {
+            return vec![];
+        };
+        xml_elements(&xml, "library")
+            .filter_map(|e| {
+                Some(ModuleInfo {
+                    name: library_name(&xml_attr(e, "name")?).to_owned(),
+                    base: parse_xml_number(&xml_attr(e, "l_addr")?)?,
+                    size: 0,
+                })
+            })
+            .filter(|m| !m.name.is_empty())
+            .collect()
+    }
+
+    /// `<library name="C:\game.exe"><segment address="0x.."/></library>`
+    fn read_libraries(&self) -> Vec<ModuleInfo> {
+        let Some(xml) = self.read_xfer("libraries", "") else {
+            return vec![];
+        };
+        xml.split("<library ")
+            .skip(1)
+            .filter_map(|chunk| {
+                let element = format!(" {}", &chunk[..chunk.find('>')?]);
+                let address = xml_elements(chunk, "segment")
+                    .chain(xml_elements(chunk, "section"))
+                    .find_map(|e| parse_xml_number(&xml_attr(e, "address")?))?;
+                Some(ModuleInfo {
+                    name: library_name(&xml_attr(&element, "name")?).to_owned(),
+                    base: address,
+                    size: 0,
+                })
+            })
+            .collect()
+    }
+
+    /// return number of bytes read from start of buffer
+    fn read_raw(&self, addr: usize, buffer: &mut [u8]) -> usize {
+        let chunk = (self.packet_size.saturating_sub(4) / 2).max(1);
+        let mut conn = self.conn.lock();
+        let mut done = 0;
+        while done < buffer.len() {
+            let len = chunk.min(buffer.len() - done);
+            let Some(at) = addr.checked_add(done) else {
+                break;
+            };
+            let Ok(reply) = conn.request(format!("m{at:x},{len:x}")) else {
+                break;
+            };
+            if is_error(&reply) {
+                break;
+            }
+            let Some(bytes) = from_hex(&reply) else {
+                break;
+            };
+            let n = bytes.len().min(len);
+            buffer[done..done + n].copy_from_slice(&bytes[..n]);
+            done += n;
+            // stub stop at first unreadable byte
+            if n < len {
+                break;
+            }
+        }
+        done
+    }
+}
+
+fn library_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+impl MemoryReaderWriter for GdbRemoteMemory {
+    fn read_buf(&self, addr: usize, buffer: &mut [u8]) -> usize {
+        let n = self.read_raw(addr, buffer);
+        // dont leave stale data in reused buffers
+        buffer[n..].fill(0);
+        n
+    }
+
+    fn write_buf(&self, addr: usize, buffer: &[u8]) -> eyre::Result<()> {
+        let chunk = (self.packet_size.saturating_sub(32) / 2).max(1);
+        let mut conn = self.conn.lock();
+        for (i, part) in buffer.chunks(chunk).enumerate() {
+            let Some(at) = addr.checked_add(i * chunk) else {
+                eyre::bail!(
+                    "{}{addr:#X}",
+                    obfstr!("Write past end of address space at ")
+                );
+            };
+            let reply = conn.request(format!("M{at:x},{:x}:{}", part.len(), to_hex(part)))?;
+            if reply != b"OK" {
+                eyre::bail!(
+                    "{}{at:#X}: {}",
+                    obfstr!("Remote refused write at "),
+                    String::from_utf8_lossy(&reply)
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn can_read(&self, addr: usize) -> bool {
+        let known = self.with_mappings(|mappings| {
+            (!mappings.is_empty()).then(|| mapping_at(mappings, addr).is_some_and(|m| m.readable))
+        });
+        // no layout, ask the stub
+        known.unwrap_or_else(|| self.read_raw(addr, &mut [0u8]) == 1)
+    }
+
+    fn modules(&self) -> Vec<ModuleInfo> {
+        let mut modules = self.with_mappings(modules_from_mappings);
+        for lib in &self.libraries {
+            if !modules.iter().any(|m| m.name == lib.name) {
+                modules.push(lib.clone());
+            }
+        }
+        modules
+    }
+
+    fn module_generation(&self) -> u64 {
+        // refresh maps when due
+        self.with_mappings(|_| ());
+        self.maps.lock().generation
+    }
+
+    fn regions(&self) -> Vec<MemoryRegion> {
+        self.with_mappings(|mappings| mappings.iter().map(MemoryMapping::to_region).collect())
+    }
+
+    fn region_at(&self, addr: usize) -> Option<MemoryRegion> {
+        self.with_mappings(|mappings| mapping_at(mappings, addr).map(MemoryMapping::to_region))
+    }
+}
+
+impl AddressResolver for GdbRemoteMemory {
+    fn module_symbol_to_address(&self, module_name: &str) -> Option<isize> {
+        self.with_mappings(|mappings| module_address(mappings, module_name))
+            .or_else(|| {
+                self.libraries
+                    .iter()
+                    .find(|l| l.name == module_name)
+                    .map(|l| l.base)
+            })
+            .map(|addr| addr as isize)
+    }
+
+    fn dereference(&self, address: usize) -> Option<isize> {
+        let mut buf = [0u8; size_of::<usize>()];
+        (self.read_raw(address, &mut buf) == buf.len()).then(|| isize::from_ne_bytes(buf))
+    }
+
+    fn find_signature(&self, module_name: &str, signature: &Signature) -> Option<isize> {
+        super::find_signature(self, module_name, signature).map(|a| a as isize)
+    }
+
+    fn read_exact(&self, address: usize, buffer: &mut [u8]) -> bool {
+        self.read_buf(address, buffer) == buffer.len()
+    }
+}
+
+impl MemoryState for GdbRemoteMemory {}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, net::TcpListener, sync::Arc, thread::JoinHandle};
+
+    use crate::address_parser::AddressParser;
+
+    use super::*;
+
+    const HEAP: usize = 0x10000;
+    const PID: i32 = 0x1f;
+    /// stub answer reads here after the client gave up
+    const SLOW: usize = HEAP + 0x1000;
+
+    /// Single connection stub serving one heap mapping of fake memory
+    fn spawn_stub() -> (String, Arc<Mutex<Vec<u8>>>, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let target = listener.local_addr().unwrap().to_string();
+        let heap = Arc::new(Mutex::new(vec![0u8; 0x2000]));
+        let memory = heap.clone();
+
+        let maps = format!(
+            "{HEAP:x}-{:x} rw-p 00000000 00:00 0    [heap]\n\
+             400000-401000 r-xp 00000000 08:01 42   /opt/game/sample.bin\n",
+            HEAP + 0x2000
+        );
+
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut conn = RspConnection::new(stream).unwrap();
+            // wait for the client however long it take
+            conn.writer.set_read_timeout(None).unwrap();
+            // stub side use the same framing, in ack mode until asked
+            while let Ok(packet) = conn.recv() {
+                let packet = String::from_utf8(packet).unwrap();
+                let reply: Vec<u8> = match packet.as_str() {
+                    p if p.starts_with("qSupported") => {
+                        b"PacketSize=40;QStartNoAckMode+;qXfer:libraries-svr4:read+".to_vec()
+                    }
+                    "QStartNoAckMode" => {
+                        conn.send(b"OK").unwrap();
+                        conn.no_ack = true;
+                        continue;
+                    }
+                    "?" => b"S05".to_vec(),
+                    "qC" => format!("QCp{PID:x}.{PID:x}").into_bytes(),
+                    p if p.starts_with("vFile:setfs") => b"F0".to_vec(),
+                    p if p.starts_with("vFile:open:") => {
+                        let path = from_hex(p[11..].split(',').next().unwrap().as_bytes());
+                        if path.as_deref() == Some(format!("/proc/{PID}/maps").as_bytes()) {
+                            b"F3".to_vec()
+                        } else {
+                            b"F-1,2".to_vec()
+                        }
+                    }
+                    p if p.starts_with("vFile:pread:3,") => {
+                        let args = p[14..].split(',').collect::<Vec<_>>();
+                        let count = usize::from_str_radix(args[0], 16).unwrap();
+                        let offset = usize::from_str_radix(args[1], 16).unwrap();
+                        let data = &maps.as_bytes()[offset.min(maps.len())..];
+                        let data = &data[..count.min(data.len())];
+                        let mut reply = format!("F{:x};", data.len()).into_bytes();
+                        reply.extend(data);
+                        reply
+                    }
+                    p if p.starts_with("vFile:close") => b"F0".to_vec(),
+                    p if p.starts_with("qXfer:libraries-svr4:read::0,") => {
+                        b"l<library-list-svr4 version=\"1.0\">\
+                          <library name=\"/lib/libc.so.6\" lm=\"0x1\" l_addr=\"0x7f0000\" l_ld=\"0x2\"/>\
+                          </library-list-svr4>"
+                            .to_vec()
+                    }
+                    p if p.starts_with('m') => {
+                        let (addr, len) = p[1..].split_once(',').unwrap();
+                        let addr = usize::from_str_radix(addr, 16).unwrap();
+                        let len = usize::from_str_radix(len, 16).unwrap();
+                        if addr == SLOW {
+                            std::thread::sleep(IO_TIMEOUT + Duration::from_millis(200));
+                        }
+                        let heap = memory.lock();
+                        match addr.checked_sub(HEAP).filter(|o| *o < heap.len()) {
+                            Some(offset) => {
+                                let end = (offset + len).min(heap.len());
+                                to_hex(&heap[offset..end]).into_bytes()
+                            }
+                            None => b"E14".to_vec(),
+                        }
+                    }
+                    p if p.starts_with('M') => {
+                        let (header, data) = p[1..].split_once(':').unwrap();
+                        let addr = usize::from_str_radix(header.split(',').next().unwrap(), 16)
+                            .unwrap();
+                        let data = from_hex(data.as_bytes()).unwrap();
+                        let mut heap = memory.lock();
+                        match addr.checked_sub(HEAP) {
+                            Some(o) if o + data.len() <= heap.len() => {
+                                heap[o..o + data.len()].copy_from_slice(&data);
+                                b"OK".to_vec()
+                            }
+                            _ => b"E0e".to_vec(),
+                        }
+                    }
+                    _ => vec![],
+                };
+                if conn.send(&reply).is_err() {
+                    break;
+                }
+            }
+        });
+
+        (target, heap, handle)
+    }
+
+    #[test]
+    fn test_decode_payload() {
+        assert_eq!(decode_payload(b"0* "), b"0000");
+        assert_eq!(decode_payload(b"a}]b"), b"a}b");
+        assert_eq!(decode_payload(b"}\x03"), b"#");
+    }
+
+    #[test]
+    fn test_gdb_remote() {
+        let (target, heap, handle) = spawn_stub();
+        heap.lock()[0x10..0x18].copy_from_slice(&(HEAP + 0x100).to_ne_bytes());
+        heap.lock()[0x100..0x104].copy_from_slice(b"GDB!");
+        heap.lock()[0x1000..0x1004].copy_from_slice(b"LATE");
+
+        {
+            let remote = GdbRemoteMemory::connect(&target).unwrap();
+            assert_eq!(remote.pid(), Some(PID));
+            assert_eq!(remote.mappings().len(), 2);
+
+            assert!(remote.can_read(HEAP));
+            assert!(!remote.can_read(HEAP + 0x2000));
+
+            // more than one packet worth, crossing the end of the heap
+            let mut buf = vec![0xCCu8; 0x40];
+            assert_eq!(remote.read_buf(HEAP + 0x2000 - 0x20, &mut buf), 0x20);
+            assert_eq!(&buf[0x20..], &[0u8; 0x20]);
+            remote.read_buf(HEAP + 0x100, &mut buf[..4]);
+            assert_eq!(&buf[..4], b"GDB!");
+
+            // late reply must not answer the next read
+            assert_eq!(remote.read_buf(SLOW, &mut buf[..4]), 0);
+            assert_eq!(remote.read_buf(HEAP + 0x100, &mut buf[..4]), 4);
+            assert_eq!(&buf[..4], b"GDB!");
+            assert_eq!(remote.read_buf(usize::MAX - 1, &mut buf[..4]), 0);
+
+            remote.write_buf(HEAP + 0x200, &[0xAB; 0x30]).unwrap();
+            assert_eq!(&heap.lock()[0x200..0x230], &[0xAB; 0x30]);
+            assert!(remote.write_buf(0, &[1]).is_err());
+
+            assert_eq!(
+                remote.module_symbol_to_address("sample.bin"),
+                Some(0x400000)
+            );
+            assert_eq!(remote.module_symbol_to_address("libc.so.6"), Some(0x7f0000));
+            assert_eq!(remote.modules().len(), 2);
+
+            let eval = AddressParser::new(&format!("[[{HEAP:#x} + 0x10 ] ]"))
+                .parse()
+                .unwrap();
+            let v = eval.eval(&mut HashMap::new(), &remote).unwrap() as usize;
+            assert_eq!(&v.to_ne_bytes()[..4], b"GDB!");
+        }
+
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,455 @@
+// Executable file on disk mapped at its preferred image base
+//
+// Every allocated section is placed at its virtual address, bytes past the
+// raw data (bss) read as zero. Good enough for static data: globals, vtables, rtti.
+
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use memmap2::Mmap;
+use object::{Object, ObjectSection, ObjectSegment, SectionFlags, SectionKind, elf, pe};
+
+use crate::address_parser::{AddressResolver, Signature};
+
+use super::{
+    MemoryReaderWriter, MemoryRegion, MemoryState, ModuleInfo, PAGE_SIZE, Protection, SymbolInfo,
+};
+
+#[derive(Debug, Clone)]
+pub struct ImageSection {
+    pub name: String,
+    pub start: usize,
+    pub size: usize,
+    pub protection: Protection,
+    file_offset: usize,
+    /// raw bytes in file, the rest of `size` is zero
+    file_size: usize,
+}
+
+impl ImageSection {
+    /// checked on parse, cant overflow
+    pub fn end(&self) -> usize {
+        self.start + self.size
+    }
+}
+
+pub struct ImageMemory {
+    data: Mmap,
+    /// sorted by start, never overlap
+    sections: Vec<ImageSection>,
+    module: ModuleInfo,
+    /// export table, PE exports or ELF dynamic symbols
+    exports: Vec<SymbolInfo>,
+    path: PathBuf,
+}
+
+impl ImageMemory {
+    pub fn open(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        // SAFETY: opened read only, we dont expect it change while opened
+        let data = unsafe { Mmap::map(&file)? };
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default()
+            .to_owned();
+        Self::parse(data, name, path.to_owned())
+    }
+
+    fn parse(data: Mmap, name: String, path: PathBuf) -> eyre::Result<Self> {
+        let object = object::File::parse(&*data)?;
+
+        let mut sections = vec![];
+        for s in object.sections() {
+            let Some(protection) = section_protection(&s) else {
+                continue;
+            };
+            if s.address() == 0 || s.size() == 0 {
+                continue;
+            }
+
+            let name = s.name().unwrap_or_default();
+            let (start, size) = (s.address() as usize, s.size() as usize);
+            if start.checked_add(size).is_none() {
+                eyre::bail!(
+                    "{}{name}{}",
+                    obfstr!("Section "),
+                    obfstr!(" out of address space")
+                );
+            }
+
+            let (file_offset, file_size) = match s.kind() {
+                SectionKind::UninitializedData | SectionKind::UninitializedTls => (0, 0),
+                _ => s.file_range().unwrap_or_default(),
+            };
+
+            sections.push(ImageSection {
+                name: name.to_owned(),
+                start,
+                size,
+                protection,
+                file_offset: file_offset as usize,
+                file_size: (file_size.min(s.size()) as usize)
+                    .min(data.len().saturating_sub(file_offset as usize)),
+            });
+        }
+
+        sections.sort_by_key(|s| s.start);
+        // tls templates and such can overlap real sections, first one wins
+        let mut end = 0;
+        sections.retain(|s| {
+            let keep = s.start >= end;
+            if keep {
+                end = s.end();
+            }
+            keep
+        });
+
+        let base = match object.relative_address_base() {
+            0 => {
+                object
+                    .segments()
+                    .map(|s| s.address() as usize)
+                    .min()
+                    .unwrap_or_default()
+                    & !(PAGE_SIZE - 1)
+            }
+            base => base as usize,
+        };
+        let size = sections
+            .last()
+            .map(|s| s.end())
+            .unwrap_or(base)
+            .saturating_sub(base);
+
+        let exports = object
+            .exports()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|e| e.address() != 0)
+            .map(|e| SymbolInfo {
+                module: name.clone(),
+                name: String::from_utf8_lossy(e.name()).into_owned(),
+                address: e.address() as usize,
+            })
+            .collect();
+
+        Ok(Self {
+            data,
+            sections,
+            module: ModuleInfo { name, base, size },
+            exports,
+            path,
+        })
+    }
+
+    pub fn sections(&self) -> &[ImageSection] {
+        &self.sections
+    }
+
+    fn region(&self, s: &ImageSection) -> MemoryRegion {
+        MemoryRegion {
+            start: s.start,
+            size: s.size,
+            protection: s.protection,
+            name: Some(self.module.name.clone()),
+        }
+    }
+
+    fn section_at(&self, addr: usize) -> Option<&ImageSection> {
+        let idx = self.sections.partition_point(|s| s.end() <= addr);
+        self.sections.get(idx).filter(|s| s.start <= addr)
+    }
+
+    /// Copy section bytes, zero the rest
+    ///
+    /// return number of bytes inside sections
+    fn read_raw(&self, addr: usize, buffer: &mut [u8]) -> usize {
+        let mut pos = 0;
+        let mut covered = 0;
+        while pos < buffer.len() {
+            let curr = addr.saturating_add(pos);
+            let idx = self.sections.partition_point(|s| s.end() <= curr);
+            let Some(s) = self.sections.get(idx) else {
+                buffer[pos..].fill(0);
+                break;
+            };
+
+            if curr < s.start {
+                let n = (s.start - curr).min(buffer.len() - pos);
+                buffer[pos..pos + n].fill(0);
+                pos += n;
+                continue;
+            }
+
+            let offset = curr - s.start;
+            let n = (s.end() - curr).min(buffer.len() - pos);
+            let raw = s.file_size.saturating_sub(offset).min(n);
+            if raw > 0 {
+                let from = s.file_offset + offset;
+                buffer[pos..pos + raw].copy_from_slice(&self.data[from..from + raw]);
+            }
+            buffer[pos + raw..pos + n].fill(0);
+            // only count up to the first hole
+            if s.protection.read && covered == pos {
+                covered += n;
+            }
+            pos += n;
+        }
+        covered
+    }
+}
+
+/// None when section not loaded in memory
+fn section_protection(section: &object::Section) -> Option<Protection> {
+    match section.flags() {
+        SectionFlags::Elf { sh_flags } => {
+            (sh_flags & elf::SHF_ALLOC as u64 != 0).then_some(Protection {
+                read: true,
+                write: sh_flags & elf::SHF_WRITE as u64 != 0,
+                execute: sh_flags & elf::SHF_EXECINSTR as u64 != 0,
+            })
+        }
+        SectionFlags::Coff { characteristics } => {
+            (characteristics & pe::IMAGE_SCN_MEM_DISCARDABLE == 0).then_some(Protection {
+                read: characteristics & pe::IMAGE_SCN_MEM_READ != 0,
+                write: characteristics & pe::IMAGE_SCN_MEM_WRITE != 0,
+                execute: characteristics & pe::IMAGE_SCN_MEM_EXECUTE != 0,
+            })
+        }
+        _ => Some(Protection {
+            read: true,
+            write: matches!(
+                section.kind(),
+                SectionKind::Data | SectionKind::UninitializedData
+            ),
+            execute: section.kind() == SectionKind::Text,
+        }),
+    }
+}
+
+impl MemoryReaderWriter for ImageMemory {
+    fn read_buf(&self, addr: usize, buffer: &mut [u8]) -> usize {
+        self.read_raw(addr, buffer)
+    }
+
+    fn write_buf(&self, addr: usize, _buffer: &[u8]) -> eyre::Result<()> {
+        eyre::bail!(
+            "{}{addr:#X}",
+            obfstr!("Image file is read only, cant write at ")
+        )
+    }
+
+    fn can_read(&self, addr: usize) -> bool {
+        self.section_at(addr).is_some_and(|s| s.protection.read)
+    }
+
+    fn modules(&self) -> Vec<ModuleInfo> {
+        vec![self.module.clone()]
+    }
+
+    fn symbols(&self) -> Vec<SymbolInfo> {
+        self.exports.clone()
+    }
+
+    /// Headers are not mapped, symbols only come from the file
+    fn module_file(&self, module_name: &str) -> Option<PathBuf> {
+        self.module
+            .name
+            .eq_ignore_ascii_case(module_name)
+            .then(|| self.path.clone())
+    }
+
+    fn regions(&self) -> Vec<MemoryRegion> {
+        self.sections.iter().map(|s| self.region(s)).collect()
+    }
+
+    fn region_at(&self, addr: usize) -> Option<MemoryRegion> {
+        self.section_at(addr).map(|s| self.region(s))
+    }
+}
+
+impl AddressResolver for ImageMemory {
+    fn module_symbol_to_address(&self, module_name: &str) -> Option<isize> {
+        self.module
+            .name
+            .eq_ignore_ascii_case(module_name)
+            .then_some(self.module.base as isize)
+    }
+
+    fn dereference(&self, address: usize) -> Option<isize> {
+        let mut buf = [0u8; size_of::<usize>()];
+        (self.read_raw(address, &mut buf) == buf.len()).then(|| isize::from_ne_bytes(buf))
+    }
+
+    fn find_signature(&self, module_name: &str, signature: &Signature) -> Option<isize> {
+        super::find_signature(self, module_name, signature).map(|a| a as isize)
+    }
+
+    fn read_exact(&self, address: usize, buffer: &mut [u8]) -> bool {
+        self.read_buf(address, buffer) == buffer.len()
+    }
+}
+
+impl MemoryState for ImageMemory {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{address_parser::AddressParser, memory::RegionKind};
+
+    use super::*;
+
+    /// name, rva, virtual size, raw bytes, characteristics
+    type TestSection = (&'static [u8; 8], u32, u32, Vec<u8>, u32);
+
+    /// PE32+ with .text, .rdata holding a pointer into .data, and bss like .data tail
+    fn build_pe(image_base: u64) -> Vec<u8> {
+        let sections: [TestSection; 3] = [
+            (
+                b".text\0\0\0",
+                0x1000,
+                0x100,
+                vec![0xC3; 0x100],
+                pe::IMAGE_SCN_CNT_CODE | pe::IMAGE_SCN_MEM_READ | pe::IMAGE_SCN_MEM_EXECUTE,
+            ),
+            (
+                b".rdata\0\0",
+                0x2000,
+                0x200,
+                {
+                    let mut b = vec![0u8; 0x200];
+                    b[0x10..0x18].copy_from_slice(&(image_base + 0x3008).to_le_bytes());
+                    b
+                },
+                pe::IMAGE_SCN_CNT_INITIALIZED_DATA | pe::IMAGE_SCN_MEM_READ,
+            ),
+            (
+                b".data\0\0\0",
+                0x3000,
+                0x400,
+                {
+                    let mut b = vec![0u8; 0x200];
+                    b[8..16].copy_from_slice(&0x1234u64.to_le_bytes());
+                    b
+                },
+                pe::IMAGE_SCN_CNT_INITIALIZED_DATA
+                    | pe::IMAGE_SCN_MEM_READ
+                    | pe::IMAGE_SCN_MEM_WRITE,
+            ),
+        ];
+
+        let mut out = vec![0u8; 0x40];
+        out[..2].copy_from_slice(b"MZ");
+        out[0x3C..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+
+        out.extend(b"PE\0\0");
+        out.extend(pe::IMAGE_FILE_MACHINE_AMD64.to_le_bytes());
+        out.extend((sections.len() as u16).to_le_bytes());
+        out.extend([0u8; 12]);
+        out.extend(240u16.to_le_bytes());
+        out.extend(pe::IMAGE_FILE_EXECUTABLE_IMAGE.to_le_bytes());
+
+        let mut optional = vec![0u8; 240];
+        optional[..2].copy_from_slice(&pe::IMAGE_NT_OPTIONAL_HDR64_MAGIC.to_le_bytes());
+        optional[24..32].copy_from_slice(&image_base.to_le_bytes());
+        optional[32..36].copy_from_slice(&0x1000u32.to_le_bytes());
+        optional[36..40].copy_from_slice(&0x200u32.to_le_bytes());
+        optional[56..60].copy_from_slice(&0x4000u32.to_le_bytes());
+        optional[60..64].copy_from_slice(&0x400u32.to_le_bytes());
+        optional[108..112].copy_from_slice(&16u32.to_le_bytes());
+        out.extend(optional);
+
+        let mut raw_at = 0x400u32;
+        let mut raw: Vec<u8> = vec![];
+        for (name, rva, virtual_size, bytes, characteristics) in &sections {
+            out.extend(*name);
+            out.extend(virtual_size.to_le_bytes());
+            out.extend(rva.to_le_bytes());
+            out.extend((bytes.len() as u32).to_le_bytes());
+            out.extend(raw_at.to_le_bytes());
+            out.extend([0u8; 12]);
+            out.extend(characteristics.to_le_bytes());
+            raw_at += bytes.len() as u32;
+            raw.extend(bytes);
+        }
+
+        out.resize(0x400, 0);
+        out.extend(raw);
+        out
+    }
+
+    #[test]
+    fn test_pe_image() {
+        let path = std::env::temp_dir().join(format!("mctest{}.exe", std::process::id()));
+        std::fs::write(&path, build_pe(0x140000000)).unwrap();
+        let image = ImageMemory::open(&path).unwrap();
+
+        _ = std::fs::remove_file(&path);
+
+        // .data end past the address space
+        let bad = path.with_extension("bad.exe");
+        std::fs::write(&bad, build_pe(u64::MAX - 0x31FF)).unwrap();
+        let err = ImageMemory::open(&bad).err().unwrap();
+        assert!(err.to_string().contains("out of address space"));
+        _ = std::fs::remove_file(&bad);
+
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert_eq!(image.modules()[0].base, 0x140000000);
+        assert_eq!(image.modules()[0].size, 0x3400);
+        assert_eq!(image.module_symbol_to_address(name), Some(0x140000000));
+
+        let text = &image.sections()[0];
+        assert_eq!(text.name, ".text");
+        assert!(text.protection.execute && !text.protection.write);
+        assert!(image.sections()[2].protection.write);
+        assert_eq!(
+            image.region_at(0x140002010).unwrap().kind(),
+            RegionKind::ModuleData
+        );
+        assert_eq!(
+            image.region_at(0x140001000).unwrap().kind(),
+            RegionKind::Code
+        );
+
+        assert!(image.can_read(0x140001000));
+        assert!(!image.can_read(0x140001100));
+        // tail of .data past raw bytes
+        assert!(image.can_read(0x1400033FF));
+        assert_eq!(image.dereference(0x140003200), Some(0));
+
+        let eval = AddressParser::new(&format!("[[{name} + 0x2010 ] ]"))
+            .parse()
+            .unwrap();
+        assert_eq!(eval.eval(&mut HashMap::new(), &image).unwrap(), 0x1234);
+        assert!(image.write_buf(0x140003000, &[0]).is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_elf_image() {
+        let exe = std::env::current_exe().unwrap();
+        let image = ImageMemory::open(&exe).unwrap();
+
+        let text = image.sections().iter().find(|s| s.name == ".text").unwrap();
+        assert!(text.protection.execute);
+        assert!(image.can_read(text.start));
+
+        let rodata = image
+            .sections()
+            .iter()
+            .find(|s| s.name == ".rodata")
+            .unwrap();
+        assert!(!rodata.protection.write && !rodata.protection.execute);
+
+        let name = exe.file_name().unwrap().to_str().unwrap();
+        assert_eq!(
+            image.module_symbol_to_address(name),
+            Some(image.modules()[0].base as isize)
+        );
+    }
+}
@@ -0,0 +1,356 @@
+// Linux live process backend
+// read/write with process_vm_readv/process_vm_writev
+// fallback to /proc/<pid>/mem when the syscall is blocked
+
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    os::unix::fs::FileExt,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+use crate::address_parser::{AddressResolver, Signature};
+
+use super::{
+    MemoryReaderWriter, MemoryRegion, MemoryState, ModuleInfo,
+    maps::{MemoryMapping, mapping_at, module_address, modules_from_mappings, parse_maps},
+};
+
+/// /proc/<pid>/maps is re-read at most this often
+const MAPS_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+pub fn read_maps(pid: i32) -> io::Result<Vec<MemoryMapping>> {
+    let content = std::fs::read_to_string(format!("/proc/{pid}/maps"))?;
+    Ok(parse_maps(&content))
+}
+
+struct MapsCache {
+    refreshed_at: Instant,
+    mappings: Vec<MemoryMapping>,
+    /// bumped when a refresh find modules loaded, unloaded or moved
+    generation: u64,
+}
+
+impl MapsCache {
+    fn replace(&mut self, mappings: Vec<MemoryMapping>) {
+        if modules_from_mappings(&mappings) != modules_from_mappings(&self.mappings) {
+            self.generation += 1;
+        }
+        self.mappings = mappings;
+    }
+}
+
+pub struct LinuxProcessMemory {
+    pid: i32,
+    mem: Option<File>,
+    maps: Mutex<MapsCache>,
+}
+
+impl LinuxProcessMemory {
+    pub fn attach(pid: i32) -> eyre::Result<Self> {
+        let mappings = read_maps(pid).map_err(|e| {
+            eyre::eyre!(
+                "{}{pid}: {e}",
+                obfstr!("Failed read memory maps of process ")
+            )
+        })?;
+
+        // /proc/<pid>/mem only used as fallback
+        // failed open is not fatal
+        let mem_path = format!("/proc/{pid}/mem");
+        let mem = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&mem_path)
+            .or_else(|_| File::open(&mem_path))
+            .inspect_err(|e| warn!("Failed open {mem_path}: {e}"))
+            .ok();
+
+        Ok(Self {
+            pid,
+            mem,
+            maps: Mutex::new(MapsCache {
+                refreshed_at: Instant::now(),
+                mappings,
+                generation: 0,
+            }),
+        })
+    }
+
+    pub fn pid(&self) -> i32 {
+        self.pid
+    }
+
+    pub fn is_alive(&self) -> bool {
+        Path::new(&format!("/proc/{}", self.pid)).exists()
+    }
+
+    /// Current mappings, refreshed when older than [`MAPS_REFRESH_INTERVAL`]
+    pub fn mappings(&self) -> Vec<MemoryMapping> {
+        self.with_mappings(|m| m.to_vec())
+    }
+
+    fn with_mappings<R>(&self, f: impl FnOnce(&[MemoryMapping]) -> R) -> R {
+        let mut cache = self.maps.lock();
+        if cache.refreshed_at.elapsed() >= MAPS_REFRESH_INTERVAL {
+            // keep old mappings if process gone
+            if let Ok(mappings) = read_maps(self.pid) {
+                cache.replace(mappings);
+            }
+            cache.refreshed_at = Instant::now();
+        }
+        f(&cache.mappings)
+    }
+
+    /// return number of bytes read from start of buffer
+    fn read_raw(&self, addr: usize, buffer: &mut [u8]) -> io::Result<usize> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        let vm_read = process_vm_read(self.pid, addr, buffer);
+        if let Ok(n) = vm_read
+            && n == buffer.len()
+        {
+            return Ok(n);
+        }
+
+        let Some(mem) = &self.mem else {
+            return vm_read;
+        };
+
+        // process_vm_readv failed entirely when the first page not mapped
+        // /proc/<pid>/mem give us the same partial behavior
+        match (vm_read, mem.read_at(buffer, addr as u64)) {
+            (Ok(a), Ok(b)) => Ok(a.max(b)),
+            (Ok(a), Err(_)) => Ok(a),
+            (Err(_), r) => r,
+        }
+    }
+
+    /// return number of bytes written from start of buffer
+    fn write_raw(&self, addr: usize, buffer: &[u8]) -> io::Result<usize> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        let vm_write = process_vm_write(self.pid, addr, buffer);
+        if let Ok(n) = vm_write
+            && n == buffer.len()
+        {
+            return Ok(n);
+        }
+
+        let Some(mem) = &self.mem else {
+            return vm_write;
+        };
+
+        // /proc/<pid>/mem also write into read-only pages
+        mem.write_at(buffer, addr as u64)
+    }
+}
+
+fn process_vm_read(pid: i32, addr: usize, buffer: &mut [u8]) -> io::Result<usize> {
+    let local = libc::iovec {
+        iov_base: buffer.as_mut_ptr() as *mut libc::c_void,
+        iov_len: buffer.len(),
+    };
+    let remote = libc::iovec {
+        iov_base: addr as *mut libc::c_void,
+        iov_len: buffer.len(),
+    };
+
+    let n = unsafe { libc::process_vm_readv(pid, &local, 1, &remote, 1, 0) };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(n as usize)
+}
+
+fn process_vm_write(pid: i32, addr: usize, buffer: &[u8]) -> io::Result<usize> {
+    let local = libc::iovec {
+        iov_base: buffer.as_ptr() as *mut libc::c_void,
+        iov_len: buffer.len(),
+    };
+    let remote = libc::iovec {
+        iov_base: addr as *mut libc::c_void,
+        iov_len: buffer.len(),
+    };
+
+    let n = unsafe { libc::process_vm_writev(pid, &local, 1, &remote, 1, 0) };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(n as usize)
+}
+
+impl MemoryReaderWriter for LinuxProcessMemory {
+    fn read_buf(&self, addr: usize, buffer: &mut [u8]) -> usize {
+        let n = self.read_raw(addr, buffer).unwrap_or_default();
+        // dont leave stale data in reused buffers
+        buffer[n..].fill(0);
+        n
+    }
+
+    fn write_buf(&self, addr: usize, buffer: &[u8]) -> eyre::Result<()> {
+        match self.write_raw(addr, buffer) {
+            Ok(n) if n == buffer.len() => Ok(()),
+            Ok(n) => eyre::bail!(
+                "{}{addr:#X} ({n}/{})",
+                obfstr!("Partial write at "),
+                buffer.len()
+            ),
+            Err(e) => eyre::bail!("{}{addr:#X}: {e}", obfstr!("Failed write at ")),
+        }
+    }
+
+    fn can_read(&self, addr: usize) -> bool {
+        self.with_mappings(|mappings| mapping_at(mappings, addr).is_some_and(|m| m.readable))
+    }
+
+    fn modules(&self) -> Vec<ModuleInfo> {
+        self.with_mappings(modules_from_mappings)
+    }
+
+    fn module_file(&self, module_name: &str) -> Option<PathBuf> {
+        self.with_mappings(|mappings| {
+            mappings
+                .iter()
+                .find(|m| m.file_name() == Some(module_name))
+                .and_then(|m| m.path.clone())
+        })
+        // through the process root, target can live in another mount namespace
+        .map(|path| PathBuf::from(format!("/proc/{}/root{path}", self.pid)))
+    }
+
+    fn module_generation(&self) -> u64 {
+        // refresh maps when due
+        self.with_mappings(|_| ());
+        self.maps.lock().generation
+    }
+
+    fn regions(&self) -> Vec<MemoryRegion> {
+        self.with_mappings(|mappings| mappings.iter().map(MemoryMapping::to_region).collect())
+    }
+
+    fn region_at(&self, addr: usize) -> Option<MemoryRegion> {
+        self.with_mappings(|mappings| mapping_at(mappings, addr).map(MemoryMapping::to_region))
+    }
+}
+
+impl AddressResolver for LinuxProcessMemory {
+    fn module_symbol_to_address(&self, module_name: &str) -> Option<isize> {
+        self.with_mappings(|mappings| module_address(mappings, module_name))
+            .map(|addr| addr as isize)
+    }
+
+    fn dereference(&self, address: usize) -> Option<isize> {
+        let mut buf = [0u8; size_of::<usize>()];
+        match self.read_raw(address, &mut buf) {
+            Ok(n) if n == buf.len() => Some(isize::from_ne_bytes(buf)),
+            _ => None,
+        }
+    }
+
+    fn find_signature(&self, module_name: &str, signature: &Signature) -> Option<isize> {
+        super::find_signature(self, module_name, signature).map(|a| a as isize)
+    }
+
+    fn read_exact(&self, address: usize, buffer: &mut [u8]) -> bool {
+        self.read_buf(address, buffer) == buffer.len()
+    }
+}
+
+impl MemoryState for LinuxProcessMemory {}
+
+#[cfg(test)]
+mod tests {
+    use std::process::{Child, Command, Stdio};
+
+    use crate::memory::RegionKind;
+
+    use super::*;
+
+    struct KillOnDrop(Child);
+
+    impl Drop for KillOnDrop {
+        fn drop(&mut self) {
+            _ = self.0.kill();
+            _ = self.0.wait();
+        }
+    }
+
+    fn spawn_sleeper() -> KillOnDrop {
+        let child = Command::new("sleep")
+            .arg("30")
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("spawn sleep");
+        // give the loader time to map libraries
+        std::thread::sleep(Duration::from_millis(100));
+        KillOnDrop(child)
+    }
+
+    #[test]
+    fn test_read_child_process() {
+        let child = spawn_sleeper();
+        let pid = child.0.id() as i32;
+        let memory = LinuxProcessMemory::attach(pid).unwrap();
+
+        let exe = std::fs::read_link(format!("/proc/{pid}/exe")).unwrap();
+        let exe_name = exe.file_name().unwrap().to_str().unwrap();
+
+        let base = memory
+            .module_symbol_to_address(exe_name)
+            .expect("executable mapped") as usize;
+        assert!(memory.can_read(base));
+        assert!(!memory.can_read(0));
+        assert_eq!(
+            memory.region_at(base).unwrap().name.as_deref(),
+            Some(exe_name)
+        );
+        assert!(
+            memory
+                .regions()
+                .iter()
+                .any(|r| r.kind() == RegionKind::Stack)
+        );
+
+        // module mapped from file offset 0
+        let mut magic = [0u8; 4];
+        memory.read_buf(base, &mut magic);
+        assert_eq!(&magic, b"\x7fELF");
+
+        let deref = memory.dereference(base).unwrap();
+        assert_eq!(&deref.to_ne_bytes()[..4], b"\x7fELF");
+        assert_eq!(memory.dereference(0), None);
+
+        // unreadable reads are zeroed
+        let mut buf = [0xCCu8; 8];
+        assert_eq!(memory.read_buf(0, &mut buf), 0);
+        assert_eq!(buf, [0; 8]);
+
+        let stack = memory
+            .mappings()
+            .into_iter()
+            .find(|m| m.path.as_deref() == Some("[stack]"))
+            .unwrap();
+        let target = stack.start + 0x10;
+        memory.write_buf(target, b"MakeClass").unwrap();
+        let mut buf = [0u8; 9];
+        assert_eq!(memory.read_buf(target, &mut buf), 9);
+        assert_eq!(&buf, b"MakeClass");
+        assert!(memory.write_buf(0, &[1]).is_err());
+
+        if let Some(libc) = memory
+            .mappings()
+            .iter()
+            .find(|m| m.file_name() == Some("libc.so.6"))
+        {
+            assert!(memory.module_symbol_to_address("libc.so.6").unwrap() as usize <= libc.start);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+// /proc/<pid>/maps parsing, shared by the live linux backend and remote targets
+
+use std::path::Path;
+
+use super::{MemoryRegion, ModuleInfo, Protection};
+
+#[derive(Debug, Clone)]
+pub struct MemoryMapping {
+    pub start: usize,
+    pub end: usize,
+    pub readable: bool,
+    pub writable: bool,
+    pub executable: bool,
+    pub file_offset: usize,
+    pub path: Option<String>,
+}
+
+impl MemoryMapping {
+    pub fn contains(&self, addr: usize) -> bool {
+        self.start <= addr && addr < self.end
+    }
+
+    /// `/usr/lib/libc.so.6` -> `libc.so.6`
+    pub fn file_name(&self) -> Option<&str> {
+        let path = self.path.as_deref()?;
+        if !path.starts_with('/') {
+            // [heap] [stack] [vdso] ...
+            return None;
+        }
+        Path::new(path).file_name()?.to_str()
+    }
+
+    pub fn to_region(&self) -> MemoryRegion {
+        MemoryRegion {
+            start: self.start,
+            size: self.end - self.start,
+            protection: Protection {
+                read: self.readable,
+                write: self.writable,
+                execute: self.executable,
+            },
+            name: self
+                .file_name()
+                .map(str::to_owned)
+                .or_else(|| self.path.clone()),
+        }
+    }
+}
+
+/// Mapping containing `addr`, mappings sorted by start
+pub fn mapping_at(mappings: &[MemoryMapping], addr: usize) -> Option<&MemoryMapping> {
+    let idx = mappings.partition_point(|m| m.end <= addr);
+    mappings.get(idx).filter(|m| m.contains(addr))
+}
+
+/// Parse content of /proc/<pid>/maps
+///
+/// `55d0c7a3f000-55d0c7a41000 r--p 00000000 103:02 1835046    /usr/bin/cat`
+pub fn parse_maps(content: &str) -> Vec<MemoryMapping> {
+    content
+        .lines()
+        .filter_map(|line| {
+            // first 5 columns separated by a single space
+            // path column padded
+            let mut columns = line.splitn(6, ' ');
+            let (start, end) = columns.next()?.split_once('-')?;
+            let perms = columns.next()?.as_bytes();
+            let file_offset = columns.next()?;
+            let _dev = columns.next()?;
+            let _inode = columns.next()?;
+            let path = columns
+                .next()
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(str::to_owned);
+
+            if perms.len() < 3 {
+                return None;
+            }
+
+            Some(MemoryMapping {
+                start: usize::from_str_radix(start, 16).ok()?,
+                end: usize::from_str_radix(end, 16).ok()?,
+                readable: perms[0] == b'r',
+                writable: perms[1] == b'w',
+                executable: perms[2] == b'x',
+                file_offset: usize::from_str_radix(file_offset, 16).ok()?,
+                path,
+            })
+        })
+        .collect()
+}
+
+/// Group file backed mappings into modules, one module mapped as several segments
+///
+/// Module only span mappings of its file that follow each other, any other
+/// mapping between end it. Same file mapped again elsewhere (plain mmap of it)
+/// is another run, the run with code is the module.
+pub fn modules_from_mappings(mappings: &[MemoryMapping]) -> Vec<ModuleInfo> {
+    // (module, has code)
+    let mut runs: Vec<(ModuleInfo, bool)> = vec![];
+    let mut prev_name = None;
+    for m in mappings {
+        let name = m.file_name();
+        match runs.last_mut() {
+            Some((run, code)) if name.is_some() && name == prev_name => {
+                run.size = m.end.max(run.base + run.size) - run.base;
+                *code |= m.executable;
+            }
+            _ => {
+                if let Some(name) = name {
+                    let module = ModuleInfo {
+                        name: name.to_owned(),
+                        base: m.start,
+                        size: m.end - m.start,
+                    };
+                    runs.push((module, m.executable));
+                }
+            }
+        }
+        prev_name = name;
+    }
+
+    let mut modules: Vec<(ModuleInfo, bool)> = vec![];
+    for (run, code) in runs {
+        match modules
+            .iter_mut()
+            .find(|(module, _)| module.name == run.name)
+        {
+            Some(module) if code && !module.1 => *module = (run, code),
+            Some(_) => {}
+            None => modules.push((run, code)),
+        }
+    }
+    modules.into_iter().map(|(module, _)| module).collect()
+}
+
+/// Base of the module, by file name or full path
+///
+/// Same base as [`modules_from_mappings`] report, pointer scan and signatures
+/// look the module up by it. `[heap]` like names get their lowest mapping.
+pub fn module_address(mappings: &[MemoryMapping], module_name: &str) -> Option<usize> {
+    let matches = |m: &&MemoryMapping| {
+        m.file_name() == Some(module_name) || m.path.as_deref() == Some(module_name)
+    };
+    let Some(name) = mappings.iter().find(matches)?.file_name() else {
+        return mappings.iter().filter(matches).map(|m| m.start).min();
+    };
+    modules_from_mappings(mappings)
+        .into_iter()
+        .find(|m| m.name == name)
+        .map(|m| m.base)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::memory::RegionKind;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_maps() {
+        let maps = parse_maps(
+            "55d0c7a3f000-55d0c7a41000 r-xp 00001000 103:02 1835046                   /usr/bin/cat\n\
+             7ffd5e3b1000-7ffd5e3d2000 rw-p 00000000 00:00 0                          [stack]\n\
+             7f0000000000-7f0000001000 ---p 00000000 00:00 0\n",
+        );
+
+        assert_eq!(maps.len(), 3);
+        assert_eq!(maps[0].start, 0x55d0c7a3f000);
+        assert_eq!(maps[0].end, 0x55d0c7a41000);
+        assert!(maps[0].readable && !maps[0].writable && maps[0].executable);
+        assert_eq!(maps[0].file_offset, 0x1000);
+        assert_eq!(maps[0].file_name(), Some("cat"));
+        assert_eq!(maps[1].path.as_deref(), Some("[stack]"));
+        assert_eq!(maps[1].file_name(), None);
+        assert!(!maps[2].readable);
+        assert_eq!(maps[2].path, None);
+
+        assert_eq!(maps[0].to_region().kind(), RegionKind::Code);
+        assert_eq!(maps[1].to_region().kind(), RegionKind::Stack);
+        assert_eq!(maps[2].to_region().kind(), RegionKind::Other);
+        assert_eq!(
+            mapping_at(&maps, 0x55d0c7a40fff).unwrap().start,
+            0x55d0c7a3f000
+        );
+        assert!(mapping_at(&maps, 0x55d0c7a41000).is_none());
+    }
+
+    #[test]
+    fn test_modules_from_mappings() {
+        let maps = parse_maps(
+            "1000-2000 r--p 00000000 00:00 0 /usr/bin/game\n\
+             2000-3000 r-xp 00001000 00:00 0 /usr/bin/game\n\
+             5000-6000 rw-p 00003000 00:00 0 /usr/bin/game\n\
+             6000-7000 rw-p 00000000 00:00 0 [heap]\n\
+             8000-9000 r--p 00000000 00:00 0 /usr/bin/game\n\
+             a000-b000 r-xp 00000000 00:00 0 /usr/lib/libc.so.6\n",
+        );
+        let modules = modules_from_mappings(&maps);
+
+        // second mmap of the file dont stretch the module over heap
+        assert_eq!(modules.len(), 2);
+        assert_eq!((modules[0].base, modules[0].size), (0x1000, 0x5000));
+        assert!(!modules[0].contains(0x6000));
+        assert!(!modules[0].contains(0x8000));
+        assert_eq!(module_address(&maps, "game"), Some(0x1000));
+        assert_eq!(module_address(&maps, "[heap]"), Some(0x6000));
+
+        // plain mmap below the module, run with code still win
+        let maps = parse_maps(
+            "1000-2000 r--p 00000000 00:00 0 /usr/bin/game\n\
+             4000-5000 r-xp 00000000 00:00 0 /usr/lib/libc.so.6\n\
+             8000-9000 r--p 00000000 00:00 0 /usr/bin/game\n\
+             9000-a000 r-xp 00001000 00:00 0 /usr/bin/game\n",
+        );
+        let modules = modules_from_mappings(&maps);
+        assert_eq!((modules[0].base, modules[0].size), (0x8000, 0x2000));
+        for name in ["game", "/usr/bin/game"] {
+            assert_eq!(module_address(&maps, name), Some(modules[0].base));
+        }
+        assert_eq!(module_address(&maps, "libc.so.6"), Some(0x4000));
+    }
+}
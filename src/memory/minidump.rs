@@ -0,0 +1,551 @@
+// Windows minidump
+//
+// Only the streams we need are parsed: module list for names and memory lists
+// for the bytes. Full memory dumps use Memory64ListStream, small ones MemoryListStream.
+
+use std::{fs::File, path::Path};
+
+use memmap2::Mmap;
+
+use crate::address_parser::{AddressResolver, Signature};
+
+use super::{
+    MemoryReaderWriter, MemoryRegion, MemoryState, ModuleInfo, Protection, module_name_at,
+};
+
+pub(super) const SIGNATURE: &[u8; 4] = b"MDMP";
+
+const MODULE_LIST_STREAM: u32 = 4;
+const MEMORY_LIST_STREAM: u32 = 5;
+const MEMORY64_LIST_STREAM: u32 = 9;
+const MEMORY_INFO_LIST_STREAM: u32 = 16;
+
+const MEM_COMMIT: u32 = 0x1000;
+
+const MODULE_SIZE: usize = 108;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinidumpRange {
+    pub start: usize,
+    pub size: usize,
+    /// offset of the bytes in dump file
+    rva: usize,
+}
+
+impl MinidumpRange {
+    /// checked on parse, cant overflow
+    pub fn end(&self) -> usize {
+        self.start + self.size
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinidumpModule {
+    /// full path as written by the dumper, usually windows style
+    pub path: String,
+    pub base: usize,
+    pub size: usize,
+}
+
+impl MinidumpModule {
+    /// `C:\Games\game.exe` -> `game.exe`
+    pub fn name(&self) -> &str {
+        self.path.rsplit(['\\', '/']).next().unwrap_or(&self.path)
+    }
+}
+
+pub struct MinidumpMemory {
+    data: Mmap,
+    /// sorted by start
+    ranges: Vec<MinidumpRange>,
+    modules: Vec<MinidumpModule>,
+    /// committed regions with protection, sorted by start, empty when dump has no info list
+    infos: Vec<(usize, usize, Protection)>,
+}
+
+impl MinidumpMemory {
+    pub fn open(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: opened read only, dump files dont change under us
+        let data = unsafe { Mmap::map(&file)? };
+        Self::parse(data)
+    }
+
+    fn parse(data: Mmap) -> eyre::Result<Self> {
+        if data.get(..4) != Some(SIGNATURE) {
+            eyre::bail!("{}", obfstr!("Not a minidump file"));
+        }
+
+        let stream_count = read_u32(&data, 8)? as usize;
+        let directory = read_u32(&data, 12)? as usize;
+
+        let mut ranges = vec![];
+        let mut modules = vec![];
+        let mut infos = vec![];
+        for i in 0..stream_count {
+            let entry = directory + i * 12;
+            let stream_type = read_u32(&data, entry)?;
+            let rva = read_u32(&data, entry + 8)? as usize;
+
+            match stream_type {
+                MODULE_LIST_STREAM => {
+                    let count = read_u32(&data, rva)? as usize;
+                    for m in 0..count {
+                        let at = rva + 4 + m * MODULE_SIZE;
+                        modules.push(MinidumpModule {
+                            base: read_u64(&data, at)? as usize,
+                            size: read_u32(&data, at + 8)? as usize,
+                            path: read_string(&data, read_u32(&data, at + 20)? as usize)?,
+                        });
+                    }
+                }
+                MEMORY_LIST_STREAM => {
+                    let count = read_u32(&data, rva)? as usize;
+                    for r in 0..count {
+                        let at = rva + 4 + r * 16;
+                        let start = read_u64(&data, at)? as usize;
+                        let size = read_u32(&data, at + 8)? as usize;
+                        checked_end(start, size)?;
+                        ranges.push(MinidumpRange {
+                            start,
+                            size,
+                            rva: read_u32(&data, at + 12)? as usize,
+                        });
+                    }
+                }
+                MEMORY64_LIST_STREAM => {
+                    // ranges stored back to back from base rva
+                    let count = read_u64(&data, rva)? as usize;
+                    let mut range_rva = read_u64(&data, rva + 8)? as usize;
+                    for r in 0..count {
+                        let at = rva + 16 + r * 16;
+                        let start = read_u64(&data, at)? as usize;
+                        let size = read_u64(&data, at + 8)? as usize;
+                        checked_end(start, size)?;
+                        ranges.push(MinidumpRange {
+                            start,
+                            size,
+                            rva: range_rva,
+                        });
+                        range_rva = checked_end(range_rva, size)?;
+                    }
+                }
+                MEMORY_INFO_LIST_STREAM => {
+                    let header_size = read_u32(&data, rva)? as usize;
+                    let entry_size = read_u32(&data, rva + 4)? as usize;
+                    let count = read_u64(&data, rva + 8)? as usize;
+                    // entry_size 0 would loop count times over the same bytes
+                    let fits = count
+                        .checked_mul(entry_size)
+                        .and_then(|n| n.checked_add(rva + header_size))
+                        .is_some_and(|end| end <= data.len());
+                    if header_size < 16 || entry_size < 48 || !fits {
+                        eyre::bail!("{}", obfstr!("Minidump memory info list is broken"));
+                    }
+                    for r in 0..count {
+                        let at = rva + header_size + r * entry_size;
+                        if read_u32(&data, at + 32)? != MEM_COMMIT {
+                            continue;
+                        }
+                        let start = read_u64(&data, at)? as usize;
+                        let size = read_u64(&data, at + 24)? as usize;
+                        checked_end(start, size)?;
+                        infos.push((start, size, page_protection(read_u32(&data, at + 36)?)));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // drop what point outside the file, keep the part that fit
+        for r in ranges.iter_mut() {
+            r.size = r.size.min(data.len().saturating_sub(r.rva));
+        }
+        ranges.retain(|r| r.size > 0);
+        ranges.sort_by_key(|r| r.start);
+        infos.sort_by_key(|i| i.0);
+
+        Ok(Self {
+            data,
+            ranges,
+            modules,
+            infos,
+        })
+    }
+
+    pub fn ranges(&self) -> &[MinidumpRange] {
+        &self.ranges
+    }
+
+    pub fn minidump_modules(&self) -> &[MinidumpModule] {
+        &self.modules
+    }
+
+    fn range_at(&self, addr: usize) -> Option<&MinidumpRange> {
+        let idx = self.ranges.partition_point(|r| r.end() <= addr);
+        self.ranges.get(idx).filter(|r| r.start <= addr)
+    }
+
+    /// Protection from the info list, READ when dump has none
+    fn region(&self, r: &MinidumpRange, modules: &[ModuleInfo]) -> MemoryRegion {
+        let idx = self.infos.partition_point(|i| i.0 + i.1 <= r.start);
+        let protection = self
+            .infos
+            .get(idx)
+            .filter(|i| i.0 <= r.start)
+            .map(|i| i.2)
+            .unwrap_or(Protection::READ);
+        MemoryRegion {
+            start: r.start,
+            size: r.size,
+            protection,
+            name: module_name_at(modules, r.start),
+        }
+    }
+
+    /// Copy dumped bytes, zero the rest
+    ///
+    /// return number of bytes found in the dump
+    fn read_raw(&self, addr: usize, buffer: &mut [u8]) -> usize {
+        let mut pos = 0;
+        let mut covered = 0;
+        while pos < buffer.len() {
+            let curr = addr.saturating_add(pos);
+            let idx = self.ranges.partition_point(|r| r.end() <= curr);
+            match self.ranges.get(idx) {
+                Some(r) if r.start <= curr => {
+                    let offset = curr - r.start;
+                    let n = (r.size - offset).min(buffer.len() - pos);
+                    let from = r.rva + offset;
+                    buffer[pos..pos + n].copy_from_slice(&self.data[from..from + n]);
+                    // only count up to the first hole
+                    if covered == pos {
+                        covered += n;
+                    }
+                    pos += n;
+                }
+                Some(r) => {
+                    let n = (r.start - curr).min(buffer.len() - pos);
+                    buffer[pos..pos + n].fill(0);
+                    pos += n;
+                }
+                None => {
+                    buffer[pos..].fill(0);
+                    break;
+                }
+            }
+        }
+        covered
+    }
+}
+
+/// PAGE_* constants, guard and cache flags ignored
+fn page_protection(protect: u32) -> Protection {
+    const READ: u32 = 0x02 | 0x04 | 0x08 | 0x20 | 0x40 | 0x80;
+    const WRITE: u32 = 0x04 | 0x08 | 0x40 | 0x80;
+    const EXECUTE: u32 = 0x10 | 0x20 | 0x40 | 0x80;
+    Protection {
+        read: protect & READ != 0,
+        write: protect & WRITE != 0,
+        execute: protect & EXECUTE != 0,
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> eyre::Result<u32> {
+    let Some(b) = data.get(offset..offset.saturating_add(4)) else {
+        eyre::bail!("{}{offset:#X}", obfstr!("Minidump truncated at "));
+    };
+    Ok(u32::from_le_bytes(b.try_into()?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> eyre::Result<u64> {
+    let Some(b) = data.get(offset..offset.saturating_add(8)) else {
+        eyre::bail!("{}{offset:#X}", obfstr!("Minidump truncated at "));
+    };
+    Ok(u64::from_le_bytes(b.try_into()?))
+}
+
+fn checked_end(start: usize, size: usize) -> eyre::Result<usize> {
+    let Some(end) = start.checked_add(size) else {
+        eyre::bail!(
+            "{}{start:#X}",
+            obfstr!("Minidump range out of address space at ")
+        );
+    };
+    Ok(end)
+}
+
+/// MINIDUMP_STRING: u32 byte length then utf16
+fn read_string(data: &[u8], rva: usize) -> eyre::Result<String> {
+    let len = read_u32(data, rva)? as usize;
+    let Some(b) = data.get(rva + 4..rva + 4 + len) else {
+        eyre::bail!("{}{rva:#X}", obfstr!("Minidump string truncated at "));
+    };
+    let units = b
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect::<Vec<_>>();
+    Ok(String::from_utf16_lossy(&units))
+}
+
+impl MemoryReaderWriter for MinidumpMemory {
+    fn read_buf(&self, addr: usize, buffer: &mut [u8]) -> usize {
+        self.read_raw(addr, buffer)
+    }
+
+    fn write_buf(&self, addr: usize, _buffer: &[u8]) -> eyre::Result<()> {
+        eyre::bail!(
+            "{}{addr:#X}",
+            obfstr!("Minidump is read only, cant write at ")
+        )
+    }
+
+    fn can_read(&self, addr: usize) -> bool {
+        self.range_at(addr).is_some()
+    }
+
+    fn modules(&self) -> Vec<ModuleInfo> {
+        self.modules
+            .iter()
+            .map(|m| ModuleInfo {
+                name: m.name().to_owned(),
+                base: m.base,
+                size: m.size,
+            })
+            .collect()
+    }
+
+    fn regions(&self) -> Vec<MemoryRegion> {
+        let modules = self.modules();
+        self.ranges
+            .iter()
+            .map(|r| self.region(r, &modules))
+            .collect()
+    }
+
+    /// Hex view ask for every pointer each frame, dont build the whole list
+    fn region_at(&self, addr: usize) -> Option<MemoryRegion> {
+        let r = self.range_at(addr)?;
+        // only the module covering it
+        let module = self
+            .modules
+            .iter()
+            .find(|m| m.base <= r.start && r.start - m.base < m.size)
+            .map(|m| ModuleInfo {
+                name: m.name().to_owned(),
+                base: m.base,
+                size: m.size,
+            });
+        Some(self.region(r, module.as_slice()))
+    }
+}
+
+impl AddressResolver for MinidumpMemory {
+    fn module_symbol_to_address(&self, module_name: &str) -> Option<isize> {
+        // windows file names are case insensitive
+        self.modules
+            .iter()
+            .find(|m| m.name().eq_ignore_ascii_case(module_name) || m.path == module_name)
+            .map(|m| m.base as isize)
+    }
+
+    fn dereference(&self, address: usize) -> Option<isize> {
+        let mut buf = [0u8; size_of::<usize>()];
+        (self.read_raw(address, &mut buf) == buf.len()).then(|| isize::from_ne_bytes(buf))
+    }
+
+    fn find_signature(&self, module_name: &str, signature: &Signature) -> Option<isize> {
+        super::find_signature(self, module_name, signature).map(|a| a as isize)
+    }
+
+    fn read_exact(&self, address: usize, buffer: &mut [u8]) -> bool {
+        self.read_buf(address, buffer) == buffer.len()
+    }
+}
+
+impl MemoryState for MinidumpMemory {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{address_parser::AddressParser, memory::RegionKind};
+
+    use super::*;
+
+    /// Header, directory, module list, memory list, memory64 list, memory info list, then data
+    fn build_minidump(
+        modules: &[(u64, u32, &str)],
+        small: &[(u64, Vec<u8>)],
+        full: &[(u64, Vec<u8>)],
+        infos: &[(u64, u64, u32)],
+    ) -> Vec<u8> {
+        let directory = 32;
+        let module_list = directory + 4 * 12;
+        let memory_list = module_list + 4 + modules.len() * MODULE_SIZE;
+        let memory64_list = memory_list + 4 + small.len() * 16;
+        let memory_info_list = memory64_list + 16 + full.len() * 16;
+        let mut data_at = memory_info_list + 16 + infos.len() * 48;
+
+        let mut out = vec![];
+        out.extend(SIGNATURE);
+        out.extend(0xA793u32.to_le_bytes());
+        out.extend(4u32.to_le_bytes());
+        out.extend((directory as u32).to_le_bytes());
+        out.extend([0u8; 16]);
+
+        for (stream_type, rva) in [
+            (MODULE_LIST_STREAM, module_list),
+            (MEMORY_LIST_STREAM, memory_list),
+            (MEMORY64_LIST_STREAM, memory64_list),
+            (MEMORY_INFO_LIST_STREAM, memory_info_list),
+        ] {
+            out.extend(stream_type.to_le_bytes());
+            out.extend(0u32.to_le_bytes());
+            out.extend((rva as u32).to_le_bytes());
+        }
+
+        let mut tail = vec![];
+
+        out.extend((modules.len() as u32).to_le_bytes());
+        for (base, size, path) in modules {
+            let name_rva = data_at + tail.len();
+            let utf16 = path
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes)
+                .collect::<Vec<_>>();
+            tail.extend((utf16.len() as u32).to_le_bytes());
+            tail.extend(utf16);
+            tail.extend([0, 0]);
+
+            let mut module = vec![0u8; MODULE_SIZE];
+            module[..8].copy_from_slice(&base.to_le_bytes());
+            module[8..12].copy_from_slice(&size.to_le_bytes());
+            module[20..24].copy_from_slice(&(name_rva as u32).to_le_bytes());
+            out.extend(module);
+        }
+
+        out.extend((small.len() as u32).to_le_bytes());
+        for (start, bytes) in small {
+            out.extend(start.to_le_bytes());
+            out.extend((bytes.len() as u32).to_le_bytes());
+            out.extend(((data_at + tail.len()) as u32).to_le_bytes());
+            tail.extend(bytes);
+        }
+
+        data_at += tail.len();
+        out.extend((full.len() as u64).to_le_bytes());
+        out.extend((data_at as u64).to_le_bytes());
+        for (start, bytes) in full {
+            out.extend(start.to_le_bytes());
+            out.extend((bytes.len() as u64).to_le_bytes());
+            tail.extend(bytes);
+        }
+
+        out.extend(16u32.to_le_bytes());
+        out.extend(48u32.to_le_bytes());
+        out.extend((infos.len() as u64).to_le_bytes());
+        for (base, size, protect) in infos {
+            let mut info = vec![0u8; 48];
+            info[..8].copy_from_slice(&base.to_le_bytes());
+            info[24..32].copy_from_slice(&size.to_le_bytes());
+            info[32..36].copy_from_slice(&MEM_COMMIT.to_le_bytes());
+            info[36..40].copy_from_slice(&protect.to_le_bytes());
+            out.extend(info);
+        }
+
+        out.extend(tail);
+        out
+    }
+
+    fn open_minidump(bytes: &[u8]) -> eyre::Result<MinidumpMemory> {
+        let path = std::env::temp_dir().join(format!("make-class-test-{}.dmp", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let dump = MinidumpMemory::open(&path);
+        _ = std::fs::remove_file(&path);
+        dump
+    }
+
+    #[test]
+    fn test_minidump() {
+        let mut image = vec![0u8; 0x100];
+        image[..2].copy_from_slice(b"MZ");
+        image[0x40..0x48].copy_from_slice(&0x7FF000usize.to_ne_bytes());
+
+        let mut heap = vec![0u8; 0x20];
+        heap[0x10..0x18].copy_from_slice(&0xDEADusize.to_ne_bytes());
+
+        let dump = open_minidump(&build_minidump(
+            &[
+                (0x140000000, 0x2000, r"C:\Games\Game.exe"),
+                (0x7FFA0000, 0x1000, r"C:\Windows\System32\ntdll.dll"),
+            ],
+            &[(0x7FF000, heap)],
+            &[(0x140000000, image), (0x140000100, vec![0xAA; 0x10])],
+            &[
+                // PAGE_READONLY, PAGE_EXECUTE_READ, PAGE_READWRITE
+                (0x140000000, 0x100, 0x02),
+                (0x140000100, 0x1F00, 0x20),
+                (0x7FF000, 0x1000, 0x04),
+            ],
+        ))
+        .unwrap();
+
+        assert_eq!(dump.ranges().len(), 3);
+        assert_eq!(dump.ranges()[0].start, 0x7FF000);
+        assert_eq!(dump.minidump_modules()[0].name(), "Game.exe");
+
+        let regions = dump.regions();
+        assert_eq!(regions[0].kind(), RegionKind::Heap);
+        assert_eq!(regions[1].kind(), RegionKind::ModuleData);
+        assert_eq!(regions[1].name.as_deref(), Some("Game.exe"));
+        assert_eq!(
+            dump.region_at(0x140000108).unwrap().kind(),
+            RegionKind::Code
+        );
+
+        assert!(dump.can_read(0x140000000));
+        assert!(dump.can_read(0x14000010F));
+        assert!(!dump.can_read(0x140000110));
+
+        // memory64 ranges are back to back in the file
+        let mut buf = [0u8; 4];
+        dump.read_buf(0x1400000FE, &mut buf);
+        assert_eq!(buf, [0, 0, 0xAA, 0xAA]);
+        dump.read_buf(0x14000010E, &mut buf);
+        assert_eq!(buf, [0xAA, 0xAA, 0, 0]);
+        assert!(dump.write_buf(0x7FF000, &[0]).is_err());
+
+        assert_eq!(dump.module_symbol_to_address("game.exe"), Some(0x140000000));
+        assert_eq!(dump.modules()[1].name, "ntdll.dll");
+
+        let eval = AddressParser::new("[[game.exe + 0x40 ] + 0x10 ]")
+            .parse()
+            .unwrap();
+        assert_eq!(eval.eval(&mut HashMap::new(), &dump).unwrap(), 0xDEAD);
+        assert_eq!(dump.dereference(0x7FF01C), None);
+
+        // ranges wrapping around the address space
+        let wrap = u64::MAX - 0xF;
+        let bad = build_minidump(&[], &[(wrap, vec![0; 0x20])], &[], &[]);
+        assert!(open_minidump(&bad).is_err());
+        let bad = build_minidump(&[], &[], &[(wrap, vec![0; 0x20])], &[]);
+        assert!(open_minidump(&bad).is_err());
+        let bad = build_minidump(&[], &[], &[], &[(wrap, 0x20, 0x04)]);
+        assert!(open_minidump(&bad).is_err());
+
+        // info list header right after empty module, memory and memory64 lists
+        let info_list = 32 + 4 * 12 + 4 + 4 + 16;
+        let mut bad = build_minidump(&[], &[], &[], &[(0x10000, 0x1000, 0x04)]);
+        bad[info_list + 4..info_list + 8].copy_from_slice(&0u32.to_le_bytes());
+        assert!(open_minidump(&bad).is_err());
+        let mut bad = build_minidump(&[], &[], &[], &[(0x10000, 0x1000, 0x04)]);
+        bad[info_list + 8..info_list + 16].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(open_minidump(&bad).is_err());
+        let good = build_minidump(&[], &[], &[], &[(0x10000, 0x1000, 0x04)]);
+        assert!(open_minidump(&good).is_ok());
+
+        assert!(open_minidump(b"MDMP").is_err());
+        assert!(open_minidump(b"PK\x03\x04").is_err());
+    }
+}
@@ -0,0 +1,346 @@
+pub mod cache;
+pub mod elf_core;
+pub mod gdb;
+pub mod image;
+#[cfg(target_os = "linux")]
+pub mod linux;
+pub mod maps;
+pub mod minidump;
+pub mod poller;
+pub mod snapshot;
+pub mod symbols;
+
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::address_parser::{AddressResolver, Signature};
+
+pub const PAGE_SIZE: usize = 0x1000;
+
+/// Bytes read at once while scanning a module for signature
+const SIGNATURE_CHUNK: usize = 0x10_0000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Protection {
+    pub read: bool,
+    pub write: bool,
+    pub execute: bool,
+}
+
+impl Protection {
+    pub const READ: Self = Self {
+        read: true,
+        write: false,
+        execute: false,
+    };
+}
+
+impl std::fmt::Display for Protection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let flag = |set: bool, c: char| if set { c } else { '-' };
+        write!(
+            f,
+            "{}{}{}",
+            flag(self.read, 'r'),
+            flag(self.write, 'w'),
+            flag(self.execute, 'x')
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleInfo {
+    pub name: String,
+    pub base: usize,
+    pub size: usize,
+}
+
+impl ModuleInfo {
+    pub fn contains(&self, addr: usize) -> bool {
+        self.base <= addr && addr - self.base < self.size
+    }
+}
+
+/// Exported symbol of a module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolInfo {
+    pub module: String,
+    pub name: String,
+    pub address: usize,
+}
+
+/// Name of module covering `addr`
+pub fn module_name_at(modules: &[ModuleInfo], addr: usize) -> Option<String> {
+    modules
+        .iter()
+        .find(|m| m.contains(addr))
+        .map(|m| m.name.clone())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionKind {
+    Code,
+    ModuleData,
+    Heap,
+    Stack,
+    Other,
+}
+
+impl std::fmt::Display for RegionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RegionKind::Code => "code",
+            RegionKind::ModuleData => "module data",
+            RegionKind::Heap => "heap",
+            RegionKind::Stack => "stack",
+            RegionKind::Other => "other",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemoryRegion {
+    pub start: usize,
+    pub size: usize,
+    pub protection: Protection,
+    /// backing module or file name, `[heap]` `[stack]` style for anonymous ones
+    pub name: Option<String>,
+}
+
+impl MemoryRegion {
+    pub fn end(&self) -> usize {
+        self.start + self.size
+    }
+
+    pub fn contains(&self, addr: usize) -> bool {
+        self.start <= addr && addr - self.start < self.size
+    }
+
+    /// Best guess what live here, from permission and backing name
+    pub fn kind(&self) -> RegionKind {
+        match self.name.as_deref() {
+            _ if self.protection.execute => RegionKind::Code,
+            Some("[heap]") => RegionKind::Heap,
+            Some(n) if n.starts_with("[stack") => RegionKind::Stack,
+            Some(n) if n.starts_with('[') => RegionKind::Other,
+            Some(_) => RegionKind::ModuleData,
+            // anonymous writable memory is mostly allocator arenas
+            None if self.protection.write => RegionKind::Heap,
+            None => RegionKind::Other,
+        }
+    }
+}
+
+pub trait MemoryReaderWriter: AddressResolver {
+    /// Fill buffer, return how many bytes from its start could be read,
+    /// everything after is zeroed
+    fn read_buf(&self, addr: usize, buffer: &mut [u8]) -> usize;
+    /// Write whole buffer or fail
+    fn write_buf(&self, addr: usize, buffer: &[u8]) -> eyre::Result<()>;
+    fn can_read(&self, addr: usize) -> bool;
+
+    /// Loaded modules, empty when backend dont know
+    fn modules(&self) -> Vec<ModuleInfo> {
+        vec![]
+    }
+
+    /// Exported symbols of loaded modules, empty when backend dont know
+    fn symbols(&self) -> Vec<SymbolInfo> {
+        vec![]
+    }
+
+    /// Symbols of one module, `module!symbol` completion list them
+    fn module_symbols(&self, module_name: &str) -> Vec<SymbolInfo> {
+        self.symbols()
+            .into_iter()
+            .filter(|s| s.module.eq_ignore_ascii_case(module_name))
+            .collect()
+    }
+
+    /// Bumped when loaded modules change, dumps and files never change
+    fn module_generation(&self) -> u64 {
+        0
+    }
+
+    /// File a module was loaded from, None when it is not on this machine
+    fn module_file(&self, _module_name: &str) -> Option<PathBuf> {
+        None
+    }
+
+    /// Memory layout sorted by start, empty when backend dont know
+    fn regions(&self) -> Vec<MemoryRegion> {
+        vec![]
+    }
+
+    /// Called per pointer each frame, backends override it to not build the whole list
+    fn region_at(&self, addr: usize) -> Option<MemoryRegion> {
+        let regions = self.regions();
+        let idx = regions.partition_point(|r| r.end() <= addr);
+        regions.into_iter().nth(idx).filter(|r| r.start <= addr)
+    }
+
+    /// Drop cached bytes, app call it once per frame
+    fn invalidate_cache(&self) {}
+
+    /// Hint a range about to be read in small pieces
+    fn prefetch(&self, _addr: usize, _len: usize) {}
+
+    /// See [`readable_bytes`], cache answer it without copying bytes
+    fn readable_bytes(&self, addr: usize, len: usize) -> usize {
+        readable_bytes(self, addr, len)
+    }
+}
+
+/// How many bytes of the range can be read, holes skipped using region layout
+pub fn readable_bytes<M: MemoryReaderWriter + ?Sized>(
+    memory: &M,
+    addr: usize,
+    len: usize,
+) -> usize {
+    let regions = memory.regions();
+    let end = addr.saturating_add(len);
+    let mut buf = vec![0u8; PAGE_SIZE];
+    let mut readable = 0;
+    let mut curr = addr;
+    while curr < end {
+        let n = (end - curr).min(PAGE_SIZE);
+        let got = memory.read_buf(curr, &mut buf[..n]);
+        readable += got;
+        if got == n {
+            curr += n;
+            continue;
+        }
+
+        let Some(next) = next_readable(&regions, curr + got) else {
+            break;
+        };
+        curr = next;
+    }
+    readable
+}
+
+/// Where to continue after unreadable `hole`, next page when layout unknown
+fn next_readable(regions: &[MemoryRegion], hole: usize) -> Option<usize> {
+    if regions.is_empty() {
+        return (hole & !(PAGE_SIZE - 1)).checked_add(PAGE_SIZE);
+    }
+    let idx = regions.partition_point(|r| r.start <= hole);
+    regions[idx..]
+        .iter()
+        .find(|r| r.protection.read)
+        .map(|r| r.start)
+}
+
+/// Module by name, as the backend resolve module names
+pub fn find_module(memory: &dyn MemoryReaderWriter, module_name: &str) -> Option<ModuleInfo> {
+    let base = memory.module_symbol_to_address(module_name)? as usize;
+    let modules = memory.modules();
+    let idx = modules
+        .iter()
+        .position(|m| m.base == base)
+        .or_else(|| modules.iter().position(|m| m.contains(base)))?;
+    modules.into_iter().nth(idx)
+}
+
+/// First match of `signature` inside module, unreadable parts skipped
+pub fn find_signature(
+    memory: &dyn MemoryReaderWriter,
+    module_name: &str,
+    signature: &Signature,
+) -> Option<usize> {
+    let module = find_module(memory, module_name)?;
+    let end = module.base + module.size;
+
+    let regions = memory.regions();
+    let mut buf = vec![0u8; SIGNATURE_CHUNK];
+    let mut curr = module.base;
+    while curr < end {
+        let n = (end - curr).min(SIGNATURE_CHUNK);
+        let got = memory.read_buf(curr, &mut buf[..n]);
+        if let Some(i) = signature.find(&buf[..got]) {
+            return Some(curr + i);
+        }
+
+        if got == n {
+            if curr + n == end {
+                break;
+            }
+            // overlap chunks so match across the border is not missed
+            curr += n.saturating_sub(signature.size() - 1).max(1);
+            continue;
+        }
+        curr = next_readable(&regions, curr + got)?;
+    }
+    None
+}
+
+pub struct NullMemoryReader;
+
+impl MemoryReaderWriter for NullMemoryReader {
+    fn read_buf(&self, _addr: usize, buffer: &mut [u8]) -> usize {
+        buffer.fill(0);
+        0
+    }
+
+    fn write_buf(&self, addr: usize, _buffer: &[u8]) -> eyre::Result<()> {
+        eyre::bail!(
+            "{}{addr:#X}",
+            obfstr!("No process attached, cant write at ")
+        )
+    }
+
+    fn can_read(&self, _addr: usize) -> bool {
+        false
+    }
+}
+
+impl AddressResolver for NullMemoryReader {
+    fn module_symbol_to_address(&self, module_name: &str) -> Option<isize> {
+        if module_name == obfstr!("sample.dll") {
+            return Some(0x1000);
+        }
+        None
+    }
+
+    fn dereference(&self, _address: usize) -> Option<isize> {
+        None
+    }
+}
+
+/// Send + Sync so backend can be read from poller thread
+pub trait MemoryState: MemoryReaderWriter + AddressResolver + Send + Sync {}
+
+impl MemoryState for NullMemoryReader {}
+
+/// Open a memory dump or executable file, format picked from its magic
+pub fn open_dump(path: &Path) -> eyre::Result<Box<dyn MemoryState>> {
+    let mut magic = [0u8; 18];
+    let n = File::open(path)?.read(&mut magic)?;
+    let magic = &magic[..n];
+
+    if magic.starts_with(snapshot::MAGIC) {
+        return Ok(Box::new(snapshot::MemorySnapshot::load(path)?));
+    }
+    if magic.starts_with(b"\x7fELF") {
+        // e_type, byte order from EI_DATA
+        let e_type = match magic.get(5..).unwrap_or_default() {
+            [2, .., a, b] => u16::from_be_bytes([*a, *b]),
+            [_, .., a, b] => u16::from_le_bytes([*a, *b]),
+            _ => 0,
+        };
+        if e_type == object::elf::ET_CORE {
+            return Ok(Box::new(elf_core::ElfCoreMemory::open(path)?));
+        }
+        return Ok(Box::new(image::ImageMemory::open(path)?));
+    }
+    if magic.starts_with(b"MZ") {
+        return Ok(Box::new(image::ImageMemory::open(path)?));
+    }
+    if magic.starts_with(minidump::SIGNATURE) {
+        return Ok(Box::new(minidump::MinidumpMemory::open(path)?));
+    }
+    eyre::bail!("{}{}", obfstr!("Unknown dump format: "), path.display())
+}
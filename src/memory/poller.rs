@@ -0,0 +1,140 @@
+// Background refresh of the memory cache
+//
+// Slow backend (remote stub, big process) would freeze the UI when fields
+// read on draw. Poller thread reload pages UI read recently at a set rate,
+// the inspector always draw from the latest refreshed pages.
+
+use std::{
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+use super::cache::MemoryCache;
+
+pub const MIN_RATE_HZ: u32 = 1;
+pub const MAX_RATE_HZ: u32 = 60;
+pub const DEFAULT_RATE_HZ: u32 = 10;
+
+/// Pages not read for this long are not refreshed anymore
+const PAGE_MAX_AGE: Duration = Duration::from_secs(2);
+
+#[derive(Default)]
+struct PollerShared {
+    target: Mutex<Option<MemoryCache>>,
+    rate_hz: AtomicU32,
+    paused: AtomicBool,
+    last_refresh: Mutex<Option<Instant>>,
+}
+
+pub struct MemoryPoller {
+    shared: Arc<PollerShared>,
+}
+
+impl MemoryPoller {
+    /// Thread exit after poller dropped
+    pub fn new(rate_hz: u32) -> Self {
+        let shared = Arc::new(PollerShared::default());
+        shared
+            .rate_hz
+            .store(rate_hz.clamp(MIN_RATE_HZ, MAX_RATE_HZ), Ordering::Relaxed);
+
+        let weak = Arc::downgrade(&shared);
+        std::thread::Builder::new()
+            .name(obfstring!("memory-poller"))
+            .spawn(move || poll_loop(weak))
+            .expect("spawn memory poller");
+
+        Self { shared }
+    }
+
+    pub fn watch(&self, cache: MemoryCache) {
+        cache.set_deferred(!self.is_paused());
+        self.shared.target.lock().replace(cache);
+        self.shared.last_refresh.lock().replace(Instant::now());
+    }
+
+    pub fn unwatch(&self) {
+        if let Some(cache) = self.shared.target.lock().take() {
+            cache.set_deferred(false);
+        }
+    }
+
+    /// Has a target and not paused, UI should not drop pages itself
+    pub fn is_active(&self) -> bool {
+        !self.is_paused() && self.shared.target.lock().is_some()
+    }
+
+    pub fn rate_hz(&self) -> u32 {
+        self.shared.rate_hz.load(Ordering::Relaxed)
+    }
+
+    pub fn set_rate_hz(&self, rate_hz: u32) {
+        self.shared
+            .rate_hz
+            .store(rate_hz.clamp(MIN_RATE_HZ, MAX_RATE_HZ), Ordering::Relaxed);
+    }
+
+    pub fn interval(&self) -> Duration {
+        interval_of(self.rate_hz())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.shared.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.shared.paused.store(paused, Ordering::Relaxed);
+        // nobody load queued pages while paused, UI read itself
+        if let Some(cache) = &*self.shared.target.lock() {
+            cache.set_deferred(!paused);
+        }
+        if !paused {
+            // time spent paused is not falling behind
+            self.shared.last_refresh.lock().replace(Instant::now());
+        }
+    }
+
+    /// Time since last finished refresh, None when not active
+    pub fn lag(&self) -> Option<Duration> {
+        if !self.is_active() {
+            return None;
+        }
+        self.shared.last_refresh.lock().map(|t| t.elapsed())
+    }
+
+    /// Backend can't keep up with the rate
+    pub fn is_stale(&self) -> bool {
+        let limit = (self.interval() * 3).max(Duration::from_millis(200));
+        self.lag().is_some_and(|lag| lag > limit)
+    }
+}
+
+fn interval_of(rate_hz: u32) -> Duration {
+    Duration::from_secs(1) / rate_hz.max(1)
+}
+
+fn poll_loop(shared: Weak<PollerShared>) {
+    loop {
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+        let started = Instant::now();
+
+        let target = shared.target.lock().clone();
+        if let Some(cache) = target
+            && !shared.paused.load(Ordering::Relaxed)
+        {
+            cache.refresh(PAGE_MAX_AGE);
+            shared.last_refresh.lock().replace(Instant::now());
+        }
+
+        let interval = interval_of(shared.rate_hz.load(Ordering::Relaxed));
+        drop(shared);
+        std::thread::sleep(interval.saturating_sub(started.elapsed()));
+    }
+}
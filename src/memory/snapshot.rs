@@ -0,0 +1,550 @@
+// Offline memory snapshot
+//
+// File layout, all integers little endian
+//
+// magic        b"MCSNAP\0\x01"
+// u32          region count
+//   u64        base
+//   u8         protection bits: 1 read, 2 write, 4 execute
+//   u64        byte count
+//   [u8]       bytes
+// u32          module count
+//   u64        base
+//   u64        size
+//   u32        name length
+//   [u8]       utf8 name
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::Path,
+};
+
+use crate::{
+    address_parser::{AddressResolver, Signature},
+    class::class_list::ClassList,
+    field::field_tag::FieldTag,
+};
+
+use super::{
+    MemoryReaderWriter, MemoryRegion, MemoryState, ModuleInfo, PAGE_SIZE, Protection,
+    module_name_at,
+};
+
+pub(super) const MAGIC: &[u8; 8] = b"MCSNAP\0\x01";
+
+#[derive(Debug, Clone)]
+pub struct SnapshotRegion {
+    pub base: usize,
+    pub protection: Protection,
+    pub bytes: Vec<u8>,
+}
+
+impl SnapshotRegion {
+    pub fn end(&self) -> usize {
+        self.base + self.bytes.len()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MemorySnapshot {
+    /// sorted by base, never overlap
+    regions: Vec<SnapshotRegion>,
+    modules: Vec<ModuleInfo>,
+}
+
+impl MemorySnapshot {
+    /// Overlapping part of a later region is dropped, first one by base wins
+    pub fn new(mut regions: Vec<SnapshotRegion>, modules: Vec<ModuleInfo>) -> Self {
+        regions.sort_by_key(|r| r.base);
+        let mut end = 0;
+        for r in regions.iter_mut() {
+            r.bytes.truncate(usize::MAX - r.base);
+            if r.base < end {
+                let overlap = (end - r.base).min(r.bytes.len());
+                r.bytes.drain(..overlap);
+                r.base += overlap;
+            }
+            end = end.max(r.end());
+        }
+        regions.retain(|r| !r.bytes.is_empty());
+        Self { regions, modules }
+    }
+
+    pub fn regions(&self) -> &[SnapshotRegion] {
+        &self.regions
+    }
+
+    fn region(&self, r: &SnapshotRegion) -> MemoryRegion {
+        MemoryRegion {
+            start: r.base,
+            size: r.bytes.len(),
+            protection: r.protection,
+            name: module_name_at(&self.modules, r.base),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> eyre::Result<()> {
+        std::fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = MAGIC.to_vec();
+
+        out.extend((self.regions.len() as u32).to_le_bytes());
+        for r in &self.regions {
+            out.extend((r.base as u64).to_le_bytes());
+            out.push(
+                r.protection.read as u8
+                    | (r.protection.write as u8) << 1
+                    | (r.protection.execute as u8) << 2,
+            );
+            out.extend((r.bytes.len() as u64).to_le_bytes());
+            out.extend(&r.bytes);
+        }
+
+        out.extend((self.modules.len() as u32).to_le_bytes());
+        for m in &self.modules {
+            out.extend((m.base as u64).to_le_bytes());
+            out.extend((m.size as u64).to_le_bytes());
+            out.extend((m.name.len() as u32).to_le_bytes());
+            out.extend(m.name.as_bytes());
+        }
+        out
+    }
+
+    pub fn from_bytes(data: &[u8]) -> eyre::Result<Self> {
+        let mut r = ByteReader { data, pos: 0 };
+        if r.bytes(MAGIC.len())? != MAGIC {
+            eyre::bail!("{}", obfstr!("Not a MakeClass snapshot file"))
+        }
+
+        let region_count = r.u32()?;
+        let mut regions = vec![];
+        for _ in 0..region_count {
+            let base = r.u64()? as usize;
+            let bits = r.u8()?;
+            let len = r.u64()? as usize;
+            if base.checked_add(len).is_none() {
+                eyre::bail!(
+                    "{}{base:#X}",
+                    obfstr!("Snapshot region out of address space at ")
+                );
+            }
+            regions.push(SnapshotRegion {
+                base,
+                protection: Protection {
+                    read: bits & 1 != 0,
+                    write: bits & 2 != 0,
+                    execute: bits & 4 != 0,
+                },
+                bytes: r.bytes(len)?.to_vec(),
+            });
+        }
+
+        // saved snapshot never overlap, file is broken
+        regions.sort_by_key(|r| r.base);
+        if let Some(w) = regions.windows(2).find(|w| w[1].base < w[0].end()) {
+            eyre::bail!(
+                "{}{:#X}",
+                obfstr!("Snapshot regions overlap at "),
+                w[1].base
+            );
+        }
+
+        let module_count = r.u32()?;
+        let mut modules = vec![];
+        for _ in 0..module_count {
+            let base = r.u64()? as usize;
+            let size = r.u64()? as usize;
+            let len = r.u32()? as usize;
+            let name = String::from_utf8(r.bytes(len)?.to_vec())?;
+            modules.push(ModuleInfo { name, base, size });
+        }
+
+        Ok(Self::new(regions, modules))
+    }
+
+    /// Capture pages reachable from every class
+    ///
+    /// Class pointers and text pointers are followed up to `depth` levels,
+    /// embedded class instances dont count as a level.
+    pub fn capture(memory: &dyn MemoryState, class_list: &ClassList, depth: usize) -> Self {
+        let mut pages = BTreeSet::new();
+        let mut visited = HashSet::new();
+        let mut queue = class_list
+            .classes()
+            .iter()
+            .map(|c| (c.id(), c.address.borrow().address_value(), depth))
+            .collect::<Vec<_>>();
+
+        while let Some((class_id, address, depth)) = queue.pop() {
+            if address == 0 || !visited.insert((class_id, address)) {
+                continue;
+            }
+            let Some(class) = class_list.get_class(class_id) else {
+                continue;
+            };
+
+            add_readable_pages(&mut pages, memory, address, class.class_size());
+
+            let mut offset = 0;
+            for field in class.fields.iter() {
+                // garbage pointer near the top, rest of fields too
+                let Some(field_address) = address.checked_add(offset) else {
+                    break;
+                };
+                offset += field.field_size();
+
+                match field.field_tag() {
+                    FieldTag::ClassInstance => {
+                        if let Some(id) = field.class_id() {
+                            queue.push((id, field_address, depth));
+                        }
+                    }
+                    FieldTag::ClassPointer if depth > 0 => {
+                        if let Some(id) = field.class_id()
+                            && let Some(ptr) = memory.dereference(field_address)
+                        {
+                            queue.push((id, ptr as usize, depth - 1));
+                        }
+                    }
+                    FieldTag::PtrUtf8 | FieldTag::PtrUtf16 if depth > 0 => {
+                        if let Some(ptr) = memory.dereference(field_address) {
+                            add_readable_pages(&mut pages, memory, ptr as usize, 1);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        // merge continuous pages with same protection
+        let mut regions: Vec<SnapshotRegion> = vec![];
+        for page in pages {
+            let protection = memory
+                .region_at(page)
+                .map(|r| r.protection)
+                .unwrap_or(Protection::READ);
+            match regions.last_mut() {
+                Some(r)
+                    if r.base.checked_add(r.bytes.len()) == Some(page)
+                        && r.protection == protection =>
+                {
+                    r.bytes.resize(r.bytes.len() + PAGE_SIZE, 0)
+                }
+                _ => regions.push(SnapshotRegion {
+                    base: page,
+                    protection,
+                    bytes: vec![0; PAGE_SIZE],
+                }),
+            }
+        }
+
+        for r in regions.iter_mut() {
+            memory.read_buf(r.base, &mut r.bytes);
+        }
+
+        Self::new(regions, memory.modules())
+    }
+
+    fn snapshot_region_at(&self, addr: usize) -> Option<&SnapshotRegion> {
+        let idx = self.regions.partition_point(|r| r.end() <= addr);
+        self.regions.get(idx).filter(|r| r.base <= addr)
+    }
+
+    /// Copy covered bytes, zero the rest
+    ///
+    /// return number of bytes covered by regions
+    fn read_raw(&self, addr: usize, buffer: &mut [u8]) -> usize {
+        let mut pos = 0;
+        let mut covered = 0;
+        while pos < buffer.len() {
+            let curr = addr.saturating_add(pos);
+            let idx = self.regions.partition_point(|r| r.end() <= curr);
+            match self.regions.get(idx) {
+                Some(r) if r.base <= curr => {
+                    let offset = curr - r.base;
+                    let n = (r.bytes.len() - offset).min(buffer.len() - pos);
+                    buffer[pos..pos + n].copy_from_slice(&r.bytes[offset..offset + n]);
+                    // only count up to the first hole
+                    if covered == pos {
+                        covered += n;
+                    }
+                    pos += n;
+                }
+                Some(r) => {
+                    let n = (r.base - curr).min(buffer.len() - pos);
+                    buffer[pos..pos + n].fill(0);
+                    pos += n;
+                }
+                None => {
+                    buffer[pos..].fill(0);
+                    break;
+                }
+            }
+        }
+        covered
+    }
+}
+
+fn add_readable_pages(
+    pages: &mut BTreeSet<usize>,
+    memory: &dyn MemoryState,
+    address: usize,
+    size: usize,
+) {
+    let start = address & !(PAGE_SIZE - 1);
+    let end = address.saturating_add(size.max(1));
+    for page in (start..end).step_by(PAGE_SIZE) {
+        if memory.can_read(page) || memory.can_read(page.max(address)) {
+            pages.insert(page);
+        }
+    }
+}
+
+struct ByteReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn bytes(&mut self, n: usize) -> eyre::Result<&'a [u8]> {
+        let Some(b) = self.data.get(self.pos..self.pos.saturating_add(n)) else {
+            eyre::bail!("{}{}", obfstr!("Snapshot file truncated at "), self.pos)
+        };
+        self.pos += n;
+        Ok(b)
+    }
+
+    fn u8(&mut self) -> eyre::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> eyre::Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> eyre::Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into()?))
+    }
+}
+
+impl MemoryReaderWriter for MemorySnapshot {
+    fn read_buf(&self, addr: usize, buffer: &mut [u8]) -> usize {
+        self.read_raw(addr, buffer)
+    }
+
+    fn write_buf(&self, addr: usize, _buffer: &[u8]) -> eyre::Result<()> {
+        eyre::bail!(
+            "{}{addr:#X}",
+            obfstr!("Snapshot is read only, cant write at ")
+        )
+    }
+
+    fn can_read(&self, addr: usize) -> bool {
+        self.snapshot_region_at(addr)
+            .is_some_and(|r| r.protection.read)
+    }
+
+    fn modules(&self) -> Vec<ModuleInfo> {
+        self.modules.clone()
+    }
+
+    fn regions(&self) -> Vec<MemoryRegion> {
+        self.regions.iter().map(|r| self.region(r)).collect()
+    }
+
+    /// Hex view ask for every pointer each frame, dont build the whole list
+    fn region_at(&self, addr: usize) -> Option<MemoryRegion> {
+        let idx = self.regions.partition_point(|r| r.end() <= addr);
+        let r = self.regions.get(idx).filter(|r| r.base <= addr)?;
+        Some(self.region(r))
+    }
+}
+
+impl AddressResolver for MemorySnapshot {
+    fn module_symbol_to_address(&self, module_name: &str) -> Option<isize> {
+        self.modules
+            .iter()
+            .find(|m| m.name == module_name)
+            .map(|m| m.base as isize)
+    }
+
+    fn dereference(&self, address: usize) -> Option<isize> {
+        let mut buf = [0u8; size_of::<usize>()];
+        (self.read_raw(address, &mut buf) == buf.len()).then(|| isize::from_ne_bytes(buf))
+    }
+
+    fn find_signature(&self, module_name: &str, signature: &Signature) -> Option<isize> {
+        super::find_signature(self, module_name, signature).map(|a| a as isize)
+    }
+
+    fn read_exact(&self, address: usize, buffer: &mut [u8]) -> bool {
+        self.read_buf(address, buffer) == buffer.len()
+    }
+}
+
+impl MemoryState for MemorySnapshot {}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        address::AddressString,
+        address_parser::AddressParser,
+        field::{Field, class_pointer::ClassPointerField},
+    };
+
+    use super::*;
+
+    /// module at 0x400000, heap object at 0x10000 pointing to 0x20000
+    fn fixture() -> MemorySnapshot {
+        let mut module = vec![0u8; PAGE_SIZE];
+        module[0x10..0x18].copy_from_slice(&0x10000usize.to_ne_bytes());
+
+        let mut object = vec![0u8; PAGE_SIZE];
+        object[0x8..0x10].copy_from_slice(&0x20000usize.to_ne_bytes());
+
+        let mut child = vec![0u8; PAGE_SIZE];
+        child[..4].copy_from_slice(b"CHLD");
+
+        MemorySnapshot::new(
+            vec![
+                SnapshotRegion {
+                    base: 0x20000,
+                    protection: Protection::READ,
+                    bytes: child,
+                },
+                SnapshotRegion {
+                    base: 0x400000,
+                    protection: Protection {
+                        read: true,
+                        write: false,
+                        execute: true,
+                    },
+                    bytes: module,
+                },
+                SnapshotRegion {
+                    base: 0x10000,
+                    protection: Protection::READ,
+                    bytes: object,
+                },
+            ],
+            vec![ModuleInfo {
+                name: "sample.bin".into(),
+                base: 0x400000,
+                size: PAGE_SIZE,
+            }],
+        )
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let snapshot = MemorySnapshot::from_bytes(&fixture().to_bytes()).unwrap();
+        assert_eq!(snapshot.regions().len(), 3);
+        assert_eq!(snapshot.regions()[0].base, 0x10000);
+        assert!(snapshot.regions()[2].protection.execute);
+        assert_eq!(snapshot.modules(), fixture().modules());
+
+        assert!(MemorySnapshot::from_bytes(b"garbage").is_err());
+        let bytes = fixture().to_bytes();
+        assert!(MemorySnapshot::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+
+        let region = |base, bytes| SnapshotRegion {
+            base,
+            protection: Protection::READ,
+            bytes,
+        };
+        let overlapping = vec![
+            region(0x10800, vec![2; PAGE_SIZE]),
+            region(0x10000, vec![1; PAGE_SIZE]),
+        ];
+        let snapshot = MemorySnapshot::new(overlapping.clone(), vec![]);
+        assert_eq!(snapshot.regions()[1].base, 0x11000);
+        assert_eq!(snapshot.regions()[1].bytes.len(), 0x800);
+        let mut buf = [0u8; 2];
+        snapshot.read_buf(0x10FFF, &mut buf);
+        assert_eq!(buf, [1, 2]);
+
+        // written by hand, new would have fixed it
+        let broken = MemorySnapshot {
+            regions: overlapping,
+            modules: vec![],
+        };
+        assert!(MemorySnapshot::from_bytes(&broken.to_bytes()).is_err());
+        let broken = MemorySnapshot {
+            regions: vec![region(usize::MAX - 1, vec![0; 4])],
+            modules: vec![],
+        };
+        assert!(MemorySnapshot::from_bytes(&broken.to_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_snapshot_read() {
+        let snapshot = fixture();
+
+        assert!(snapshot.can_read(0x10000));
+        assert!(snapshot.can_read(0x10FFF));
+        assert!(!snapshot.can_read(0x11000));
+        assert_eq!(snapshot.region_at(0x10FFF).map(|r| r.start), Some(0x10000));
+        assert_eq!(snapshot.region_at(0x11000), None);
+
+        // straddle the end of a region
+        let mut buf = [0xCCu8; 8];
+        // hole first, nothing readable from the start
+        assert_eq!(snapshot.read_buf(0x20000 - 4, &mut buf), 0);
+        assert_eq!(&buf, b"\0\0\0\0CHLD");
+        assert!(snapshot.write_buf(0x20000, &[0]).is_err());
+
+        let eval = AddressParser::new("[[sample.bin + 0x10 ] + 8 ]")
+            .parse()
+            .unwrap();
+        let v = eval.eval(&mut HashMap::new(), &snapshot).unwrap();
+        assert_eq!(v, 0x20000);
+        assert_eq!(snapshot.dereference(0x10FFC), None);
+    }
+
+    #[test]
+    fn test_snapshot_capture() {
+        // object at 0x30000 pointing near the end of address space
+        let mut garbage = vec![0u8; PAGE_SIZE];
+        garbage[..8].copy_from_slice(&(usize::MAX - 7).to_ne_bytes());
+        let mut regions = fixture().regions().to_vec();
+        regions.push(SnapshotRegion {
+            base: 0x30000,
+            protection: Protection::READ,
+            bytes: garbage,
+        });
+        let source = MemorySnapshot::new(regions, fixture().modules());
+
+        let mut list = ClassList::default();
+        let child = list.add_empty_class("Child");
+        let root = list.add_empty_class("Root");
+        let root_class = list.get_class_mut(root).unwrap();
+        root_class.address.replace(AddressString::from(0x10000));
+        root_class.add_field(crate::field::hex::HexField::<64>::new().boxed());
+        root_class.add_field(ClassPointerField::new_with_class_id(child).boxed());
+        let child_class = list.get_class_mut(child).unwrap();
+        child_class.add_field(crate::field::hex::HexField::<64>::new().boxed());
+        child_class.add_field(crate::field::hex::HexField::<64>::new().boxed());
+        let bad = list.add_empty_class("Bad");
+        let bad_class = list.get_class_mut(bad).unwrap();
+        bad_class.address.replace(AddressString::from(0x30000));
+        bad_class.add_field(ClassPointerField::new_with_class_id(child).boxed());
+
+        let shallow = MemorySnapshot::capture(&source, &list, 0);
+        assert_eq!(shallow.regions().len(), 2);
+        assert_eq!(shallow.regions()[0].base, 0x10000);
+
+        let deep = MemorySnapshot::capture(&source, &list, 1);
+        assert_eq!(deep.regions().len(), 3);
+        assert_eq!(deep.dereference(0x20000), source.dereference(0x20000));
+        assert_eq!(deep.module_symbol_to_address("sample.bin"), Some(0x400000));
+    }
+}
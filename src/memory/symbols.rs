@@ -0,0 +1,518 @@
+// Symbol tables of loaded modules, for `module!symbol`
+//
+// Module file is parsed when backend know where it is on disk, only the file
+// has ELF .symtab. Otherwise headers are read back from memory: ELF .dynsym
+// found through the dynamic segment, or PE export directory.
+
+use std::{collections::HashMap, fs::File, path::Path, sync::Arc};
+
+use memmap2::Mmap;
+use object::{BinaryFormat, Object, ObjectSegment, ObjectSymbol, SymbolKind, elf};
+use parking_lot::Mutex;
+
+use super::{MemoryReaderWriter, ModuleInfo, PAGE_SIZE, SymbolInfo, find_module};
+
+/// Memory headers are not trusted, dont read more symbols than this
+const MAX_SYMBOLS: usize = 0x10_0000;
+const MAX_STRINGS: usize = 0x400_0000;
+/// Longest export name read from memory
+const MAX_NAME: usize = 0x400;
+/// Biggest dynamic segment read from memory
+const MAX_DYNAMIC: usize = 0x1_0000;
+
+/// Symbol name to offset from module base
+#[derive(Debug, Default)]
+pub struct SymbolTable {
+    symbols: HashMap<String, usize>,
+}
+
+impl SymbolTable {
+    pub fn get(&self, name: &str) -> Option<usize> {
+        self.symbols.get(name).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.symbols
+            .iter()
+            .map(|(name, offset)| (name.as_str(), *offset))
+    }
+
+    /// Local symbols dont replace global ones of the same name
+    fn insert(&mut self, name: String, offset: usize, global: bool) {
+        if name.is_empty() {
+            return;
+        }
+        if global {
+            self.symbols.insert(name, offset);
+        } else {
+            self.symbols.entry(name).or_insert(offset);
+        }
+    }
+
+    /// File on disk, ELF .dynsym and .symtab or PE exports
+    pub fn from_file(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: opened read only, only used while parsing
+        let data = unsafe { Mmap::map(&file)? };
+        Self::parse_file(&data)
+    }
+
+    fn parse_file(data: &[u8]) -> eyre::Result<Self> {
+        let object = object::File::parse(data)?;
+        let mut table = Self::default();
+
+        if object.format() == BinaryFormat::Pe {
+            let base = object.relative_address_base();
+            for export in object.exports()? {
+                let Some(offset) = export.address().checked_sub(base) else {
+                    continue;
+                };
+                let name = String::from_utf8_lossy(export.name()).into_owned();
+                table.insert(name, offset as usize, true);
+            }
+            return Ok(table);
+        }
+
+        // module base is where lowest segment got mapped
+        let base = object
+            .segments()
+            .map(|s| s.address())
+            .min()
+            .unwrap_or_default()
+            & !(PAGE_SIZE as u64 - 1);
+        for symbol in object.dynamic_symbols().chain(object.symbols()) {
+            if symbol.is_undefined()
+                || symbol.section_index().is_none()
+                || !matches!(symbol.kind(), SymbolKind::Text | SymbolKind::Data)
+            {
+                continue;
+            }
+            let (Ok(name), Some(offset)) = (symbol.name(), symbol.address().checked_sub(base))
+            else {
+                continue;
+            };
+            table.insert(name.to_owned(), offset as usize, symbol.is_global());
+        }
+        Ok(table)
+    }
+
+    /// Module headers read from memory at `base`
+    pub fn from_memory(memory: &dyn MemoryReaderWriter, base: usize) -> eyre::Result<Self> {
+        let magic = read_bytes(memory, base, 4)?;
+        match magic.as_slice() {
+            b"\x7FELF" => elf_from_memory(memory, base),
+            [b'M', b'Z', ..] => pe_from_memory(memory, base),
+            _ => eyre::bail!("{}{base:#X}", obfstr!("Unknown module format at ")),
+        }
+    }
+
+    /// File when backend know it, memory otherwise, empty when both fail
+    fn load(memory: &dyn MemoryReaderWriter, module: &ModuleInfo) -> Self {
+        if let Some(path) = memory.module_file(&module.name) {
+            match Self::from_file(&path) {
+                Ok(table) => return table,
+                Err(e) => warn!("Failed read symbols from {}: {e}", path.display()),
+            }
+        }
+        Self::from_memory(memory, module.base).unwrap_or_else(|e| {
+            warn!("Failed read symbols of {}: {e}", module.name);
+            Self::default()
+        })
+    }
+}
+
+/// Tables of loaded modules, each parsed once on first use
+#[derive(Default)]
+pub struct SymbolCache {
+    /// by name and base, module loaded again somewhere else is parsed again
+    tables: Mutex<HashMap<(String, usize), Arc<SymbolTable>>>,
+}
+
+impl SymbolCache {
+    fn table(
+        &self,
+        memory: &dyn MemoryReaderWriter,
+        module_name: &str,
+    ) -> Option<(ModuleInfo, Arc<SymbolTable>)> {
+        let module = find_module(memory, module_name)?;
+        let key = (module.name.clone(), module.base);
+        if let Some(table) = self.tables.lock().get(&key) {
+            return Some((module, table.clone()));
+        }
+
+        // parse without the lock, big modules take a while
+        let table = Arc::new(SymbolTable::load(memory, &module));
+        self.tables.lock().insert(key, table.clone());
+        Some((module, table))
+    }
+
+    pub fn address(
+        &self,
+        memory: &dyn MemoryReaderWriter,
+        module_name: &str,
+        symbol_name: &str,
+    ) -> Option<usize> {
+        let (module, table) = self.table(memory, module_name)?;
+        table
+            .get(symbol_name)
+            .and_then(|offset| module.base.checked_add(offset))
+    }
+
+    pub fn symbols(&self, memory: &dyn MemoryReaderWriter, module_name: &str) -> Vec<SymbolInfo> {
+        let Some((module, table)) = self.table(memory, module_name) else {
+            return vec![];
+        };
+        table
+            .iter()
+            .filter_map(|(name, offset)| {
+                Some(SymbolInfo {
+                    module: module.name.clone(),
+                    name: name.to_owned(),
+                    address: module.base.checked_add(offset)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Loaded ELF, .dynsym through PT_DYNAMIC
+fn elf_from_memory(memory: &dyn MemoryReaderWriter, base: usize) -> eyre::Result<SymbolTable> {
+    let header = read_bytes(memory, base, 0x40)?;
+    let wide = match header[4] {
+        elf::ELFCLASS32 => false,
+        elf::ELFCLASS64 => true,
+        class => eyre::bail!("{}{class}", obfstr!("Unknown ELF class ")),
+    };
+    eyre::ensure!(
+        header[5] == elf::ELFDATA2LSB,
+        obfstr!("Only little endian ELF supported")
+    );
+    let word = if wide { 8 } else { 4 };
+    let (ph_size, sym_size) = if wide { (56, 24) } else { (32, 16) };
+
+    let (phoff, phentsize, phnum) = match wide {
+        true => (
+            le(&header, 0x20, 8),
+            le(&header, 0x36, 2),
+            le(&header, 0x38, 2),
+        ),
+        false => (
+            le(&header, 0x1C, 4),
+            le(&header, 0x2A, 2),
+            le(&header, 0x2C, 2),
+        ),
+    };
+    // phnum is 16 bit, with fixed entry size this stay small
+    eyre::ensure!(
+        phentsize == ph_size,
+        "{}{phentsize}",
+        obfstr!("Bad ELF program header size ")
+    );
+    let headers = read_bytes(memory, add(base, phoff)?, phentsize * phnum)?;
+
+    let mut load = None;
+    let mut dynamic = None;
+    for ph in headers.chunks_exact(phentsize) {
+        let (vaddr, memsz) = match wide {
+            true => (le(ph, 0x10, 8), le(ph, 0x28, 8)),
+            false => (le(ph, 0x08, 4), le(ph, 0x14, 4)),
+        };
+        match le(ph, 0, 4) as u32 {
+            elf::PT_LOAD => load = Some(load.map_or(vaddr, |l: usize| l.min(vaddr))),
+            elf::PT_DYNAMIC => dynamic = Some((vaddr, memsz)),
+            _ => {}
+        }
+    }
+    let load =
+        load.ok_or_else(|| eyre::eyre!(obfstr!("ELF has no loadable segment")))? & !(PAGE_SIZE - 1);
+    let (dynamic, dynamic_size) =
+        dynamic.ok_or_else(|| eyre::eyre!(obfstr!("ELF has no dynamic segment")))?;
+    let bias = base.wrapping_sub(load);
+    // glibc rewrite these entries to absolute addresses after loading
+    let ptr = |value: usize| match value >= base {
+        true => value,
+        false => value.wrapping_add(bias),
+    };
+
+    let dynamic = read_bytes(
+        memory,
+        dynamic.wrapping_add(bias),
+        dynamic_size.min(MAX_DYNAMIC),
+    )?;
+    let mut tags = HashMap::new();
+    for entry in dynamic.chunks_exact(word * 2) {
+        let tag = le(entry, 0, word);
+        if tag == elf::DT_NULL as usize {
+            break;
+        }
+        tags.entry(tag).or_insert(le(entry, word, word));
+    }
+    let tag = |t: u32| tags.get(&(t as usize)).copied();
+    let (Some(symtab), Some(strtab)) = (tag(elf::DT_SYMTAB), tag(elf::DT_STRTAB)) else {
+        eyre::bail!(obfstr!("ELF dynamic segment has no symbol table"));
+    };
+    let (symtab, strtab) = (ptr(symtab), ptr(strtab));
+    let syment = tag(elf::DT_SYMENT).unwrap_or(sym_size);
+    eyre::ensure!(
+        syment == sym_size,
+        "{}{syment}",
+        obfstr!("Bad ELF symbol entry size ")
+    );
+
+    // table size is only known from the hash table
+    let count = match (tag(elf::DT_GNU_HASH), tag(elf::DT_HASH)) {
+        (Some(hash), _) => gnu_hash_count(memory, ptr(hash), word)?,
+        (None, Some(hash)) => le(&read_bytes(memory, add(ptr(hash), 4)?, 4)?, 0, 4),
+        // .dynstr mostly follow .dynsym
+        (None, None) => strtab.saturating_sub(symtab) / syment,
+    }
+    .min(MAX_SYMBOLS);
+    let strings_size = tag(elf::DT_STRSZ).unwrap_or_default().min(MAX_STRINGS);
+
+    let symbols = read_bytes(memory, symtab, count * syment)?;
+    let strings = read_bytes(memory, strtab, strings_size)?;
+    let mut table = SymbolTable::default();
+    for symbol in symbols.chunks_exact(syment) {
+        let (name, info, shndx, value) = match wide {
+            true => (
+                le(symbol, 0, 4),
+                symbol[4],
+                le(symbol, 6, 2),
+                le(symbol, 8, 8),
+            ),
+            false => (
+                le(symbol, 0, 4),
+                symbol[12],
+                le(symbol, 14, 2),
+                le(symbol, 4, 4),
+            ),
+        };
+        // defined function or object, not section, file or tls
+        if shndx == elf::SHN_UNDEF as usize
+            || !matches!(
+                info & 0xF,
+                elf::STT_OBJECT | elf::STT_FUNC | elf::STT_GNU_IFUNC
+            )
+        {
+            continue;
+        }
+        let Some(offset) = value.checked_sub(load) else {
+            continue;
+        };
+        table.insert(c_str(&strings, name), offset, info >> 4 != elf::STB_LOCAL);
+    }
+    Ok(table)
+}
+
+/// Symbol count from DT_GNU_HASH, one past the end of the longest chain
+fn gnu_hash_count(
+    memory: &dyn MemoryReaderWriter,
+    hash: usize,
+    word: usize,
+) -> eyre::Result<usize> {
+    let header = read_bytes(memory, hash, 16)?;
+    let (buckets, offset, bloom) = (le(&header, 0, 4), le(&header, 4, 4), le(&header, 8, 4));
+    // saturated sizes fail the add
+    let buckets_at = add(hash, bloom.saturating_mul(word).saturating_add(16))?;
+    let last = read_bytes(memory, buckets_at, buckets.min(MAX_SYMBOLS) * 4)?
+        .chunks_exact(4)
+        .map(|b| le(b, 0, 4))
+        .max()
+        .unwrap_or_default();
+    if last < offset {
+        return Ok(offset);
+    }
+
+    // last bit set mark end of chain
+    let chains = add(buckets_at, buckets.saturating_mul(4))?;
+    let mut index = last;
+    while index < MAX_SYMBOLS {
+        let value = le(
+            &read_bytes(memory, add(chains, (index - offset) * 4)?, 4)?,
+            0,
+            4,
+        );
+        index += 1;
+        if value & 1 == 1 {
+            break;
+        }
+    }
+    Ok(index)
+}
+
+/// Loaded PE, export directory at its RVA
+fn pe_from_memory(memory: &dyn MemoryReaderWriter, base: usize) -> eyre::Result<SymbolTable> {
+    let nt = add(base, le(&read_bytes(memory, base, 0x40)?, 0x3C, 4))?;
+    // signature, file header, optional header up to end of data directories
+    let header = read_bytes(memory, nt, 0x108)?;
+    eyre::ensure!(&header[..4] == b"PE\0\0", obfstr!("Bad PE signature"));
+    let directories = match le(&header, 24, 2) as u16 {
+        object::pe::IMAGE_NT_OPTIONAL_HDR32_MAGIC => 24 + 96,
+        object::pe::IMAGE_NT_OPTIONAL_HDR64_MAGIC => 24 + 112,
+        magic => eyre::bail!("{}{magic:#X}", obfstr!("Unknown PE optional header ")),
+    };
+    let (rva, size) = (le(&header, directories, 4), le(&header, directories + 4, 4));
+    let mut table = SymbolTable::default();
+    if rva == 0 {
+        return Ok(table);
+    }
+
+    let directory = read_bytes(memory, add(base, rva)?, 0x28)?;
+    let functions = le(&directory, 0x14, 4).min(MAX_SYMBOLS);
+    let names = le(&directory, 0x18, 4).min(MAX_SYMBOLS);
+    let functions = read_bytes(memory, add(base, le(&directory, 0x1C, 4))?, functions * 4)?;
+    let name_rvas = read_bytes(memory, add(base, le(&directory, 0x20, 4))?, names * 4)?;
+    let ordinals = read_bytes(memory, add(base, le(&directory, 0x24, 4))?, names * 2)?;
+    for i in 0..names {
+        let function = le(&functions, le(&ordinals, i * 2, 2) * 4, 4);
+        // forwarded export point at "other.dll.Name" string inside the directory
+        if function == 0 || (rva..rva + size).contains(&function) {
+            continue;
+        }
+        let mut name = [0u8; MAX_NAME];
+        let n = memory.read_buf(add(base, le(&name_rvas, i * 4, 4))?, &mut name);
+        table.insert(c_str(&name[..n], 0), function, true);
+    }
+    Ok(table)
+}
+
+fn read_bytes(memory: &dyn MemoryReaderWriter, addr: usize, len: usize) -> eyre::Result<Vec<u8>> {
+    let mut buf = vec![0u8; len];
+    let n = memory.read_buf(addr, &mut buf);
+    eyre::ensure!(
+        n == len,
+        "{}{len}{}{addr:#X}",
+        obfstr!("Failed read "),
+        obfstr!(" bytes of module header at ")
+    );
+    Ok(buf)
+}
+
+/// Header values come from target memory, garbage must not overflow
+fn add(addr: usize, offset: usize) -> eyre::Result<usize> {
+    addr.checked_add(offset).ok_or_else(|| {
+        eyre::eyre!(
+            "{}{addr:#X} + {offset:#X}",
+            obfstr!("Module header address overflow: ")
+        )
+    })
+}
+
+/// Little endian integer of `size` bytes, 0 past the end
+fn le(data: &[u8], offset: usize, size: usize) -> usize {
+    data.get(offset..offset + size)
+        .map_or(0, |b| b.iter().rev().fold(0, |v, b| (v << 8) | *b as usize))
+}
+
+fn c_str(data: &[u8], offset: usize) -> String {
+    let bytes = data.get(offset..).unwrap_or_default();
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use crate::{
+        address_parser::{AddressParser, AddressResolver},
+        memory::{cache::MemoryCache, linux::LinuxProcessMemory},
+    };
+
+    use super::*;
+
+    /// Only in .symtab of the test binary, never called
+    #[unsafe(no_mangle)]
+    extern "C" fn make_class_symbol_probe() -> u32 {
+        0x1337
+    }
+
+    #[test]
+    fn test_garbage_elf_header() {
+        use crate::memory::{
+            Protection,
+            snapshot::{MemorySnapshot, SnapshotRegion},
+        };
+
+        let elf = |phoff: u64, phentsize: u16| {
+            let mut header = vec![0u8; PAGE_SIZE];
+            header[..6].copy_from_slice(b"\x7FELF\x02\x01");
+            header[0x20..0x28].copy_from_slice(&phoff.to_le_bytes());
+            header[0x36..0x38].copy_from_slice(&phentsize.to_le_bytes());
+            header[0x38..0x3A].copy_from_slice(&0xFFFFu16.to_le_bytes());
+            let region = SnapshotRegion {
+                base: 0x10000,
+                protection: Protection::READ,
+                bytes: header,
+            };
+            elf_from_memory(&MemorySnapshot::new(vec![region], vec![]), 0x10000)
+        };
+        // error, not overflow or a 4 GB read
+        assert!(elf(0x40, 0xFFFF).is_err());
+        assert!(elf(u64::MAX - 8, 56).is_err());
+
+        // module near the end of address space, e_lfanew past it
+        let base = usize::MAX - 0xFFF;
+        let mut header = vec![0u8; 0x1000];
+        header[..2].copy_from_slice(b"MZ");
+        header[0x3C..0x40].copy_from_slice(&0x2000u32.to_le_bytes());
+        let region = SnapshotRegion {
+            base,
+            protection: Protection::READ,
+            bytes: header,
+        };
+        let memory = MemorySnapshot::new(vec![region], vec![]);
+        assert!(pe_from_memory(&memory, base).is_err());
+    }
+
+    #[test]
+    fn test_own_process_symbols() {
+        let memory = LinuxProcessMemory::attach(std::process::id() as i32).unwrap();
+        let malloc = libc::malloc as *const () as usize;
+        let libc = memory
+            .modules()
+            .into_iter()
+            .find(|m| m.contains(malloc))
+            .unwrap();
+
+        // .dynsym found from loaded headers, dynamic segment already rewritten by loader
+        let table = SymbolTable::from_memory(&memory, libc.base).unwrap();
+        assert_eq!(table.get("malloc").map(|o| libc.base + o), Some(malloc));
+        let table = SymbolTable::from_file(memory.module_file(&libc.name).unwrap()).unwrap();
+        assert_eq!(table.get("malloc").map(|o| libc.base + o), Some(malloc));
+
+        let probe = make_class_symbol_probe as *const () as usize;
+        let exe = memory
+            .modules()
+            .into_iter()
+            .find(|m| m.contains(probe))
+            .unwrap();
+        let table = SymbolTable::from_file(memory.module_file(&exe.name).unwrap()).unwrap();
+        assert_eq!(
+            table.get("make_class_symbol_probe").map(|o| exe.base + o),
+            Some(probe)
+        );
+
+        let cache = MemoryCache::new(Box::new(memory));
+        let src = format!("{}!malloc + 1", libc.name);
+        let value = AddressParser::new(&src)
+            .parse()
+            .unwrap()
+            .eval(&mut HashMap::new(), &cache)
+            .unwrap();
+        assert_eq!(value, malloc as isize + 1);
+        assert_eq!(cache.export_to_address(&libc.name, "no_such_symbol"), None);
+        assert!(
+            cache
+                .module_symbols(&libc.name)
+                .iter()
+                .any(|s| s.name == "free")
+        );
+    }
+}
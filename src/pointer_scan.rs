@@ -0,0 +1,358 @@
+// Pointer path scanner
+//
+// Find chains from a module static address to a target, walking backward:
+// every pointer with value in `[target - max_offset, target]` is a candidate
+// parent, its location become the next target. Chain stop when the location
+// is inside a module, a module name the parser cant read end it without a path.
+
+use std::{
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rayon::prelude::*;
+
+use crate::{
+    address_parser::is_lexable_word,
+    memory::{MemoryRegion, MemoryState, ModuleInfo, PAGE_SIZE},
+};
+
+/// Bytes read at once while building pointer map
+const CHUNK_SIZE: usize = 0x10_0000;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PointerPath {
+    pub module: String,
+    pub module_offset: usize,
+    /// applied after each dereference, outermost first
+    pub offsets: Vec<usize>,
+}
+
+impl PointerPath {
+    /// Follow the chain in current memory
+    pub fn resolve(&self, memory: &dyn MemoryState) -> Option<usize> {
+        let base = memory.module_symbol_to_address(&self.module)? as usize;
+        let mut address = base.checked_add(self.module_offset)?;
+        for offset in &self.offsets {
+            address = (memory.dereference(address)? as usize).checked_add(*offset)?;
+        }
+        Some(address)
+    }
+}
+
+/// In `AddressParser` syntax, paste ready for class address
+impl std::fmt::Display for PointerPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut expr = format!("{} + {:#X}", self.module, self.module_offset);
+        for offset in &self.offsets {
+            expr = match offset {
+                0 => format!("[{expr}]"),
+                _ => format!("[{expr}] + {offset:#X}"),
+            };
+        }
+        write!(f, "{expr}")
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PointerScanOptions {
+    pub target: usize,
+    /// Dereferences in a chain
+    pub max_depth: usize,
+    pub max_offset: usize,
+    pub max_results: usize,
+}
+
+/// Every aligned pointer sized value pointing into readable memory,
+/// as `(value, location)` sorted by value
+fn pointer_map(
+    memory: &dyn MemoryState,
+    regions: &[MemoryRegion],
+    progress: &AtomicUsize,
+) -> Vec<(usize, usize)> {
+    const PTR: usize = size_of::<usize>();
+    let points_to_readable = |value: usize| {
+        let idx = regions.partition_point(|r| r.end() <= value);
+        regions.get(idx).is_some_and(|r| r.start <= value)
+    };
+
+    let mut map = regions
+        .par_iter()
+        .flat_map_iter(|r| {
+            let mut found = vec![];
+            let mut buf = vec![0u8; CHUNK_SIZE];
+            let mut pos = r.start.next_multiple_of(PTR);
+            while pos < r.end() {
+                let len = (r.end() - pos).min(CHUNK_SIZE);
+                let n = memory.read_buf(pos, &mut buf[..len]);
+                for (i, chunk) in buf[..n].chunks_exact(PTR).enumerate() {
+                    let value = usize::from_ne_bytes(chunk.try_into().unwrap());
+                    if value >= PAGE_SIZE && points_to_readable(value) {
+                        found.push((value, pos + i * PTR));
+                    }
+                }
+                progress.fetch_add(len, Ordering::Relaxed);
+                pos += len;
+            }
+            found
+        })
+        .collect::<Vec<_>>();
+    map.par_sort_unstable();
+    map
+}
+
+/// Progress total of `scan_pointer_paths`
+pub fn pointer_scan_size(memory: &dyn MemoryState) -> usize {
+    memory
+        .regions()
+        .iter()
+        .filter(|r| r.protection.read)
+        .map(|r| r.size)
+        .sum()
+}
+
+pub fn scan_pointer_paths(
+    memory: &dyn MemoryState,
+    options: &PointerScanOptions,
+    progress: &AtomicUsize,
+) -> eyre::Result<Vec<PointerPath>> {
+    let regions = memory
+        .regions()
+        .into_iter()
+        .filter(|r| r.protection.read && r.size > 0)
+        .collect::<Vec<_>>();
+    if regions.is_empty() {
+        eyre::bail!(obfstr!("Memory backend has no region layout to scan"));
+    }
+    let modules = memory.modules();
+    if modules.is_empty() {
+        eyre::bail!(obfstr!("No module to start pointer paths from"));
+    }
+
+    let map = pointer_map(memory, &regions, progress);
+    let search = PathSearch {
+        map: &map,
+        modules: &modules,
+        options,
+        found: AtomicUsize::new(0),
+    };
+
+    // first level in parallel, each branch searched depth first
+    let mut paths = search
+        .parents(options.target)
+        .par_iter()
+        .flat_map_iter(|&(value, location)| {
+            let mut out = vec![];
+            let mut offsets = vec![options.target - value];
+            search.walk(location, options.max_depth - 1, &mut offsets, &mut out);
+            out
+        })
+        .collect::<Vec<_>>();
+
+    paths.sort_by(|a, b| {
+        (a.offsets.len(), &a.module, a.module_offset).cmp(&(
+            b.offsets.len(),
+            &b.module,
+            b.module_offset,
+        ))
+    });
+    paths.truncate(options.max_results);
+    Ok(paths)
+}
+
+struct PathSearch<'a> {
+    map: &'a [(usize, usize)],
+    modules: &'a [ModuleInfo],
+    options: &'a PointerScanOptions,
+    found: AtomicUsize,
+}
+
+impl PathSearch<'_> {
+    /// Pointers close enough before `target`
+    fn parents(&self, target: usize) -> &[(usize, usize)] {
+        let from = self
+            .map
+            .partition_point(|(v, _)| *v < target.saturating_sub(self.options.max_offset));
+        let to = self.map.partition_point(|(v, _)| *v <= target);
+        &self.map[from..to]
+    }
+
+    /// `location` hold a pointer, `offsets` from it to the target innermost last
+    fn walk(
+        &self,
+        location: usize,
+        depth_left: usize,
+        offsets: &mut Vec<usize>,
+        out: &mut Vec<PointerPath>,
+    ) {
+        if self.found.load(Ordering::Relaxed) >= self.options.max_results {
+            return;
+        }
+
+        if let Some(module) = self.modules.iter().find(|m| m.contains(location)) {
+            // `libstdc++.so.6 + 0x10` would not parse, path is useless
+            if !is_lexable_word(&module.name) {
+                return;
+            }
+            self.found.fetch_add(1, Ordering::Relaxed);
+            out.push(PointerPath {
+                module: module.name.clone(),
+                module_offset: location - module.base,
+                offsets: offsets.iter().rev().copied().collect(),
+            });
+            return;
+        }
+        if depth_left == 0 {
+            return;
+        }
+
+        for &(value, parent) in self.parents(location) {
+            offsets.push(location - value);
+            self.walk(parent, depth_left - 1, offsets, out);
+            offsets.pop();
+        }
+    }
+}
+
+/// Keep paths still leading to `target`, after restart target usually moved
+pub fn revalidate_paths(
+    memory: &dyn MemoryState,
+    paths: &[PointerPath],
+    target: usize,
+    progress: &AtomicUsize,
+) -> Vec<PointerPath> {
+    paths
+        .par_iter()
+        .filter(|p| {
+            progress.fetch_add(1, Ordering::Relaxed);
+            p.resolve(memory) == Some(target)
+        })
+        .cloned()
+        .collect()
+}
+
+pub fn save_paths(path: &Path, paths: &[PointerPath]) -> eyre::Result<()> {
+    std::fs::write(path, ron::to_string(paths)?)?;
+    Ok(())
+}
+
+pub fn load_paths(path: &Path) -> eyre::Result<Vec<PointerPath>> {
+    Ok(ron::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        address_parser::AddressParser,
+        memory::{
+            Protection,
+            snapshot::{MemorySnapshot, SnapshotRegion},
+        },
+    };
+
+    const GAME: usize = 0x400000;
+    const HEAP_A: usize = 0x10000;
+    const HEAP_B: usize = 0x20000;
+
+    /// `game.exe + 0x100 -> [HEAP_A] + 0x18 -> [heap_b] + 0x40`
+    fn build(heap_b: usize) -> MemorySnapshot {
+        let put = |bytes: &mut Vec<u8>, offset: usize, value: usize| {
+            bytes[offset..offset + 8].copy_from_slice(&value.to_ne_bytes());
+        };
+        let mut game = vec![0u8; 0x1000];
+        put(&mut game, 0x100, HEAP_A);
+        let mut heap_a = vec![0u8; 0x1000];
+        put(&mut heap_a, 0x18, heap_b);
+        // a dead end, nothing static point here
+        let mut heap_c = vec![0u8; 0x1000];
+        put(&mut heap_c, 0x8, heap_b + 0x20);
+
+        let region = |base, bytes| SnapshotRegion {
+            base,
+            protection: Protection::READ,
+            bytes,
+        };
+        MemorySnapshot::new(
+            vec![
+                region(GAME, game),
+                region(HEAP_A, heap_a),
+                region(heap_b, vec![0u8; 0x1000]),
+                region(0x30000, heap_c),
+            ],
+            vec![ModuleInfo {
+                name: "game.exe".into(),
+                base: GAME,
+                size: 0x1000,
+            }],
+        )
+    }
+
+    #[test]
+    fn test_pointer_scan() {
+        let memory = build(HEAP_B);
+        let target = HEAP_B + 0x40;
+        let options = PointerScanOptions {
+            target,
+            max_depth: 3,
+            max_offset: 0x100,
+            max_results: 100,
+        };
+        let progress = AtomicUsize::new(0);
+        let paths = scan_pointer_paths(&memory, &options, &progress).unwrap();
+        assert_eq!(progress.load(Ordering::Relaxed), pointer_scan_size(&memory));
+
+        assert_eq!(paths.len(), 1);
+        let expr = paths[0].to_string();
+        assert_eq!(expr, "[[game.exe + 0x100] + 0x18] + 0x40");
+
+        // output is valid address syntax
+        let eval = AddressParser::new(&expr).parse().unwrap();
+        assert_eq!(
+            eval.eval(&mut HashMap::new(), &memory).unwrap() as usize,
+            target
+        );
+
+        // one level is not enough
+        let shallow = PointerScanOptions {
+            max_depth: 1,
+            ..options
+        };
+        assert!(
+            scan_pointer_paths(&memory, &shallow, &progress)
+                .unwrap()
+                .is_empty()
+        );
+
+        // path would not parse back
+        let unreadable = MemorySnapshot::new(
+            memory.regions().to_vec(),
+            vec![ModuleInfo {
+                name: "libstdc++.so.6".into(),
+                base: GAME,
+                size: 0x1000,
+            }],
+        );
+        assert!(
+            scan_pointer_paths(&unreadable, &options, &progress)
+                .unwrap()
+                .is_empty()
+        );
+
+        // target restarted with heap somewhere else
+        let restarted = build(0x50000);
+        let moved = PointerPath {
+            module_offset: 0x108,
+            ..paths[0].clone()
+        };
+        let kept = revalidate_paths(
+            &restarted,
+            &[paths[0].clone(), moved],
+            0x50000 + 0x40,
+            &progress,
+        );
+        assert_eq!(kept, paths);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::memory::MemoryState;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessEntry {
+    pub pid: i32,
+    /// executable file name, used to find the process again after restart
+    pub name: String,
+    pub cmdline: String,
+}
+
+impl std::fmt::Display for ProcessEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.pid)
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub fn list_processes() -> Vec<ProcessEntry> {
+    let Ok(dir) = std::fs::read_dir("/proc") else {
+        return vec![];
+    };
+
+    let mut processes = dir
+        .filter_map(|e| e.ok()?.file_name().to_str()?.parse::<i32>().ok())
+        .filter_map(process_entry)
+        .collect::<Vec<_>>();
+    processes.sort_by_key(|p| p.pid);
+    processes
+}
+
+#[cfg(target_os = "linux")]
+fn process_entry(pid: i32) -> Option<ProcessEntry> {
+    // exe link not readable for processes of other users
+    // comm is truncated to 15 chars but always there
+    let name = std::fs::read_link(format!("/proc/{pid}/exe"))
+        .ok()
+        .and_then(|p| Some(p.file_name()?.to_str()?.to_owned()))
+        .or_else(|| {
+            std::fs::read_to_string(format!("/proc/{pid}/comm"))
+                .ok()
+                .map(|s| s.trim_end().to_owned())
+        })?;
+
+    let cmdline = std::fs::read(format!("/proc/{pid}/cmdline"))
+        .map(|b| {
+            b.split(|c| *c == 0)
+                .filter(|s| !s.is_empty())
+                .map(String::from_utf8_lossy)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .unwrap_or_default();
+
+    Some(ProcessEntry { pid, name, cmdline })
+}
+
+#[cfg(target_os = "linux")]
+pub fn is_process_alive(pid: i32) -> bool {
+    std::path::Path::new(&format!("/proc/{pid}")).exists()
+}
+
+#[cfg(target_os = "linux")]
+pub fn open_process_memory(pid: i32) -> eyre::Result<Box<dyn MemoryState>> {
+    Ok(Box::new(crate::memory::linux::LinuxProcessMemory::attach(
+        pid,
+    )?))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn list_processes() -> Vec<ProcessEntry> {
+    vec![]
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn is_process_alive(_pid: i32) -> bool {
+    false
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn open_process_memory(_pid: i32) -> eyre::Result<Box<dyn MemoryState>> {
+    eyre::bail!(
+        "{}",
+        obfstr!("Live process backend only available on Linux")
+    )
+}
+
+/// First process with this executable name, in pid order
+pub fn find_process_by_name(name: &str) -> Option<ProcessEntry> {
+    list_processes().into_iter().find(|p| p.name == name)
+}
@@ -1,5 +1,5 @@
 use crate::{
-    class::{Class, class_list::ClassList},
+    class::{ReevalPolicy, class_list::ClassList},
     field::{
         Field, allocate_padding,
         boolean::BoolField,
@@ -13,6 +13,8 @@ use crate::{
         vector::VectorField,
     },
     generator::Generator,
+    global_state::GlobalState,
+    variables::Variable,
 };
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -28,6 +30,8 @@ struct FieldData {
 struct ClassData {
     name: String,
     fields: Vec<FieldData>,
+    #[serde(default)]
+    reeval: ReevalPolicy,
 }
 
 #[derive(Default)]
@@ -41,6 +45,7 @@ impl Generator for ProjectDataGenerator {
         self.classes.push(ClassData {
             name: name.to_owned(),
             fields: vec![],
+            reeval: Default::default(),
         });
     }
 
@@ -74,26 +79,51 @@ impl Generator for ProjectDataGenerator {
 #[non_exhaustive]
 pub struct ProjectData {
     classes: Vec<ClassData>,
+    #[serde(default)]
+    last_process_name: Option<String>,
+    #[serde(default)]
+    poll_rate_hz: Option<u32>,
+    #[serde(default)]
+    variables: Vec<Variable>,
 }
 
 impl ProjectData {
-    pub fn store(classes: &[Class]) -> Self {
+    pub fn store(state: &GlobalState) -> Self {
         let mut datagen = ProjectDataGenerator::default();
         let dynam = &mut datagen as &mut dyn Generator;
 
-        for class in classes {
+        for class in state.class_list.classes() {
             dynam.begin_class(&class.name);
             for f in class.fields.iter() {
                 f.codegen(dynam);
             }
             dynam.end_class();
         }
+        // generator only know layout
+        for (data, class) in datagen.classes.iter_mut().zip(state.class_list.classes()) {
+            data.reeval = class.reeval.get();
+        }
 
         Self {
             classes: datagen.classes,
+            last_process_name: state.last_process_name.clone(),
+            poll_rate_hz: Some(state.poller.rate_hz()),
+            variables: state.variables.variables().to_vec(),
         }
     }
 
+    pub fn last_process_name(&self) -> Option<String> {
+        self.last_process_name.clone()
+    }
+
+    pub fn poll_rate_hz(&self) -> Option<u32> {
+        self.poll_rate_hz
+    }
+
+    pub fn variables(&self) -> Vec<Variable> {
+        self.variables.clone()
+    }
+
     pub fn to_class_list(self) -> ClassList {
         let mut list = ClassList::EMPTY;
 
@@ -103,7 +133,9 @@ impl ProjectData {
         self.classes.into_iter().for_each(|mut dataclass| {
             dataclass.fields.sort_by_key(|f| f.offset);
 
-            let cid = list.get_class_by_name(&dataclass.name).unwrap().id();
+            let class = list.get_class_by_name(&dataclass.name).unwrap();
+            class.reeval.set(dataclass.reeval);
+            let cid = class.id();
 
             for FieldData {
                 name,
@@ -130,8 +162,8 @@ impl ProjectData {
                         c.set_name(name);
                         c.boxed()
                     }
-                    FieldTag::Float32 => FloatField::<32>::new(name).boxed(),
-                    FieldTag::Float64 => FloatField::<64>::new(name).boxed(),
+                    FieldTag::Float32 => FloatField::<4>::new(name).boxed(),
+                    FieldTag::Float64 => FloatField::<8>::new(name).boxed(),
                     FieldTag::I8 => IntField::<8>::signed(name).boxed(),
                     FieldTag::I16 => IntField::<16>::signed(name).boxed(),
                     FieldTag::I32 => IntField::<32>::signed(name).boxed(),
@@ -0,0 +1,734 @@
+// Value scanner, find addresses holding a value then narrow them down
+//
+// First scan walk every readable region, next scan only re-check previous
+// results. Unknown initial value keep a copy of readable memory instead,
+// next scan compare against it.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rayon::prelude::*;
+
+use crate::memory::{MemoryRegion, MemoryState, PAGE_SIZE, snapshot::SnapshotRegion};
+
+/// Bytes read at once from a region
+const CHUNK_SIZE: usize = 0x10_0000;
+/// Results checked by one rayon task on next scan
+const HIT_BATCH: usize = 0x1000;
+/// Stop collecting after this many results
+pub const MAX_HITS: usize = 10_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanValueType {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+    Utf8,
+    Utf16,
+}
+
+impl ScanValueType {
+    pub const ALL: [Self; 12] = [
+        Self::U8,
+        Self::U16,
+        Self::U32,
+        Self::U64,
+        Self::I8,
+        Self::I16,
+        Self::I32,
+        Self::I64,
+        Self::F32,
+        Self::F64,
+        Self::Utf8,
+        Self::Utf16,
+    ];
+
+    pub fn is_text(self) -> bool {
+        matches!(self, Self::Utf8 | Self::Utf16)
+    }
+
+    /// Size of a numeric value, 0 for text
+    pub fn size(self) -> usize {
+        match self {
+            Self::U8 | Self::I8 => 1,
+            Self::U16 | Self::I16 => 2,
+            Self::U32 | Self::I32 | Self::F32 => 4,
+            Self::U64 | Self::I64 | Self::F64 => 8,
+            Self::Utf8 | Self::Utf16 => 0,
+        }
+    }
+
+    fn int_range(self) -> (i128, i128) {
+        match self {
+            Self::U8 => (0, u8::MAX as _),
+            Self::U16 => (0, u16::MAX as _),
+            Self::U32 => (0, u32::MAX as _),
+            Self::U64 => (0, u64::MAX as _),
+            Self::I8 => (i8::MIN as _, i8::MAX as _),
+            Self::I16 => (i16::MIN as _, i16::MAX as _),
+            Self::I32 => (i32::MIN as _, i32::MAX as _),
+            _ => (i64::MIN as _, i64::MAX as _),
+        }
+    }
+
+    fn decode(self, b: &[u8]) -> Number {
+        match self {
+            Self::U8 => Number::Int(b[0] as _),
+            Self::U16 => Number::Int(u16::from_ne_bytes([b[0], b[1]]) as _),
+            Self::U32 => Number::Int(u32::from_ne_bytes(b[..4].try_into().unwrap()) as _),
+            Self::U64 => Number::Int(u64::from_ne_bytes(b[..8].try_into().unwrap()) as _),
+            Self::I8 => Number::Int(b[0] as i8 as _),
+            Self::I16 => Number::Int(i16::from_ne_bytes([b[0], b[1]]) as _),
+            Self::I32 => Number::Int(i32::from_ne_bytes(b[..4].try_into().unwrap()) as _),
+            Self::I64 => Number::Int(i64::from_ne_bytes(b[..8].try_into().unwrap()) as _),
+            Self::F32 => Number::Float(f32::from_ne_bytes(b[..4].try_into().unwrap()) as _),
+            Self::F64 => Number::Float(f64::from_ne_bytes(b[..8].try_into().unwrap())),
+            Self::Utf8 | Self::Utf16 => unreachable!(),
+        }
+    }
+
+    /// Parse user input, floats parsed in their own precision so exact compare work
+    pub fn parse(self, s: &str) -> eyre::Result<Number> {
+        let s = s.trim();
+        match self {
+            Self::F32 => Ok(Number::Float(s.parse::<f32>()? as _)),
+            Self::F64 => Ok(Number::Float(s.parse::<f64>()?)),
+            Self::Utf8 | Self::Utf16 => eyre::bail!(obfstr!("Text has no numeric value")),
+            _ => {
+                let (neg, digits) = match s.strip_prefix('-') {
+                    Some(d) => (true, d),
+                    None => (false, s),
+                };
+                let v = match digits
+                    .strip_prefix("0x")
+                    .or_else(|| digits.strip_prefix("0X"))
+                {
+                    Some(hex) => i128::from_str_radix(hex, 16)?,
+                    None => digits.parse::<i128>()?,
+                };
+                let v = if neg { -v } else { v };
+                let (min, max) = self.int_range();
+                if v < min || v > max {
+                    eyre::bail!("{s}{}{self}", obfstr!(" is out of range for "));
+                }
+                Ok(Number::Int(v))
+            }
+        }
+    }
+
+    /// Bytes searched for a text
+    pub fn pattern(self, s: &str) -> Vec<u8> {
+        match self {
+            Self::Utf16 => s.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            _ => s.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn format(self, b: &[u8]) -> String {
+        match self {
+            Self::Utf8 => String::from_utf8_lossy(b).into_owned(),
+            Self::Utf16 => String::from_utf16_lossy(
+                &b.chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect::<Vec<_>>(),
+            ),
+            _ => match self.decode(b) {
+                Number::Int(v) => v.to_string(),
+                Number::Float(v) => v.to_string(),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for ScanValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+            Self::Utf8 => "utf8",
+            Self::Utf16 => "utf16",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Value of a numeric scan type, ints widened so u64 and i64 fit
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Number {
+    Int(i128),
+    Float(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanKind {
+    Exact,
+    Range,
+    Unknown,
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+}
+
+impl ScanKind {
+    pub const ALL: [Self; 7] = [
+        Self::Exact,
+        Self::Range,
+        Self::Unknown,
+        Self::Changed,
+        Self::Unchanged,
+        Self::Increased,
+        Self::Decreased,
+    ];
+
+    /// Compare against value from previous scan
+    pub fn needs_previous(self) -> bool {
+        matches!(
+            self,
+            Self::Changed | Self::Unchanged | Self::Increased | Self::Decreased
+        )
+    }
+
+    /// How many values user need to type
+    pub fn inputs(self) -> usize {
+        match self {
+            Self::Exact => 1,
+            Self::Range => 2,
+            _ => 0,
+        }
+    }
+}
+
+impl std::fmt::Display for ScanKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Exact => "Exact value",
+            Self::Range => "Value between",
+            Self::Unknown => "Unknown initial value",
+            Self::Changed => "Changed value",
+            Self::Unchanged => "Unchanged value",
+            Self::Increased => "Increased value",
+            Self::Decreased => "Decreased value",
+        };
+        write!(f, "{s}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanCompare {
+    Exact(Number),
+    Range(Number, Number),
+    Text(Vec<u8>),
+    Unknown,
+    Changed,
+    Unchanged,
+    Increased,
+    Decreased,
+}
+
+impl ScanCompare {
+    pub fn new(
+        value_type: ScanValueType,
+        kind: ScanKind,
+        value: &str,
+        value_to: &str,
+    ) -> eyre::Result<Self> {
+        if value_type.is_text() {
+            if kind != ScanKind::Exact {
+                eyre::bail!(obfstr!("Text can only be scanned for exact value"));
+            }
+            if value.is_empty() {
+                eyre::bail!(obfstr!("Text to scan for is empty"));
+            }
+            return Ok(Self::Text(value_type.pattern(value)));
+        }
+
+        Ok(match kind {
+            ScanKind::Exact => Self::Exact(value_type.parse(value)?),
+            ScanKind::Range => {
+                let (min, max) = (value_type.parse(value)?, value_type.parse(value_to)?);
+                if min > max {
+                    eyre::bail!(obfstr!("Range start is bigger than its end"));
+                }
+                Self::Range(min, max)
+            }
+            ScanKind::Unknown => Self::Unknown,
+            ScanKind::Changed => Self::Changed,
+            ScanKind::Unchanged => Self::Unchanged,
+            ScanKind::Increased => Self::Increased,
+            ScanKind::Decreased => Self::Decreased,
+        })
+    }
+
+    fn needs_previous(&self) -> bool {
+        matches!(
+            self,
+            Self::Changed | Self::Unchanged | Self::Increased | Self::Decreased
+        )
+    }
+
+    /// Bytes compared at each address
+    fn width(&self, value_type: ScanValueType) -> usize {
+        match self {
+            Self::Text(pattern) => pattern.len(),
+            _ => value_type.size(),
+        }
+    }
+
+    fn matches(&self, value_type: ScanValueType, current: &[u8], previous: &[u8]) -> bool {
+        match self {
+            Self::Exact(v) => value_type.decode(current) == *v,
+            Self::Range(min, max) => {
+                let v = value_type.decode(current);
+                *min <= v && v <= *max
+            }
+            Self::Text(pattern) => current == &pattern[..],
+            Self::Unknown => true,
+            Self::Changed => current != previous,
+            Self::Unchanged => current == previous,
+            Self::Increased => value_type.decode(current) > value_type.decode(previous),
+            Self::Decreased => value_type.decode(current) < value_type.decode(previous),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ScanOptions {
+    pub value_type: ScanValueType,
+    /// Numeric values only at multiple of their size
+    pub aligned: bool,
+}
+
+impl ScanOptions {
+    fn step(&self) -> usize {
+        if self.aligned && !self.value_type.is_text() {
+            self.value_type.size()
+        } else {
+            1
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScanHit {
+    pub address: usize,
+    /// value at the time of the scan, first `size` bytes used
+    pub previous: [u8; 8],
+}
+
+impl ScanHit {
+    fn new(address: usize, value: &[u8]) -> Self {
+        let mut previous = [0u8; 8];
+        let n = value.len().min(8);
+        previous[..n].copy_from_slice(&value[..n]);
+        Self { address, previous }
+    }
+}
+
+pub enum ScanResults {
+    /// Unknown initial value, readable memory kept for next scan
+    Snapshot(Vec<SnapshotRegion>),
+    Hits {
+        hits: Vec<ScanHit>,
+        /// more than `MAX_HITS` matched
+        truncated: bool,
+    },
+}
+
+impl ScanResults {
+    /// Progress total of a next scan over these results
+    pub fn work(&self) -> usize {
+        match self {
+            Self::Snapshot(regions) => regions.iter().map(|r| r.bytes.len()).sum(),
+            Self::Hits { hits, .. } => hits.len(),
+        }
+    }
+
+    fn from_hits(parts: Vec<Vec<ScanHit>>) -> Self {
+        let mut hits = parts.concat();
+        let truncated = hits.len() > MAX_HITS;
+        hits.truncate(MAX_HITS);
+        Self::Hits { hits, truncated }
+    }
+}
+
+fn readable_regions(memory: &dyn MemoryState) -> eyre::Result<Vec<MemoryRegion>> {
+    let regions = memory.regions();
+    if regions.is_empty() {
+        eyre::bail!(obfstr!("Memory backend has no region layout to scan"));
+    }
+    Ok(regions
+        .into_iter()
+        .filter(|r| r.protection.read && r.size > 0)
+        .collect())
+}
+
+/// Progress total of a first scan
+pub fn scan_size(memory: &dyn MemoryState) -> usize {
+    readable_regions(memory)
+        .map(|regions| regions.iter().map(|r| r.size).sum())
+        .unwrap_or_default()
+}
+
+pub fn first_scan(
+    memory: &dyn MemoryState,
+    options: &ScanOptions,
+    compare: &ScanCompare,
+    progress: &AtomicUsize,
+) -> eyre::Result<ScanResults> {
+    if compare.needs_previous() {
+        eyre::bail!(obfstr!("Compare with previous value need a first scan"));
+    }
+    let regions = readable_regions(memory)?;
+
+    if *compare == ScanCompare::Unknown {
+        let snapshot = regions
+            .par_iter()
+            .map(|r| {
+                let mut bytes = vec![0u8; r.size];
+                let n = memory.read_buf(r.start, &mut bytes);
+                bytes.truncate(n);
+                progress.fetch_add(r.size, Ordering::Relaxed);
+                SnapshotRegion {
+                    base: r.start,
+                    protection: r.protection,
+                    bytes,
+                }
+            })
+            .collect();
+        return Ok(ScanResults::Snapshot(snapshot));
+    }
+
+    // shared by all tasks, cap is for the whole scan
+    let found = AtomicUsize::new(0);
+    let parts = regions
+        .par_iter()
+        .map(|r| {
+            let mut hits = vec![];
+            if found.load(Ordering::Relaxed) > MAX_HITS {
+                return hits;
+            }
+            let width = compare.width(options.value_type);
+            scan_range(
+                memory,
+                r.start,
+                r.end(),
+                width,
+                options,
+                progress,
+                |addr, value| {
+                    if compare.matches(options.value_type, value, &[]) {
+                        hits.push(ScanHit::new(addr, value));
+                        found.fetch_add(1, Ordering::Relaxed);
+                    }
+                    found.load(Ordering::Relaxed) <= MAX_HITS
+                },
+            );
+            hits
+        })
+        .collect();
+    Ok(ScanResults::from_hits(parts))
+}
+
+pub fn next_scan(
+    memory: &dyn MemoryState,
+    options: &ScanOptions,
+    compare: &ScanCompare,
+    previous: &ScanResults,
+    progress: &AtomicUsize,
+) -> eyre::Result<ScanResults> {
+    if *compare == ScanCompare::Unknown {
+        eyre::bail!(obfstr!("Unknown initial value is only for first scan"));
+    }
+    let width = compare.width(options.value_type);
+
+    let found = AtomicUsize::new(0);
+    let parts = match previous {
+        ScanResults::Snapshot(regions) => regions
+            .par_iter()
+            .map(|r| {
+                let mut hits = vec![];
+                if found.load(Ordering::Relaxed) > MAX_HITS {
+                    return hits;
+                }
+                scan_range(
+                    memory,
+                    r.base,
+                    r.end(),
+                    width,
+                    options,
+                    progress,
+                    |addr, value| {
+                        let offset = addr - r.base;
+                        // value read now can go past the end of what was readable before
+                        if let Some(prev) = r.bytes.get(offset..offset + width)
+                            && compare.matches(options.value_type, value, prev)
+                        {
+                            hits.push(ScanHit::new(addr, value));
+                            found.fetch_add(1, Ordering::Relaxed);
+                        }
+                        found.load(Ordering::Relaxed) <= MAX_HITS
+                    },
+                );
+                hits
+            })
+            .collect(),
+        ScanResults::Hits { hits, .. } => hits
+            .par_chunks(HIT_BATCH)
+            .map(|batch| {
+                let out = rescan_hits(memory, batch, options.value_type, compare, width);
+                progress.fetch_add(batch.len(), Ordering::Relaxed);
+                out
+            })
+            .collect(),
+    };
+    Ok(ScanResults::from_hits(parts))
+}
+
+/// Walk values of `[start, end)`, `visit` return false to stop
+fn scan_range(
+    memory: &dyn MemoryState,
+    start: usize,
+    end: usize,
+    width: usize,
+    options: &ScanOptions,
+    progress: &AtomicUsize,
+    mut visit: impl FnMut(usize, &[u8]) -> bool,
+) {
+    if width == 0 {
+        progress.fetch_add(end - start, Ordering::Relaxed);
+        return;
+    }
+    let step = options.step();
+    // chunks stay aligned, CHUNK_SIZE is multiple of any step
+    let first = start.next_multiple_of(step);
+    progress.fetch_add(first.min(end) - start, Ordering::Relaxed);
+
+    // read a bit more than a chunk, value at its end cross into next one
+    let mut buf = vec![0u8; CHUNK_SIZE + width - 1];
+    let mut pos = first;
+    while pos < end {
+        let len = (end - pos).min(buf.len());
+        let n = memory.read_buf(pos, &mut buf[..len]);
+
+        let mut offset = 0;
+        while offset < CHUNK_SIZE && offset + width <= n {
+            if !visit(pos + offset, &buf[offset..offset + width]) {
+                return;
+            }
+            offset += step;
+        }
+
+        let advance = CHUNK_SIZE.min(end - pos);
+        progress.fetch_add(advance, Ordering::Relaxed);
+        pos += advance;
+    }
+}
+
+/// Re-read values of previous hits, nearby hits share one read
+fn rescan_hits(
+    memory: &dyn MemoryState,
+    hits: &[ScanHit],
+    value_type: ScanValueType,
+    compare: &ScanCompare,
+    width: usize,
+) -> Vec<ScanHit> {
+    let mut out = vec![];
+    let mut window = vec![0u8; PAGE_SIZE.max(width)];
+    // start and readable length of what is in `window`
+    let mut loaded: Option<(usize, usize)> = None;
+
+    for hit in hits {
+        let inside = loaded
+            .is_some_and(|(start, n)| hit.address >= start && hit.address - start + width <= n);
+        if !inside {
+            let n = memory.read_buf(hit.address, &mut window);
+            loaded = Some((hit.address, n));
+        }
+        let (start, n) = loaded.unwrap();
+        let offset = hit.address - start;
+        if offset + width > n {
+            // not readable anymore
+            continue;
+        }
+
+        let value = &window[offset..offset + width];
+        if compare.matches(value_type, value, &hit.previous[..width.min(8)]) {
+            out.push(ScanHit::new(hit.address, value));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use parking_lot::Mutex;
+
+    use super::*;
+    use crate::{
+        address_parser::AddressResolver,
+        memory::{MemoryReaderWriter, Protection},
+    };
+
+    const BASE: usize = 0x10000;
+
+    struct TestMemory {
+        bytes: Mutex<Vec<u8>>,
+    }
+
+    impl TestMemory {
+        fn put(&self, addr: usize, value: &[u8]) {
+            let offset = addr - BASE;
+            self.bytes.lock()[offset..offset + value.len()].copy_from_slice(value);
+        }
+    }
+
+    impl MemoryReaderWriter for TestMemory {
+        fn read_buf(&self, addr: usize, buffer: &mut [u8]) -> usize {
+            let bytes = self.bytes.lock();
+            let offset = addr - BASE;
+            let n = bytes.len().saturating_sub(offset).min(buffer.len());
+            buffer[..n].copy_from_slice(&bytes[offset..offset + n]);
+            buffer[n..].fill(0);
+            n
+        }
+
+        fn write_buf(&self, addr: usize, buffer: &[u8]) -> eyre::Result<()> {
+            self.put(addr, buffer);
+            Ok(())
+        }
+
+        fn can_read(&self, addr: usize) -> bool {
+            addr >= BASE && addr - BASE < self.bytes.lock().len()
+        }
+
+        fn regions(&self) -> Vec<MemoryRegion> {
+            vec![MemoryRegion {
+                start: BASE,
+                size: self.bytes.lock().len(),
+                protection: Protection::READ,
+                name: None,
+            }]
+        }
+    }
+
+    impl AddressResolver for TestMemory {
+        fn module_symbol_to_address(&self, _module_name: &str) -> Option<isize> {
+            None
+        }
+
+        fn dereference(&self, _address: usize) -> Option<isize> {
+            None
+        }
+    }
+
+    impl MemoryState for TestMemory {}
+
+    fn addresses(results: &ScanResults) -> Vec<usize> {
+        match results {
+            ScanResults::Hits { hits, .. } => hits.iter().map(|h| h.address).collect(),
+            ScanResults::Snapshot(_) => panic!("expected hits"),
+        }
+    }
+
+    #[test]
+    fn test_scanner() {
+        let memory = TestMemory {
+            bytes: Mutex::new(vec![0u8; 0x2000]),
+        };
+        memory.put(BASE + 0x10, &1234u32.to_ne_bytes());
+        memory.put(BASE + 0x100, &1234u32.to_ne_bytes());
+        memory.put(BASE + 0x201, &1234u32.to_ne_bytes());
+        // last value end exactly at the end of memory
+        memory.put(BASE + 0x1FFC, &1234u32.to_ne_bytes());
+
+        let progress = AtomicUsize::new(0);
+        let mut options = ScanOptions {
+            value_type: ScanValueType::U32,
+            aligned: true,
+        };
+        let exact = ScanCompare::new(options.value_type, ScanKind::Exact, "1234", "").unwrap();
+        let results = first_scan(&memory, &options, &exact, &progress).unwrap();
+        assert_eq!(
+            addresses(&results),
+            [BASE + 0x10, BASE + 0x100, BASE + 0x1FFC]
+        );
+        assert_eq!(progress.load(Ordering::Relaxed), 0x2000);
+
+        options.aligned = false;
+        let results = first_scan(&memory, &options, &exact, &progress).unwrap();
+        assert_eq!(addresses(&results).len(), 4);
+
+        memory.put(BASE + 0x100, &1300u32.to_ne_bytes());
+        memory.put(BASE + 0x201, &1000u32.to_ne_bytes());
+        let increased = next_scan(
+            &memory,
+            &options,
+            &ScanCompare::Increased,
+            &results,
+            &progress,
+        )
+        .unwrap();
+        assert_eq!(addresses(&increased), [BASE + 0x100]);
+        let unchanged = next_scan(
+            &memory,
+            &options,
+            &ScanCompare::Unchanged,
+            &results,
+            &progress,
+        )
+        .unwrap();
+        assert_eq!(addresses(&unchanged), [BASE + 0x10, BASE + 0x1FFC]);
+
+        // unknown initial value, then narrow down
+        options = ScanOptions {
+            value_type: ScanValueType::I16,
+            aligned: true,
+        };
+        assert!(first_scan(&memory, &options, &ScanCompare::Changed, &progress).is_err());
+        let snapshot = first_scan(&memory, &options, &ScanCompare::Unknown, &progress).unwrap();
+        memory.put(BASE + 0x20, &(-5i16).to_ne_bytes());
+        let changed = next_scan(
+            &memory,
+            &options,
+            &ScanCompare::Changed,
+            &snapshot,
+            &progress,
+        )
+        .unwrap();
+        assert_eq!(addresses(&changed), [BASE + 0x20]);
+        let range = ScanCompare::new(options.value_type, ScanKind::Range, "-10", "-1").unwrap();
+        let in_range = next_scan(&memory, &options, &range, &changed, &progress).unwrap();
+        assert_eq!(addresses(&in_range), [BASE + 0x20]);
+
+        // floats compare in their own precision
+        memory.put(BASE + 0x400, &0.1f32.to_ne_bytes());
+        options.value_type = ScanValueType::F32;
+        let exact = ScanCompare::new(options.value_type, ScanKind::Exact, "0.1", "").unwrap();
+        let results = first_scan(&memory, &options, &exact, &progress).unwrap();
+        assert_eq!(addresses(&results), [BASE + 0x400]);
+
+        memory.put(BASE + 0x801, &ScanValueType::Utf16.pattern("hp"));
+        options.value_type = ScanValueType::Utf16;
+        let text = ScanCompare::new(options.value_type, ScanKind::Exact, "hp", "").unwrap();
+        let results = first_scan(&memory, &options, &text, &progress).unwrap();
+        assert_eq!(addresses(&results), [BASE + 0x801]);
+
+        assert!(ScanValueType::U8.parse("256").is_err());
+        assert_eq!(ScanValueType::I8.parse("-0x80").unwrap(), Number::Int(-128));
+    }
+}
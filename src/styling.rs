@@ -1,5 +1,7 @@
 use eframe::egui::{Color32, FontId, Stroke, TextFormat};
 
+use crate::memory::RegionKind;
+
 static mut FONT_SIZE_HEX_VIEW: f32 = 14.;
 
 pub fn get_current_font_size_hex_view() -> FontId {
@@ -25,3 +27,17 @@ pub fn create_text_offset_format(offset: usize) -> String {
         format!("{offset:>4}")
     }
 }
+
+/// Bytes and values that could not be read
+pub const UNREADABLE_COLOR: Color32 = Color32::DARK_GRAY;
+
+/// Pointer target colors in hex view
+pub fn region_kind_color(kind: RegionKind) -> Color32 {
+    match kind {
+        RegionKind::Code => Color32::from_rgb(255, 140, 90),
+        RegionKind::ModuleData => Color32::GOLD,
+        RegionKind::Heap => Color32::YELLOW,
+        RegionKind::Stack => Color32::LIGHT_GREEN,
+        RegionKind::Other => Color32::KHAKI,
+    }
+}
@@ -0,0 +1,276 @@
+use std::ops::Range;
+
+use eframe::egui::{
+    Area, Color32, Frame, Key, Modifiers, Order, RichText, ScrollArea, Ui,
+    text::{CCursor, CCursorRange},
+    text_edit::TextEditOutput,
+};
+
+use crate::{
+    address_parser::{WordAt, is_lexable_word, word_at},
+    global_state::global_state,
+};
+
+/// Most candidates in the popup
+const MAX_CANDIDATES: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandidateKind {
+    Module,
+    Symbol,
+    Variable,
+    Class,
+}
+
+impl CandidateKind {
+    fn tag(self) -> &'static str {
+        match self {
+            Self::Module => "mod",
+            Self::Symbol => "sym",
+            Self::Variable => "var",
+            Self::Class => "class",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub kind: CandidateKind,
+    pub label: String,
+    /// text put in place of the word
+    pub insert: String,
+    pub value: Option<usize>,
+}
+
+/// Completion popup under an address `TextEdit`
+#[derive(Default)]
+pub struct AddressCompletion {
+    candidates: Vec<Candidate>,
+    /// byte range of word under cursor
+    replace: Range<usize>,
+    selected: usize,
+    /// text and cursor candidates are for
+    last: Option<(String, usize)>,
+    /// closed with escape, until text change
+    dismissed: bool,
+    /// selection moved by arrow keys
+    scroll_to_selected: bool,
+}
+
+impl AddressCompletion {
+    /// Before the `TextEdit`, take keys the popup use, true when Tab accept
+    pub fn take_keys(&mut self, ui: &Ui) -> bool {
+        if self.candidates.is_empty() || self.dismissed {
+            return false;
+        }
+        let len = self.candidates.len();
+        ui.input_mut(|i| {
+            if i.consume_key(Modifiers::NONE, Key::ArrowDown) {
+                self.selected = (self.selected + 1) % len;
+                self.scroll_to_selected = true;
+            }
+            if i.consume_key(Modifiers::NONE, Key::ArrowUp) {
+                self.selected = (self.selected + len - 1) % len;
+                self.scroll_to_selected = true;
+            }
+            if i.consume_key(Modifiers::NONE, Key::Escape) {
+                self.dismissed = true;
+            }
+            i.consume_key(Modifiers::NONE, Key::Tab)
+        })
+    }
+
+    /// After the `TextEdit`, refresh candidates and draw popup
+    ///
+    /// return true when a candidate was put in `text`
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        output: &mut TextEditOutput,
+        text: &mut String,
+        accept: bool,
+    ) -> bool {
+        if !output.response.has_focus() {
+            self.candidates.clear();
+            self.last = None;
+            return false;
+        }
+
+        let mut accepted = None;
+        if accept {
+            accepted = Some(self.selected);
+        }
+
+        if accepted.is_none() {
+            let cursor = output
+                .cursor_range
+                .map(|c| byte_index(text, c.primary.ccursor.index))
+                .unwrap_or(text.len());
+            let key = (text.clone(), cursor);
+            if self.last.as_ref() != Some(&key) {
+                if self.last.as_ref().is_none_or(|(t, _)| *t != key.0) {
+                    self.dismissed = false;
+                }
+                self.refresh(text, cursor);
+                self.last = Some(key);
+            }
+            if self.candidates.is_empty() || self.dismissed {
+                return false;
+            }
+            accepted = self.popup(ui, output);
+        }
+
+        let Some(candidate) = accepted.and_then(|i| self.candidates.get(i)) else {
+            return false;
+        };
+        text.replace_range(self.replace.clone(), &candidate.insert);
+        let end = self.replace.start + candidate.insert.len();
+        let cursor = text[..end].chars().count();
+        output
+            .state
+            .cursor
+            .set_char_range(Some(CCursorRange::one(CCursor::new(cursor))));
+        output.state.clone().store(ui.ctx(), output.response.id);
+        self.candidates.clear();
+        // dont pop again for the word just completed
+        self.dismissed = true;
+        self.last = Some((text.clone(), end));
+        true
+    }
+
+    fn refresh(&mut self, text: &str, cursor: usize) {
+        self.candidates.clear();
+        self.selected = 0;
+        let Some(word) = word_at(text, cursor) else {
+            return;
+        };
+        self.replace = word.span.range();
+        self.candidates = rank(collect_candidates(&word), &word.prefix);
+    }
+
+    /// Return clicked candidate
+    fn popup(&mut self, ui: &Ui, output: &TextEditOutput) -> Option<usize> {
+        let mut clicked = None;
+        Area::new(output.response.id.with("_completion"))
+            .order(Order::Foreground)
+            .fixed_pos(output.response.rect.left_bottom())
+            .show(ui.ctx(), |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ScrollArea::vertical().max_height(240.).show(ui, |ui| {
+                        for (i, c) in self.candidates.iter().enumerate() {
+                            let value = c.value.map(|v| format!("{v:#X}")).unwrap_or_default();
+                            let r = ui.selectable_label(
+                                i == self.selected,
+                                RichText::new(format!(
+                                    "{:<5} {:<32} {value}",
+                                    c.kind.tag(),
+                                    c.label
+                                ))
+                                .monospace(),
+                            );
+                            if i == self.selected && self.scroll_to_selected {
+                                r.scroll_to_me(None);
+                            }
+                            if r.clicked() {
+                                clicked = Some(i);
+                            }
+                        }
+                    });
+                    self.scroll_to_selected = false;
+                    ui.label(
+                        RichText::new(obfstr!("Tab accept, Esc close"))
+                            .small()
+                            .color(Color32::GRAY),
+                    );
+                });
+            });
+        clicked
+    }
+}
+
+/// Everything the project and backend know a name for
+fn collect_candidates(word: &WordAt) -> Vec<Candidate> {
+    let state = global_state();
+    let modules = state
+        .memory
+        .modules()
+        .into_iter()
+        .map(|m| Candidate {
+            kind: CandidateKind::Module,
+            insert: m.name.clone(),
+            label: m.name,
+            value: Some(m.base),
+        })
+        .collect::<Vec<_>>();
+    // sig("module", ..) only take module names, any name fit in the string
+    if word.in_string {
+        return modules;
+    }
+    // outside string inserted text must parse back
+    let mut out = modules
+        .into_iter()
+        .filter(|m| is_lexable_word(&m.insert))
+        .collect::<Vec<_>>();
+
+    // after `module!` whole symbol table of that module
+    let symbols = match word.prefix.split_once('!') {
+        Some((module, _)) => state.memory.module_symbols(module),
+        None => state.memory.symbols(),
+    };
+    out.extend(
+        symbols
+            .into_iter()
+            .filter(|s| is_lexable_word(&s.module) && is_lexable_word(&s.name))
+            .map(|s| {
+                let name = format!("{}!{}", s.module, s.name);
+                Candidate {
+                    kind: CandidateKind::Symbol,
+                    insert: name.clone(),
+                    label: name,
+                    value: Some(s.address),
+                }
+            }),
+    );
+    out.extend(state.variables.variables().iter().map(|v| Candidate {
+        kind: CandidateKind::Variable,
+        label: v.name.clone(),
+        insert: v.name.clone(),
+        value: v.value.map(|v| v as usize),
+    }));
+    out.extend(state.class_list.classes().iter().map(|c| Candidate {
+        kind: CandidateKind::Class,
+        label: c.name.clone(),
+        insert: c.name.clone(),
+        value: Some(c.address.borrow().address_value()),
+    }));
+    out
+}
+
+/// Prefix matches first then substring, both ignore case
+fn rank(candidates: Vec<Candidate>, prefix: &str) -> Vec<Candidate> {
+    let prefix = prefix.to_lowercase();
+    let mut ranked = candidates
+        .into_iter()
+        .filter_map(|c| {
+            let label = c.label.to_lowercase();
+            let score = match label.find(&prefix)? {
+                0 if label == prefix => return None,
+                0 => 0,
+                _ => 1,
+            };
+            Some((score, c))
+        })
+        .collect::<Vec<_>>();
+    ranked.sort_by(|(a, ca), (b, cb)| a.cmp(b).then_with(|| ca.label.cmp(&cb.label)));
+    ranked
+        .into_iter()
+        .take(MAX_CANDIDATES)
+        .map(|(_, c)| c)
+        .collect()
+}
+
+fn byte_index(text: &str, char_index: usize) -> usize {
+    text.char_indices()
+        .nth(char_index)
+        .map_or(text.len(), |(i, _)| i)
+}
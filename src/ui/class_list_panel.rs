@@ -2,7 +2,10 @@ use eframe::egui::{
     self, RichText, ScrollArea, SelectableLabel, SidePanel, TextBuffer, TextEdit, vec2,
 };
 
-use crate::{class::ClassId, global_state::global_state};
+use crate::{
+    class::{ClassId, resolver::Rename},
+    global_state::global_state,
+};
 
 struct StateEditingClassName {
     request_focus_edit: bool,
@@ -80,6 +83,7 @@ impl ClassListPanel {
                             Select(ClassId),
                             Unselect,
                             Remove(ClassId),
+                            Rename(Rename),
                         }
 
                         let mut e = None;
@@ -123,7 +127,12 @@ impl ClassListPanel {
                                         // if not valid
                                         // focus it again
                                         if validate_class_name(&edit_buf) {
-                                            class.name = edit_buf.take();
+                                            let old =
+                                                std::mem::replace(&mut class.name, edit_buf.take());
+                                            e.replace(ChangeEvent::Rename(Rename::Class {
+                                                class: class.id(),
+                                                old,
+                                            }));
                                             self.edit_class_name_state = None;
                                         } else {
                                             *request_focus_edit = true;
@@ -193,6 +202,9 @@ impl ClassListPanel {
                                 ChangeEvent::Remove(id) => {
                                     state.class_list.remove_class(id);
                                 }
+                                ChangeEvent::Rename(rename) => {
+                                    state.apply_rename(rename);
+                                }
                             }
                         }
                     });
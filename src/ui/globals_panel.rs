@@ -0,0 +1,93 @@
+use eframe::egui::{Color32, Context, Grid, RichText, TextEdit, TextStyle, Window};
+
+use crate::{global_state::global_state, variables::Variable};
+
+#[derive(Default)]
+pub struct GlobalsPanel {
+    pub open: bool,
+}
+
+impl GlobalsPanel {
+    pub fn show(&mut self, ctx: &Context) {
+        if !self.open {
+            return;
+        }
+
+        let mut open = self.open;
+        Window::new(obfstr!("Globals"))
+            .open(&mut open)
+            .default_width(480.)
+            .show(ctx, |ui| {
+                ui.weak(obfstr!(
+                    "Usable in every class address, each can use the ones above it"
+                ));
+                ui.separator();
+
+                let state = global_state();
+                let mut changed = false;
+                let mut remove = None;
+
+                Grid::new("_globals_grid")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (i, v) in state.variables.variables_mut().iter_mut().enumerate() {
+                            let name = ui.add(
+                                TextEdit::singleline(&mut v.name)
+                                    .desired_width(100.)
+                                    .font(TextStyle::Monospace),
+                            );
+                            let expr = ui.add(
+                                TextEdit::singleline(&mut v.expr)
+                                    .desired_width(240.)
+                                    .font(TextStyle::Monospace),
+                            );
+                            // re-evaluate when done typing, expression may scan memory
+                            changed |= name.lost_focus() || expr.lost_focus();
+
+                            match (&v.error, v.value) {
+                                (Some(e), _) => {
+                                    ui.label(RichText::new(obfstr!("error")).color(Color32::RED))
+                                        .on_hover_text(e);
+                                }
+                                (None, Some(value)) => {
+                                    ui.monospace(format!("{value:#X}"));
+                                }
+                                (None, None) => {
+                                    ui.weak("-");
+                                }
+                            }
+
+                            if ui
+                                .small_button("x")
+                                .on_hover_text(obfstr!("Remove"))
+                                .clicked()
+                            {
+                                remove = Some(i);
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                if let Some(i) = remove {
+                    state.variables.variables_mut().remove(i);
+                    changed = true;
+                }
+
+                if ui.button(obfstr!("Add")).clicked() {
+                    let name = state.variables.unused_name();
+                    state
+                        .variables
+                        .variables_mut()
+                        .push(Variable::new(name, "0"));
+                    changed = true;
+                }
+
+                if changed {
+                    state.reevaluate_variables();
+                }
+            });
+
+        self.open &= open;
+    }
+}
@@ -1,14 +1,30 @@
+use std::{
+    ops::Range,
+    time::{Duration, Instant},
+};
+
 use eframe::egui::{
-    CentralPanel, Context, Key, ScrollArea, Ui, collapsing_header::CollapsingState,
+    CentralPanel, Color32, ComboBox, Context, DragValue, FontId, Key, RichText, ScrollArea, Stroke,
+    TextEdit, TextFormat, Ui, collapsing_header::CollapsingState, text::LayoutJob,
 };
 
 use crate::{
-    address::AddressString, field::FieldResponse, global_state::global_state,
-    inspection::InspectorContext, styling::get_current_font_size_hex_view,
+    address::AddressString,
+    address_parser::ParseDiagnostic,
+    class::{Class, ReevalPolicy},
+    field::FieldResponse,
+    global_state::global_state,
+    inspection::InspectorContext,
+    styling::{UNREADABLE_COLOR, get_current_font_size_hex_view},
 };
 
+use crate::ui::address_completion::AddressCompletion;
+
 pub struct InspectorPanel {
     address_buffer: String,
+    /// kept until the text change, so user can see what is wrong
+    address_error: Option<AddressError>,
+    completion: AddressCompletion,
     allow_scroll: bool,
 }
 
@@ -16,11 +32,40 @@ impl Default for InspectorPanel {
     fn default() -> Self {
         Self {
             address_buffer: format!("{:#X}", 0),
+            address_error: None,
+            completion: AddressCompletion::default(),
             allow_scroll: true,
         }
     }
 }
 
+struct AddressError {
+    class: String,
+    message: String,
+    help: Option<String>,
+    /// byte range in `address_buffer`, only for parse error
+    span: Option<Range<usize>>,
+}
+
+impl AddressError {
+    fn new(class: &str, e: &eyre::Report) -> Self {
+        match e.downcast_ref::<ParseDiagnostic>() {
+            Some(d) => Self {
+                class: class.to_owned(),
+                message: d.message.clone(),
+                help: d.help.clone(),
+                span: Some(d.span.range()),
+            },
+            None => Self {
+                class: class.to_owned(),
+                message: format!("{e}"),
+                help: None,
+                span: None,
+            },
+        }
+    }
+}
+
 impl InspectorPanel {
     pub fn show(&mut self, ctx: &Context) -> Option<FieldResponse> {
         let mut r = None;
@@ -53,24 +98,92 @@ impl InspectorPanel {
                         .sum::<f32>()
                         .max(160.);
 
-                    let r = ui.text_edit_singleline(&mut self.address_buffer);
+                    // error from another class
+                    if self
+                        .address_error
+                        .as_ref()
+                        .is_some_and(|e| e.class != active_class.name)
+                    {
+                        self.address_error = None;
+                    }
+
+                    let span = self.address_error.as_ref().and_then(|e| e.span.clone());
+                    let mut layouter = |ui: &Ui, text: &str, _wrap_width: f32| {
+                        let job = underlined(
+                            text,
+                            span.clone(),
+                            get_current_font_size_hex_view(),
+                            ui.visuals().text_color(),
+                        );
+                        ui.fonts(|f| f.layout_job(job))
+                    };
+                    let accept = self.completion.take_keys(ui);
+                    let mut output = TextEdit::singleline(&mut self.address_buffer)
+                        .layouter(&mut layouter)
+                        .show(ui);
+                    let completed =
+                        self.completion
+                            .show(ui, &mut output, &mut self.address_buffer, accept);
+                    let r = output.response;
+                    if r.changed() || completed {
+                        self.address_error = None;
+                    }
 
                     // parse addr on enter
                     if r.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
                         match AddressString::load_str(&self.address_buffer) {
                             Ok(addr) => {
                                 active_class.address.replace(addr);
+                                self.address_error = None;
+                                // classes using this one by name, and catch self reference
+                                let failed = global_state().class_list.reevaluate_addresses();
+                                if let Some((class, e)) =
+                                    failed.iter().find(|(c, _)| *c == active_class.name)
+                                {
+                                    self.address_error = Some(AddressError::new(class, e));
+                                }
                             }
                             Err(e) => {
                                 global_state().toasts.error(format!("{e}"));
+                                self.address_error =
+                                    Some(AddressError::new(&active_class.name, &e));
                             }
                         }
                     }
 
-                    // reset it for current data
-                    if !r.has_focus() {
+                    // reset it for current data, keep bad text while error shown
+                    if !r.has_focus() && self.address_error.is_none() {
                         self.address_buffer = active_class.address.borrow().to_string();
                     }
+
+                    if let Some(e) = &self.address_error {
+                        let label = ui.colored_label(Color32::RED, &e.message);
+                        if let Some(help) = &e.help {
+                            label.on_hover_text(help);
+                        }
+                    }
+
+                    resolved_address(ui, active_class);
+                    reeval_policy(ui, active_class);
+
+                    let size = active_class.class_size();
+                    let readable = global_state()
+                        .memory
+                        .readable_bytes(active_class.address.borrow().address_value(), size);
+                    let color = match readable {
+                        0 => UNREADABLE_COLOR,
+                        n if n == size => Color32::LIGHT_GREEN,
+                        _ => Color32::ORANGE,
+                    };
+                    ui.label(
+                        RichText::new(format!(
+                            "{}{}%",
+                            obfstr!("readable "),
+                            readable * 100 / size.max(1)
+                        ))
+                        .color(color),
+                    )
+                    .on_hover_text(format!("{readable} / {size} {}", obfstr!("bytes")));
                 })
                 .body(|ui| self.inspector(ui))
                 .2;
@@ -84,11 +197,13 @@ impl InspectorPanel {
         let state = global_state();
 
         let class = state.class_list.selected_class()?;
+        let address = class.address.borrow().address_value();
+        state.memory.prefetch(address, class.class_size());
 
         let mut ctx = InspectorContext {
             selection: state.selection_field,
             class_container: state.class_list.selected()?,
-            address: class.address.borrow().address_value(),
+            address,
             offset: 0,
             class_list: &state.class_list,
             toasts: &mut state.toasts,
@@ -116,3 +231,105 @@ impl InspectorPanel {
         response.inner
     }
 }
+
+/// How long a changed address stay highlighted
+const CHANGE_HIGHLIGHT: Duration = Duration::from_secs(1);
+
+/// Value of the expression, yellow right after change, red when it failed
+fn resolved_address(ui: &mut Ui, class: &Class) {
+    let address = class.address.borrow();
+    if let Some(e) = address.error() {
+        ui.colored_label(Color32::RED, obfstr!("= unresolved"))
+            .on_hover_text(e);
+        return;
+    }
+
+    let mut color = ui.visuals().text_color();
+    if let Some(elapsed) = address.changed_at().map(|t| t.elapsed())
+        && elapsed < CHANGE_HIGHLIGHT
+    {
+        color = Color32::YELLOW;
+        // repaint to clear the highlight
+        ui.ctx().request_repaint_after(CHANGE_HIGHLIGHT - elapsed);
+    }
+    ui.colored_label(color, format!("= {:#X}", address.address_value()));
+}
+
+fn reeval_policy(ui: &mut Ui, class: &Class) {
+    let mut policy = class.reeval.get();
+    ComboBox::from_id_salt("_reeval_policy")
+        .selected_text(policy.to_string())
+        .show_ui(ui, |ui| {
+            for p in ReevalPolicy::ALL {
+                if ui
+                    .selectable_label(policy.same_kind(p), p.to_string())
+                    .clicked()
+                    && !policy.same_kind(p)
+                {
+                    policy = p;
+                }
+            }
+        })
+        .response
+        .on_hover_text(obfstr!("When the address expression is evaluated again"));
+
+    if let ReevalPolicy::Interval(every) = &mut policy {
+        let mut ms = every.as_millis() as u64;
+        if ui
+            .add(DragValue::new(&mut ms).range(16..=60_000).suffix(" ms"))
+            .changed()
+        {
+            *every = Duration::from_millis(ms);
+        }
+    }
+    class.reeval.set(policy);
+
+    if ui
+        .small_button(obfstr!("Refresh"))
+        .on_hover_text(obfstr!("Evaluate address again now"))
+        .clicked()
+    {
+        _ = class.reevaluate_address(Instant::now());
+    }
+}
+
+/// `text` with `span` underlined red, empty span mark the char before it
+fn underlined(
+    text: &str,
+    span: Option<Range<usize>>,
+    font_id: FontId,
+    color: Color32,
+) -> LayoutJob {
+    let normal = TextFormat {
+        font_id,
+        color,
+        ..Default::default()
+    };
+    let mut job = LayoutJob::default();
+
+    let span = span.and_then(|span| {
+        let end = span.end.min(text.len());
+        let mut start = span.start.min(end);
+        if start == end {
+            start = text.get(..end)?.char_indices().last()?.0;
+        }
+        text.get(start..end).map(|_| start..end)
+    });
+    let Some(span) = span else {
+        job.append(text, 0., normal);
+        return job;
+    };
+
+    job.append(&text[..span.start], 0., normal.clone());
+    job.append(
+        &text[span.clone()],
+        0.,
+        TextFormat {
+            color: Color32::RED,
+            underline: Stroke::new(1.5_f32, Color32::RED),
+            ..normal.clone()
+        },
+    );
+    job.append(&text[span.end..], 0., normal);
+    job
+}
@@ -1,4 +1,9 @@
+pub mod address_completion;
 pub mod class_list_panel;
+pub mod globals_panel;
 pub mod inspector_panel;
 pub mod modals;
+pub mod pointer_scan_panel;
+pub mod process_panel;
+pub mod scanner_panel;
 pub mod toolbar_panel;
@@ -0,0 +1,291 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread::JoinHandle,
+};
+
+use eframe::egui::{
+    self, Button, Context, DragValue, ProgressBar, RichText, ScrollArea, TextEdit, Window,
+};
+
+use crate::{
+    address::AddressString,
+    global_state::global_state,
+    pointer_scan::{
+        PointerPath, PointerScanOptions, load_paths, pointer_scan_size, revalidate_paths,
+        save_paths, scan_pointer_paths,
+    },
+};
+
+struct PathJob {
+    handle: JoinHandle<eyre::Result<Vec<PointerPath>>>,
+    progress: Arc<AtomicUsize>,
+    total: usize,
+}
+
+pub struct PointerScanPanel {
+    pub open: bool,
+    target: String,
+    max_depth: usize,
+    max_offset: usize,
+    max_results: usize,
+    paths: Vec<PointerPath>,
+    job: Option<PathJob>,
+}
+
+impl Default for PointerScanPanel {
+    fn default() -> Self {
+        Self {
+            open: false,
+            target: String::new(),
+            max_depth: 4,
+            max_offset: 0x1000,
+            max_results: 10_000,
+            paths: vec![],
+            job: None,
+        }
+    }
+}
+
+impl PointerScanPanel {
+    pub fn show(&mut self, ctx: &Context) {
+        self.poll_job();
+
+        if !self.open {
+            return;
+        }
+        if self.job.is_some() {
+            ctx.request_repaint();
+        }
+
+        let mut open = self.open;
+        Window::new(obfstr!("Pointer scan"))
+            .open(&mut open)
+            .default_size([480., 420.])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        TextEdit::singleline(&mut self.target)
+                            .hint_text(obfstr!("target address"))
+                            .desired_width(200.),
+                    );
+                    ui.add(
+                        DragValue::new(&mut self.max_depth)
+                            .range(1..=8)
+                            .prefix(obfstr!("depth: ")),
+                    );
+                    ui.add(
+                        DragValue::new(&mut self.max_offset)
+                            .range(0..=0x10_0000)
+                            .hexadecimal(1, false, true)
+                            .prefix(obfstr!("max offset: 0x")),
+                    );
+                    ui.add(
+                        DragValue::new(&mut self.max_results)
+                            .range(1..=1_000_000)
+                            .prefix(obfstr!("max results: ")),
+                    );
+                });
+
+                ui.horizontal(|ui| {
+                    let idle = self.job.is_none() && global_state().raw_memory.is_some();
+                    if ui.add_enabled(idle, Button::new(obfstr!("Scan"))).clicked() {
+                        self.start_scan();
+                    }
+                    if ui
+                        .add_enabled(
+                            idle && !self.paths.is_empty(),
+                            Button::new(obfstr!("Revalidate")),
+                        )
+                        .on_hover_text(obfstr!("Keep only paths still leading to target"))
+                        .clicked()
+                    {
+                        self.start_revalidate();
+                    }
+
+                    ui.separator();
+
+                    if ui
+                        .add_enabled(!self.paths.is_empty(), Button::new(obfstr!("Save...")))
+                        .clicked()
+                    {
+                        self.save();
+                    }
+                    if ui
+                        .add_enabled(self.job.is_none(), Button::new(obfstr!("Load...")))
+                        .clicked()
+                    {
+                        self.load();
+                    }
+                });
+
+                if let Some(job) = &self.job {
+                    let done = job.progress.load(Ordering::Relaxed);
+                    ui.add(
+                        ProgressBar::new(done as f32 / job.total.max(1) as f32).show_percentage(),
+                    );
+                }
+
+                ui.separator();
+                ui.label(format!("{}{}", self.paths.len(), obfstr!(" paths")));
+                self.paths_view(ui);
+            });
+
+        self.open &= open;
+    }
+
+    fn paths_view(&mut self, ui: &mut egui::Ui) {
+        let row_height = ui.text_style_height(&egui::TextStyle::Body) + 4.;
+        ScrollArea::both().auto_shrink([false, false]).show_rows(
+            ui,
+            row_height,
+            self.paths.len(),
+            |ui, rows| {
+                for path in &self.paths[rows] {
+                    ui.horizontal(|ui| {
+                        let expr = path.to_string();
+                        if ui.small_button(obfstr!("Use")).clicked() {
+                            use_as_class_address(&expr);
+                        }
+                        if ui.small_button(obfstr!("Copy")).clicked() {
+                            ui.ctx().copy_text(expr.clone());
+                        }
+                        ui.label(RichText::new(expr).monospace());
+                    });
+                }
+            },
+        );
+    }
+
+    fn target(&self) -> Option<usize> {
+        match AddressString::load_str(&self.target) {
+            Ok(a) => Some(a.address_value()),
+            Err(e) => {
+                global_state()
+                    .toasts
+                    .error(format!("{}\n{e}", obfstr!("Invalid target address:")));
+                None
+            }
+        }
+    }
+
+    fn start_scan(&mut self) {
+        let (Some(target), Some(memory)) = (self.target(), global_state().raw_memory.clone())
+        else {
+            return;
+        };
+        let options = PointerScanOptions {
+            target,
+            max_depth: self.max_depth,
+            max_offset: self.max_offset,
+            max_results: self.max_results,
+        };
+
+        let progress = Arc::new(AtomicUsize::new(0));
+        let total = pointer_scan_size(&*memory);
+        let handle = {
+            let progress = progress.clone();
+            std::thread::spawn(move || scan_pointer_paths(&*memory, &options, &progress))
+        };
+        self.job = Some(PathJob {
+            handle,
+            progress,
+            total,
+        });
+    }
+
+    fn start_revalidate(&mut self) {
+        let (Some(target), Some(memory)) = (self.target(), global_state().raw_memory.clone())
+        else {
+            return;
+        };
+
+        let paths = self.paths.clone();
+        let progress = Arc::new(AtomicUsize::new(0));
+        let total = paths.len();
+        let handle = {
+            let progress = progress.clone();
+            std::thread::spawn(move || Ok(revalidate_paths(&*memory, &paths, target, &progress)))
+        };
+        self.job = Some(PathJob {
+            handle,
+            progress,
+            total,
+        });
+    }
+
+    fn poll_job(&mut self) {
+        if !self.job.as_ref().is_some_and(|j| j.handle.is_finished()) {
+            return;
+        }
+        let job = self.job.take().unwrap();
+        match job.handle.join() {
+            Ok(Ok(paths)) => {
+                self.paths = paths;
+            }
+            Ok(Err(e)) => {
+                global_state()
+                    .toasts
+                    .error(format!("{}\n{e}", obfstr!("Pointer scan failed:")));
+            }
+            Err(_) => {
+                global_state()
+                    .toasts
+                    .error(obfstr!("Pointer scan thread panicked"));
+            }
+        }
+    }
+
+    fn save(&self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(obfstr!("Pointer paths"), &["ptrs"])
+            .save_file()
+        else {
+            return;
+        };
+        if let Err(e) = save_paths(&path, &self.paths) {
+            global_state().toasts.error(format!(
+                "{}{}\n{e}",
+                obfstr!("Failed save pointer paths to "),
+                path.display()
+            ));
+        }
+    }
+
+    fn load(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter(obfstr!("Pointer paths"), &["ptrs"])
+            .pick_file()
+        else {
+            return;
+        };
+        match load_paths(&path) {
+            Ok(paths) => self.paths = paths,
+            Err(e) => {
+                global_state().toasts.error(format!(
+                    "{}{}\n{e}",
+                    obfstr!("Failed load pointer paths from "),
+                    path.display()
+                ));
+            }
+        }
+    }
+}
+
+fn use_as_class_address(expr: &str) {
+    let state = global_state();
+    let Some(class) = state.class_list.selected_class() else {
+        state.toasts.error(obfstr!("Select a class first"));
+        return;
+    };
+    match AddressString::load_str(expr) {
+        Ok(address) => {
+            class.address.replace(address);
+        }
+        Err(e) => {
+            state.toasts.error(format!("{e}"));
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::time::{Duration, Instant};
+
+use eframe::egui::{self, Context, RichText, ScrollArea, SelectableLabel, TextEdit, Window};
+
+use crate::{
+    global_state::global_state,
+    process::{ProcessEntry, find_process_by_name, is_process_alive, list_processes},
+};
+
+/// How often we check attached process still alive
+const ALIVE_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+/// How often the process list refresh while window opened
+const LIST_REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct ProcessPanel {
+    pub open: bool,
+    filter: String,
+    processes: Vec<ProcessEntry>,
+    selected: Option<i32>,
+    listed_at: Option<Instant>,
+    checked_alive_at: Instant,
+    /// executable name waiting to come back for auto reattach
+    waiting_for: Option<String>,
+}
+
+impl Default for ProcessPanel {
+    fn default() -> Self {
+        Self {
+            open: false,
+            filter: String::new(),
+            processes: vec![],
+            selected: None,
+            listed_at: None,
+            checked_alive_at: Instant::now(),
+            waiting_for: None,
+        }
+    }
+}
+
+impl ProcessPanel {
+    pub fn show(&mut self, ctx: &Context) {
+        self.poll_process();
+
+        if !self.open {
+            // force a fresh list next time window opened
+            self.listed_at = None;
+            return;
+        }
+
+        if self
+            .listed_at
+            .is_none_or(|t| t.elapsed() >= LIST_REFRESH_INTERVAL)
+        {
+            self.refresh();
+        }
+
+        let mut open = self.open;
+        Window::new(obfstr!("Processes"))
+            .open(&mut open)
+            .default_size([360., 420.])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        TextEdit::singleline(&mut self.filter)
+                            .hint_text(obfstr!("Filter by name"))
+                            .desired_width(200.),
+                    );
+
+                    if ui.button(obfstr!("Refresh")).clicked() {
+                        self.refresh();
+                    }
+                });
+
+                ui.separator();
+
+                let filter = self.filter.to_lowercase();
+                let mut attach = None;
+                ScrollArea::vertical()
+                    .max_height(ui.available_height() - 32.)
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        for p in self.processes.iter().filter(|p| {
+                            filter.is_empty() || p.name.to_lowercase().contains(&filter)
+                        }) {
+                            let attached = global_state()
+                                .process
+                                .as_ref()
+                                .is_some_and(|a| a.pid == p.pid);
+                            let mut text = RichText::new(format!("{:>7}  {}", p.pid, p.name));
+                            if attached {
+                                text = text.strong();
+                            }
+
+                            let r = ui
+                                .add(SelectableLabel::new(self.selected == Some(p.pid), text))
+                                .on_hover_text(&p.cmdline);
+
+                            if r.double_clicked() {
+                                attach = Some(p.clone());
+                            } else if r.clicked() {
+                                self.selected = Some(p.pid);
+                            }
+                        }
+                    });
+
+                ui.separator();
+
+                ui.horizontal(|ui| {
+                    let selected = self
+                        .selected
+                        .and_then(|pid| self.processes.iter().find(|p| p.pid == pid));
+                    if ui
+                        .add_enabled(selected.is_some(), egui::Button::new(obfstr!("Attach")))
+                        .clicked()
+                    {
+                        attach = selected.cloned();
+                    }
+
+                    if ui
+                        .add_enabled(
+                            global_state().process.is_some(),
+                            egui::Button::new(obfstr!("Detach")),
+                        )
+                        .clicked()
+                    {
+                        self.detach();
+                    }
+                });
+
+                if let Some(p) = attach
+                    && self.attach(p)
+                {
+                    self.open = false;
+                }
+            });
+
+        self.open &= open;
+    }
+
+    pub fn attach(&mut self, process: ProcessEntry) -> bool {
+        self.waiting_for = None;
+        match global_state().attach_process(process.clone()) {
+            Ok(()) => {
+                global_state()
+                    .toasts
+                    .info(format!("{}{process}", obfstr!("Attached to ")));
+                true
+            }
+            Err(e) => {
+                global_state()
+                    .toasts
+                    .error(format!("{}{process}\n{e}", obfstr!("Failed attach to ")));
+                false
+            }
+        }
+    }
+
+    pub fn attach_last(&mut self) {
+        let Some(name) = global_state().last_process_name.clone() else {
+            global_state()
+                .toasts
+                .error(obfstr!("No process attached before"));
+            return;
+        };
+
+        match find_process_by_name(&name) {
+            Some(p) => _ = self.attach(p),
+            None => {
+                global_state()
+                    .toasts
+                    .error(format!("{}{name}", obfstr!("Process not running: ")));
+            }
+        }
+    }
+
+    pub fn detach(&mut self) {
+        self.waiting_for = None;
+        global_state().detach_process();
+    }
+
+    fn refresh(&mut self) {
+        self.processes = list_processes();
+        self.listed_at = Some(Instant::now());
+    }
+
+    /// Detect attached process exit, reattach when it come back
+    fn poll_process(&mut self) {
+        if self.checked_alive_at.elapsed() < ALIVE_CHECK_INTERVAL {
+            return;
+        }
+        self.checked_alive_at = Instant::now();
+
+        let state = global_state();
+        if let Some(p) = &state.process
+            && !is_process_alive(p.pid)
+        {
+            state
+                .toasts
+                .warning(format!("{}{p}", obfstr!("Process exited: ")));
+            if state.auto_reattach {
+                self.waiting_for = Some(p.name.clone());
+            }
+            state.detach_process();
+        }
+
+        if !state.auto_reattach {
+            self.waiting_for = None;
+            return;
+        }
+
+        if let Some(name) = &self.waiting_for
+            && let Some(p) = find_process_by_name(name)
+        {
+            // attach also re-evaluate every class address
+            self.attach(p);
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread::JoinHandle,
+};
+
+use eframe::egui::{self, Button, ComboBox, Context, ProgressBar, ScrollArea, TextEdit, Window};
+
+use crate::{
+    address::AddressString,
+    global_state::global_state,
+    memory::MemoryState,
+    scanner::{
+        MAX_HITS, ScanCompare, ScanHit, ScanKind, ScanOptions, ScanResults, ScanValueType,
+        first_scan, next_scan, scan_size,
+    },
+};
+
+struct ScanJob {
+    handle: JoinHandle<eyre::Result<ScanResults>>,
+    progress: Arc<AtomicUsize>,
+    total: usize,
+    memory: Arc<dyn MemoryState>,
+}
+
+pub struct ScannerPanel {
+    pub open: bool,
+    value_type: ScanValueType,
+    kind: ScanKind,
+    value: String,
+    value_to: String,
+    aligned: bool,
+    results: Option<Arc<ScanResults>>,
+    /// backend `results` came from
+    results_memory: Option<Arc<dyn MemoryState>>,
+    job: Option<ScanJob>,
+}
+
+impl Default for ScannerPanel {
+    fn default() -> Self {
+        Self {
+            open: false,
+            value_type: ScanValueType::I32,
+            kind: ScanKind::Exact,
+            value: String::new(),
+            value_to: String::new(),
+            aligned: true,
+            results: None,
+            results_memory: None,
+            job: None,
+        }
+    }
+}
+
+impl ScannerPanel {
+    pub fn show(&mut self, ctx: &Context) {
+        self.poll_job();
+
+        // results point into memory we no longer look at
+        let current = global_state().raw_memory.clone();
+        if self.results.is_some()
+            && !matches!((&current, &self.results_memory), (Some(a), Some(b)) if Arc::ptr_eq(a, b))
+        {
+            self.reset();
+        }
+
+        if !self.open {
+            return;
+        }
+        if self.job.is_some() {
+            ctx.request_repaint();
+        }
+
+        let mut open = self.open;
+        Window::new(obfstr!("Scanner"))
+            .open(&mut open)
+            .default_size([420., 480.])
+            .show(ctx, |ui| {
+                self.scan_options(ui);
+
+                ui.horizontal(|ui| {
+                    let idle = self.job.is_none() && current.is_some();
+                    if ui
+                        .add_enabled(idle, Button::new(obfstr!("First scan")))
+                        .clicked()
+                    {
+                        self.reset();
+                        self.start_scan();
+                    }
+                    if ui
+                        .add_enabled(
+                            idle && self.results.is_some(),
+                            Button::new(obfstr!("Next scan")),
+                        )
+                        .clicked()
+                    {
+                        self.start_scan();
+                    }
+                    if ui
+                        .add_enabled(self.job.is_none(), Button::new(obfstr!("Reset")))
+                        .clicked()
+                    {
+                        self.reset();
+                    }
+                });
+
+                if let Some(job) = &self.job {
+                    let done = job.progress.load(Ordering::Relaxed);
+                    ui.add(
+                        ProgressBar::new(done as f32 / job.total.max(1) as f32).show_percentage(),
+                    );
+                }
+
+                ui.separator();
+                self.results_view(ui);
+            });
+
+        self.open &= open;
+    }
+
+    fn scan_options(&mut self, ui: &mut egui::Ui) {
+        let first = self.results.is_none();
+
+        ui.horizontal(|ui| {
+            // next scans compare values of the same type
+            ui.add_enabled_ui(first, |ui| {
+                ComboBox::from_id_salt("_scan_value_type")
+                    .selected_text(self.value_type.to_string())
+                    .show_ui(ui, |ui| {
+                        for t in ScanValueType::ALL {
+                            ui.selectable_value(&mut self.value_type, t, t.to_string());
+                        }
+                    });
+            });
+
+            ComboBox::from_id_salt("_scan_kind")
+                .selected_text(self.kind.to_string())
+                .width(180.)
+                .show_ui(ui, |ui| {
+                    for k in ScanKind::ALL.into_iter().filter(|k| {
+                        (*k == ScanKind::Exact || !self.value_type.is_text())
+                            && if first {
+                                !k.needs_previous()
+                            } else {
+                                *k != ScanKind::Unknown
+                            }
+                    }) {
+                        ui.selectable_value(&mut self.kind, k, k.to_string());
+                    }
+                });
+
+            ui.add_enabled(
+                !self.value_type.is_text(),
+                egui::Checkbox::new(&mut self.aligned, obfstr!("Aligned")),
+            );
+        });
+
+        // choice from before may not fit anymore
+        if self.value_type.is_text()
+            || (first && self.kind.needs_previous())
+            || (!first && self.kind == ScanKind::Unknown)
+        {
+            self.kind = ScanKind::Exact;
+        }
+
+        ui.horizontal(|ui| {
+            if self.kind.inputs() > 0 {
+                ui.add(
+                    TextEdit::singleline(&mut self.value)
+                        .hint_text(obfstr!("value"))
+                        .desired_width(160.),
+                );
+            }
+            if self.kind.inputs() > 1 {
+                ui.label(obfstr!("and"));
+                ui.add(
+                    TextEdit::singleline(&mut self.value_to)
+                        .hint_text(obfstr!("value"))
+                        .desired_width(160.),
+                );
+            }
+        });
+    }
+
+    fn results_view(&mut self, ui: &mut egui::Ui) {
+        let Some(results) = &self.results else {
+            ui.label(obfstr!("No scan yet"));
+            return;
+        };
+
+        let hits = match &**results {
+            ScanResults::Snapshot(regions) => {
+                let bytes = regions.iter().map(|r| r.bytes.len()).sum::<usize>();
+                ui.label(format!(
+                    "{bytes}{}",
+                    obfstr!(" bytes kept, next scan to narrow down")
+                ));
+                return;
+            }
+            ScanResults::Hits { hits, truncated } => {
+                if *truncated {
+                    ui.label(format!(
+                        "{}{MAX_HITS}{}",
+                        obfstr!("More than "),
+                        obfstr!(" results, rest dropped")
+                    ));
+                } else {
+                    ui.label(format!("{}{}", hits.len(), obfstr!(" results")));
+                }
+                hits
+            }
+        };
+
+        let value_type = self.value_type;
+        // text results are as long as what was searched
+        let width = match value_type.is_text() {
+            true => value_type.pattern(&self.value).len(),
+            false => value_type.size(),
+        };
+
+        let row_height = ui.text_style_height(&egui::TextStyle::Body) + 4.;
+        let mut open_class = None;
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show_rows(ui, row_height, hits.len(), |ui, rows| {
+                for hit in &hits[rows] {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{:012X}", hit.address));
+
+                        let mut current = vec![0u8; width];
+                        if global_state().memory.read_buf(hit.address, &mut current) == width {
+                            ui.label(value_type.format(&current));
+                        } else {
+                            ui.label("??");
+                        }
+                        if !value_type.is_text() {
+                            ui.weak(value_type.format(&hit.previous[..width]));
+                        }
+
+                        if ui.small_button(obfstr!("Open as class")).clicked() {
+                            open_class = Some(*hit);
+                        }
+                    });
+                }
+            });
+
+        if let Some(hit) = open_class {
+            open_as_class(hit);
+        }
+    }
+
+    fn start_scan(&mut self) {
+        let Some(memory) = global_state().raw_memory.clone() else {
+            return;
+        };
+        let compare =
+            match ScanCompare::new(self.value_type, self.kind, &self.value, &self.value_to) {
+                Ok(c) => c,
+                Err(e) => {
+                    global_state()
+                        .toasts
+                        .error(format!("{}\n{e}", obfstr!("Invalid scan:")));
+                    return;
+                }
+            };
+        let options = ScanOptions {
+            value_type: self.value_type,
+            aligned: self.aligned,
+        };
+
+        let previous = self.results.clone();
+        let total = match &previous {
+            Some(p) => p.work(),
+            None => scan_size(&*memory),
+        };
+        let progress = Arc::new(AtomicUsize::new(0));
+
+        let handle = {
+            let memory = memory.clone();
+            let progress = progress.clone();
+            std::thread::spawn(move || match previous {
+                Some(previous) => next_scan(&*memory, &options, &compare, &previous, &progress),
+                None => first_scan(&*memory, &options, &compare, &progress),
+            })
+        };
+        self.job = Some(ScanJob {
+            handle,
+            progress,
+            total,
+            memory,
+        });
+    }
+
+    fn poll_job(&mut self) {
+        if !self.job.as_ref().is_some_and(|j| j.handle.is_finished()) {
+            return;
+        }
+        let job = self.job.take().unwrap();
+        match job.handle.join() {
+            Ok(Ok(results)) => {
+                self.results = Some(Arc::new(results));
+                self.results_memory = Some(job.memory);
+            }
+            Ok(Err(e)) => {
+                global_state()
+                    .toasts
+                    .error(format!("{}\n{e}", obfstr!("Scan failed:")));
+            }
+            Err(_) => {
+                global_state().toasts.error(obfstr!("Scan thread panicked"));
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.results = None;
+        self.results_memory = None;
+    }
+}
+
+/// Point selected class at the result
+fn open_as_class(hit: ScanHit) {
+    let state = global_state();
+    let Some(class) = state.class_list.selected_class() else {
+        state.toasts.error(obfstr!("Select a class first"));
+        return;
+    };
+    class.address.replace(AddressString::from(hit.address));
+    state.toasts.info(format!(
+        "{}{}{:#X}",
+        class.name,
+        obfstr!(" now at "),
+        hit.address
+    ));
+}
@@ -2,15 +2,19 @@ use eframe::egui::{
     self, Button, Color32, Context, CornerRadius, Frame, Margin, RichText, TopBottomPanel,
 };
 
-use crate::field::{
-    Field,
-    boolean::BoolField,
-    class_pointer::ClassPointerField,
-    float::FloatField,
-    hex::HexField,
-    int::IntField,
-    string::{PointerTextField, TextField},
-    vector::VectorField,
+use crate::{
+    field::{
+        Field,
+        boolean::BoolField,
+        class_pointer::ClassPointerField,
+        float::FloatField,
+        hex::HexField,
+        int::IntField,
+        string::{PointerTextField, TextField},
+        vector::VectorField,
+    },
+    global_state::global_state,
+    memory::poller::{MAX_RATE_HZ, MIN_RATE_HZ},
 };
 
 pub enum ToolBarResponse {
@@ -22,10 +26,32 @@ pub enum ToolBarResponse {
     InsertBytes(usize),
     AlignHexFields,
     DeleteField,
+    OpenProcessList,
+    AttachLastProcess,
+    DetachProcess,
+    OpenDump,
+    /// Save snapshot following pointers this deep
+    SaveSnapshot(usize),
+    /// Connect gdb remote stub at `host:port`
+    ConnectGdb(String),
+    OpenScanner,
+    OpenPointerScan,
+    OpenGlobals,
 }
 
-#[derive(Default)]
-pub struct ToolBarPanel {}
+pub struct ToolBarPanel {
+    snapshot_depth: usize,
+    gdb_target: String,
+}
+
+impl Default for ToolBarPanel {
+    fn default() -> Self {
+        Self {
+            snapshot_depth: 2,
+            gdb_target: obfstring!("127.0.0.1:1234"),
+        }
+    }
+}
 
 impl ToolBarPanel {
     pub fn show(&mut self, ctx: &Context) -> Option<ToolBarResponse> {
@@ -51,7 +77,88 @@ impl ToolBarPanel {
 
                     ui.separator();
 
-                    ui.menu_button("Process", |ui| {});
+                    ui.menu_button("Process", |ui| {
+                        if ui.button(obfstr!("Attach...")).clicked() {
+                            response.replace(ToolBarResponse::OpenProcessList);
+                            ui.close_menu();
+                        }
+
+                        let last = global_state().last_process_name.clone();
+                        let text = match &last {
+                            Some(name) => format!("{}{name}", obfstr!("Attach to last: ")),
+                            None => obfstring!("Attach to last"),
+                        };
+                        if ui.add_enabled(last.is_some(), Button::new(text)).clicked() {
+                            response.replace(ToolBarResponse::AttachLastProcess);
+                            ui.close_menu();
+                        }
+
+                        if ui
+                            .add_enabled(
+                                global_state().process.is_some(),
+                                Button::new(obfstr!("Detach")),
+                            )
+                            .clicked()
+                        {
+                            response.replace(ToolBarResponse::DetachProcess);
+                            ui.close_menu();
+                        }
+
+                        ui.separator();
+
+                        ui.checkbox(
+                            &mut global_state().auto_reattach,
+                            obfstr!("Auto reattach on restart"),
+                        );
+
+                        ui.separator();
+
+                        if ui.button(obfstr!("Open dump or executable...")).clicked() {
+                            response.replace(ToolBarResponse::OpenDump);
+                            ui.close_menu();
+                        }
+
+                        ui.horizontal(|ui| {
+                            if ui.button(obfstr!("Save snapshot...")).clicked() {
+                                response
+                                    .replace(ToolBarResponse::SaveSnapshot(self.snapshot_depth));
+                                ui.close_menu();
+                            }
+                            ui.add(
+                                egui::DragValue::new(&mut self.snapshot_depth)
+                                    .range(0..=16)
+                                    .prefix(obfstr!("depth: ")),
+                            )
+                            .on_hover_text(obfstr!("How many pointer levels to follow"));
+                        });
+
+                        ui.separator();
+
+                        ui.horizontal(|ui| {
+                            let r = ui.add(
+                                egui::TextEdit::singleline(&mut self.gdb_target)
+                                    .hint_text(obfstr!("host:port"))
+                                    .desired_width(120.),
+                            );
+                            let enter =
+                                r.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                            if ui.button(obfstr!("Connect gdb remote")).clicked() || enter {
+                                response
+                                    .replace(ToolBarResponse::ConnectGdb(self.gdb_target.clone()));
+                                ui.close_menu();
+                            }
+                        });
+                    });
+
+                    if let Some(source) = &global_state().memory_source {
+                        let color = if global_state().process.is_some() {
+                            Color32::LIGHT_GREEN
+                        } else {
+                            Color32::LIGHT_BLUE
+                        };
+                        ui.label(RichText::new(source).color(color));
+                        self.poller_group(ui);
+                    }
 
                     ui.separator();
 
@@ -59,6 +166,20 @@ impl ToolBarPanel {
 
                     ui.separator();
 
+                    if ui.button(obfstr!("Scanner")).clicked() {
+                        response.replace(ToolBarResponse::OpenScanner);
+                    }
+
+                    if ui.button(obfstr!("Pointer scan")).clicked() {
+                        response.replace(ToolBarResponse::OpenPointerScan);
+                    }
+
+                    if ui.button(obfstr!("Globals")).clicked() {
+                        response.replace(ToolBarResponse::OpenGlobals);
+                    }
+
+                    ui.separator();
+
                     if ui.button("Align Class").clicked() {
                         response.replace(ToolBarResponse::AlignHexFields);
                     }
@@ -80,6 +201,48 @@ impl ToolBarPanel {
         response
     }
 
+    fn poller_group(&self, ui: &mut egui::Ui) {
+        let poller = &global_state().poller;
+
+        let paused = poller.is_paused();
+        let text = if paused {
+            obfstr!("Resume")
+        } else {
+            obfstr!("Pause")
+        };
+        if ui
+            .button(text)
+            .on_hover_text(obfstr!("Pause background refresh, view stay frozen"))
+            .clicked()
+        {
+            poller.set_paused(!paused);
+        }
+
+        let mut rate_hz = poller.rate_hz();
+        if ui
+            .add(
+                egui::DragValue::new(&mut rate_hz)
+                    .range(MIN_RATE_HZ..=MAX_RATE_HZ)
+                    .suffix(obfstr!(" Hz")),
+            )
+            .on_hover_text(obfstr!("Memory refresh rate"))
+            .changed()
+        {
+            poller.set_rate_hz(rate_hz);
+        }
+
+        if paused {
+            ui.label(RichText::new(obfstr!("paused")).color(Color32::GRAY));
+        } else if poller.is_stale() {
+            let lag = poller.lag().unwrap_or_default();
+            ui.label(RichText::new(obfstr!("stale")).color(Color32::ORANGE))
+                .on_hover_text(format!(
+                    "{}{lag:.1?}",
+                    obfstr!("Memory is not keeping up, last refresh ")
+                ));
+        }
+    }
+
     fn field_change_group(&self, ui: &mut egui::Ui, response: &mut Option<ToolBarResponse>) {
         macro_rules! group_type {
             ($ui:ident, $([$display:ident, $text_color:expr, $background:expr, $response_type:expr]),* $(,)?) => {
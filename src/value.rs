@@ -1,3 +1,5 @@
+use crate::field::field_tag::FieldTag;
+
 #[derive(Debug, Clone)]
 pub enum Value {
     U8(u8),
@@ -40,3 +42,164 @@ impl std::fmt::Display for Value {
         }
     }
 }
+
+impl Value {
+    /// Parse user input for a field of `tag`
+    ///
+    /// Accept what [`Value`]'s `Display` print, so a displayed value always parse back
+    pub fn parse_for(tag: FieldTag, input: &str) -> eyre::Result<Self> {
+        let input = input.trim();
+        Ok(match tag {
+            FieldTag::Bool => match input.to_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => Value::Bool(true),
+                "0" | "false" | "no" | "off" => Value::Bool(false),
+                _ => eyre::bail!("{}{input}", obfstr!("Unsupport boolean: ")),
+            },
+            FieldTag::I8 => Value::I8(parse_int_in_range(input, "i8")?),
+            FieldTag::I16 => Value::I16(parse_int_in_range(input, "i16")?),
+            FieldTag::I32 => Value::I32(parse_int_in_range(input, "i32")?),
+            FieldTag::I64 => Value::I64(parse_int_in_range(input, "i64")?),
+            FieldTag::U8 | FieldTag::Hex8 => Value::U8(parse_int_in_range(input, "u8")?),
+            FieldTag::U16 | FieldTag::Hex16 => Value::U16(parse_int_in_range(input, "u16")?),
+            FieldTag::U32 | FieldTag::Hex32 => Value::U32(parse_int_in_range(input, "u32")?),
+            FieldTag::U64 | FieldTag::Hex64 => Value::U64(parse_int_in_range(input, "u64")?),
+            FieldTag::Float32 => Value::F32(input.parse()?),
+            FieldTag::Float64 => Value::F64(input.parse()?),
+            FieldTag::Vec2 => {
+                let [x, y] = parse_floats(input)?;
+                Value::Vec2(x, y)
+            }
+            FieldTag::Vec3 => {
+                let [x, y, z] = parse_floats(input)?;
+                Value::Vec3(x, y, z)
+            }
+            FieldTag::Vec4 => {
+                let [x, y, z, w] = parse_floats(input)?;
+                Value::Vec4(x, y, z, w)
+            }
+            FieldTag::Utf8 | FieldTag::Utf16 | FieldTag::PtrUtf8 | FieldTag::PtrUtf16 => {
+                // displayed with quotes
+                let s = input
+                    .strip_prefix('"')
+                    .and_then(|s| s.strip_suffix('"'))
+                    .unwrap_or(input);
+                Value::String(s.to_owned())
+            }
+            FieldTag::ClassPointer => Value::HexAddress(parse_int_in_range(input, "pointer")?),
+            FieldTag::ClassInstance => {
+                eyre::bail!("{}", obfstr!("Class instance has no editable value"))
+            }
+        })
+    }
+
+    /// Native endian bytes as stored in memory
+    ///
+    /// String give raw utf8 bytes, text fields do their own encoding
+    pub fn to_ne_bytes(&self) -> Vec<u8> {
+        match self {
+            Value::U8(v) => v.to_ne_bytes().to_vec(),
+            Value::U16(v) => v.to_ne_bytes().to_vec(),
+            Value::U32(v) => v.to_ne_bytes().to_vec(),
+            Value::U64(v) => v.to_ne_bytes().to_vec(),
+            Value::I8(v) => v.to_ne_bytes().to_vec(),
+            Value::I16(v) => v.to_ne_bytes().to_vec(),
+            Value::I32(v) => v.to_ne_bytes().to_vec(),
+            Value::I64(v) => v.to_ne_bytes().to_vec(),
+            Value::F32(v) => v.to_ne_bytes().to_vec(),
+            Value::F64(v) => v.to_ne_bytes().to_vec(),
+            Value::Bool(v) => vec![*v as u8],
+            Value::Vec2(x, y) => [x, y].iter().flat_map(|v| v.to_ne_bytes()).collect(),
+            Value::Vec3(x, y, z) => [x, y, z].iter().flat_map(|v| v.to_ne_bytes()).collect(),
+            Value::Vec4(x, y, z, w) => [x, y, z, w].iter().flat_map(|v| v.to_ne_bytes()).collect(),
+            Value::String(v) => v.as_bytes().to_vec(),
+            Value::HexAddress(v) => v.to_ne_bytes().to_vec(),
+        }
+    }
+}
+
+/// Decimal, `0x` hex, `0b` binary, `0o` octal with optional sign
+fn parse_integer(input: &str) -> eyre::Result<i128> {
+    let (negative, digits) = match input.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, input.strip_prefix('+').unwrap_or(input)),
+    };
+
+    let (radix, digits) = match digits.get(..2) {
+        Some("0x" | "0X") => (16, &digits[2..]),
+        Some("0b" | "0B") => (2, &digits[2..]),
+        Some("0o" | "0O") => (8, &digits[2..]),
+        _ => (10, digits),
+    };
+
+    let digits = digits.replace('_', "");
+    let v = i128::from_str_radix(&digits, radix)
+        .map_err(|e| eyre::eyre!("{}{input}: {e}", obfstr!("Invalid integer ")))?;
+    Ok(if negative { -v } else { v })
+}
+
+fn parse_int_in_range<T: TryFrom<i128>>(input: &str, type_name: &str) -> eyre::Result<T> {
+    let v = parse_integer(input)?;
+    T::try_from(v).map_err(|_| eyre::eyre!("{v} {}{type_name}", obfstr!("out of range for ")))
+}
+
+/// `(1, 2.5)` `1 2.5` `1,2.5`
+fn parse_floats<const N: usize>(input: &str) -> eyre::Result<[f32; N]> {
+    let inner = input
+        .strip_prefix('(')
+        .and_then(|s| s.strip_suffix(')'))
+        .unwrap_or(input);
+
+    let values = inner
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<f32>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    values.try_into().map_err(|v: Vec<f32>| {
+        eyre::eyre!(
+            "{}{N}, {}{}",
+            obfstr!("Expected component count "),
+            obfstr!("found "),
+            v.len()
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_for_round_trip() {
+        let values = [
+            (FieldTag::U8, Value::U8(200)),
+            (FieldTag::I16, Value::I16(-1234)),
+            (FieldTag::U64, Value::U64(u64::MAX)),
+            (FieldTag::I64, Value::I64(i64::MIN)),
+            (FieldTag::Float32, Value::F32(3.3)),
+            (FieldTag::Float64, Value::F64(-0.125)),
+            (FieldTag::Bool, Value::Bool(true)),
+            (FieldTag::Vec3, Value::Vec3(1., -2.5, 3.)),
+            (FieldTag::Utf8, Value::String("hello world".into())),
+            (FieldTag::ClassPointer, Value::HexAddress(0x7FF0_1234)),
+        ];
+
+        for (tag, v) in values {
+            let parsed = Value::parse_for(tag.clone(), &v.to_string()).unwrap();
+            assert_eq!(parsed.to_string(), v.to_string(), "{tag:?}");
+            assert_eq!(parsed.to_ne_bytes(), v.to_ne_bytes(), "{tag:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_for_range_check() {
+        assert!(Value::parse_for(FieldTag::U8, "256").is_err());
+        assert!(Value::parse_for(FieldTag::U8, "-1").is_err());
+        assert!(Value::parse_for(FieldTag::I8, "-129").is_err());
+        assert!(Value::parse_for(FieldTag::I8, "-128").is_ok());
+        assert!(Value::parse_for(FieldTag::U16, "0xFFFF").is_ok());
+        assert!(Value::parse_for(FieldTag::U16, "0x1_0000").is_err());
+        assert!(Value::parse_for(FieldTag::Vec2, "(1, 2, 3)").is_err());
+        assert!(Value::parse_for(FieldTag::Bool, "maybe").is_err());
+    }
+}
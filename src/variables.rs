@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use crate::{
+    address_parser::{AddressParser, AddressResolver},
+    class::{
+        class_list::ClassList,
+        resolver::{Rename, rename_in_expr},
+    },
+};
+
+/// Named value shared by every class address, defined by its own expression
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Variable {
+    pub name: String,
+    pub expr: String,
+    /// from last `VariableTable::evaluate`
+    #[serde(skip)]
+    pub value: Option<isize>,
+    #[serde(skip)]
+    pub error: Option<String>,
+}
+
+impl Variable {
+    pub fn new(name: impl Into<String>, expr: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            expr: expr.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Project globals, evaluated in order so later can use earlier ones
+#[derive(Debug, Default)]
+pub struct VariableTable {
+    variables: Vec<Variable>,
+}
+
+impl VariableTable {
+    pub fn new(variables: Vec<Variable>) -> Self {
+        Self { variables }
+    }
+
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+
+    pub fn variables_mut(&mut self) -> &mut Vec<Variable> {
+        &mut self.variables
+    }
+
+    /// Name for a new variable not taken yet
+    pub fn unused_name(&self) -> String {
+        (0..)
+            .map(|i| format!("var{i}"))
+            .find(|n| self.variables.iter().all(|v| &v.name != n))
+            .unwrap()
+    }
+
+    pub fn evaluate(&mut self, resolver: &dyn AddressResolver) {
+        let mut env = HashMap::new();
+        for v in &mut self.variables {
+            // a variable expression can have its own temporaries, only its value leak
+            let result = match is_variable_name(&v.name) {
+                true => AddressParser::new(&v.expr)
+                    .parse()
+                    .and_then(|e| e.eval(&mut env.clone(), resolver)),
+                false => Err(eyre::eyre!(
+                    "{}'{}'",
+                    obfstr!("Invalid variable name "),
+                    v.name
+                )),
+            };
+            match result {
+                Ok(value) => {
+                    env.insert(v.name.clone(), value);
+                    v.value = Some(value);
+                    v.error = None;
+                }
+                Err(e) => {
+                    v.value = None;
+                    v.error = Some(format!("{e}"));
+                }
+            }
+        }
+    }
+
+    pub fn rename(&mut self, classes: &ClassList, rename: &Rename) {
+        for v in &mut self.variables {
+            if let Some(expr) = rename_in_expr(classes, &v.expr, rename) {
+                v.expr = expr;
+            }
+        }
+    }
+
+    /// Values for address evaluation, failed variables left out
+    pub fn env(&self) -> HashMap<String, isize> {
+        self.variables
+            .iter()
+            .filter_map(|v| Some((v.name.clone(), v.value?)))
+            .collect()
+    }
+}
+
+/// Same rule as lexer symbol, `let` is taken
+fn is_variable_name(name: &str) -> bool {
+    name != "let"
+        && name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::NullMemoryReader;
+
+    #[test]
+    fn test_variable_table() {
+        let mut table = VariableTable::new(vec![
+            Variable::new("base", "sample.dll + 0x100"),
+            Variable::new("entry", "tmp = base * 2; tmp + 8"),
+            Variable::new("bad", "[base]"),
+            Variable::new("1st", "1"),
+            Variable::new("after_bad", "bad + 1"),
+        ]);
+        table.evaluate(&NullMemoryReader);
+
+        let values = table
+            .variables()
+            .iter()
+            .map(|v| v.value)
+            .collect::<Vec<_>>();
+        assert_eq!(values, [Some(0x1100), Some(0x2208), None, None, None]);
+        assert!(table.variables()[2].error.is_some());
+
+        let env = table.env();
+        assert_eq!(env.len(), 2);
+        // temporaries stay inside their expression
+        assert!(!env.contains_key("tmp"));
+
+        // class address see globals, its own assignments stay local
+        let eval = |src: &str| {
+            AddressParser::new(src)
+                .parse()?
+                .eval(&mut env.clone(), &NullMemoryReader)
+        };
+        assert_eq!(eval("local = base + 0x20; local * 2").unwrap(), 0x2240);
+        assert_eq!(eval("let x = entry; x - 8").unwrap(), 0x2200);
+        assert!(eval("local").is_err());
+
+        assert_eq!(table.unused_name(), "var0");
+        table.variables_mut().push(Variable::new("var0", "0"));
+        assert_eq!(table.unused_name(), "var1");
+    }
+}